@@ -107,6 +107,19 @@ async def create_val_proposal(keypair):
     t_dets = await create_validator_proposal(payer_keypair, numeration, client)
     print(t_dets)
 
+@click.command(name="create_proposal_page")
+@click.argument('page_number', type = int)
+@click.option('--keypair', default = 'keypair.json')
+async def create_val_proposal_page(page_number, keypair):
+    client_state = await client.is_connected()
+    print("Client is connected" if client_state else "Client is Disconnected")
+    payer_keypair = keypair_from_json(f"./{keypair}")
+    global_gem_pubkey, _global_gem_bump = PublicKey.find_program_address([bytes(ingl_constants.GLOBAL_GEM_KEY, 'UTF-8')], ingl_constants.INGL_PROGRAM_ID)
+    gem_info = await client.get_account_info(global_gem_pubkey)
+    numeration = GlobalGems.parse(base64.urlsafe_b64decode(gem_info['result']['value']['data'][0])).proposal_numeration
+    t_dets = await create_proposal_page(payer_keypair, numeration-1, page_number, client)
+    print(t_dets)
+
 @click.command(name="finalize_proposal")
 @click.option('--keypair', default = 'keypair.json')
 async def finalize_validator_proposal(keypair):
@@ -123,14 +136,18 @@ async def finalize_validator_proposal(keypair):
 
 @click.command(name='register_validator')
 @click.option('--keypair', default = 'keypair.json')
+@click.option('--name', default = '')
+@click.option('--website', default = '')
+@click.option('--commission', default = 0)
+@click.option('--self_bond', default = 1_000_000_000)
 @click.argument('validator_keypair')
-async def reg_validator(validator_keypair, keypair):
+async def reg_validator(validator_keypair, keypair, name, website, commission, self_bond):
     client_state = await client.is_connected()
     print("Client is connected" if client_state else "Client is Disconnected")
     payer_keypair = keypair_from_json(f"./{keypair}")
     validator_keypair = keypair_from_json(f"{validator_keypair}")
     print("Validator Key: ", validator_keypair.public_key)
-    t_dets = await register_validator_id(payer_keypair, validator_keypair.public_key, client)
+    t_dets = await register_validator_id(payer_keypair, validator_keypair, name, website, commission, self_bond, client)
     print(t_dets)
 
 @click.command(name="init_rebalance")
@@ -234,6 +251,7 @@ def find_vote_key(val_pubkey):
 entry.add_command(mint)
 entry.add_command(reg_validator)
 entry.add_command(create_val_proposal)
+entry.add_command(create_val_proposal_page)
 entry.add_command(finalize_validator_proposal)
 entry.add_command(reg_validator)
 entry.add_command(initialize_rebalancing)
@@ -13,7 +13,7 @@ InstructionEnum = Enum(
     "DelegateNFT",
     "UnDelegateNFT",
     "InitRarityImprint",
-    "RegisterValidatorId",
+    "RegisterValidatorId" / CStruct("name" / String, "website" / String, "commission" / U8, "self_bond" / U64),
     "CreateValidatorSelectionProposal",
     "VoteValidatorProposal" / CStruct("num_nfts" /U8, "validator_index"/U32),
     "FinalizeProposal",
@@ -24,12 +24,42 @@ InstructionEnum = Enum(
     "InitRebalance",
     "FinalizeRebalance",
     "InjectTestingData" / CStruct("num_nfts" / U32),
+    "AddGemsToBallot" / CStruct("num_nfts" / U8),
+    "CastBallot" / CStruct("validator_index" / U32),
+    "SetVotingDelegate" / CStruct("delegate" / Option(U8[32])),
+    "InitConfig",
+    "CreateGovernanceProposal" / CStruct("action" / GovernanceAction),
+    "VoteGovernanceProposal" / CStruct("num_nfts" / U8, "approve" / Bool),
+    "ExecuteProposal",
+    "VetoGovernanceProposal",
+    "SetPause" / CStruct("is_paused" / Bool, "num_members" / U8),
+    "WithdrawCandidacy",
+    "RefundRegistration",
+    "CreateProposalPage" / CStruct("page_number" / U32),
+    "UpdateUriRegistry" / CStruct("update" / UriRegistryUpdate),
+    "RefreshGemMetadata" / CStruct("num_gems" / U8),
+    "CancelRarityImprint",
+    "RerollRarity",
+    "FuseGems" / CStruct("class" / ClassEnum, "num_gems" / U8),
+    "SplitGem" / CStruct("classes" / Vec(ClassEnum)),
+    "SetMintSchedule" / CStruct("class_caps" / Vec(ClassCap), "allowlist_phase" / Option(MintPhase), "public_phase" / Option(MintPhase), "wallet_cap" / Option(U32), "allowlist_root" / Option(U8[32])),
+    "AllowlistMintNft" / CStruct("class" / ClassEnum, "max_count" / U32, "proof" / Vec(U8[32]), "referrer" / Option(U8[32])),
+    "RegisterReferrer",
+    "ClaimReferralRewards",
+    "MintNftWithWrappedSol" / CStruct("class" / ClassEnum, "referrer" / Option(U8[32])),
+    "QuoteRedeem",
+    "RedeemQueued",
+    "ClaimRedemption",
+    "InstantRedeem",
+    "TreasuryWithdraw" / CStruct("treasury" / TreasuryAccount, "lamports" / U64),
+    "RemoveGemsFromBallot" / CStruct("num_nfts" / U8),
+    "ReleaseValidatorBond",
     
     enum_name = "InstructionEnum",
 )
 
-def build_instruction(instruction, value = None):
+def build_instruction(instruction, value = None, referrer = None):
     if instruction == InstructionEnum.enum.MintNft():
-        return InstructionEnum.build(instruction) +  ClassEnum.build(value)
+        return InstructionEnum.build(instruction) +  ClassEnum.build(value) + Option(U8[32]).build(referrer and list(bytes(referrer)))
     else:
         return InstructionEnum.build(instruction)
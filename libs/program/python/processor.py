@@ -72,7 +72,7 @@ async def create_collection(payer_keypair: Keypair, client: AsyncClient) -> Stri
         await client.close()
         return(f"Error: {e}")
 
-async def mint_nft(payer_keypair: Keypair, mint_keypair: Keypair, mint_class: ClassEnum.enum, client: AsyncClient) -> String:
+async def mint_nft(payer_keypair: Keypair, mint_keypair: Keypair, mint_class: ClassEnum.enum, client: AsyncClient, referrer_pubkey: PublicKey = None) -> String:
     mint_authority_pubkey, _mint_authority_pubkey_bump = PublicKey.find_program_address([bytes(ingl_constants.INGL_MINT_AUTHORITY_KEY, 'UTF-8')], ingl_constants.INGL_PROGRAM_ID)
     collection_mint_pubkey, _collection_mint_pubkey_bump = PublicKey.find_program_address([bytes(ingl_constants.INGL_NFT_COLLECTION_KEY, 'UTF-8')], ingl_constants.INGL_PROGRAM_ID)
     minting_pool_pubkey, _minting_pool_pubkey_bump = PublicKey.find_program_address([bytes(ingl_constants.INGL_MINTING_POOL_KEY, 'UTF-8')], ingl_constants.INGL_PROGRAM_ID)
@@ -84,6 +84,15 @@ async def mint_nft(payer_keypair: Keypair, mint_keypair: Keypair, mint_class: Cl
     mint_edition_pda, _mint_edition_bump = PublicKey.find_program_address([b"metadata", bytes(metaplex_program_id), bytes(mint_keypair.public_key), b"edition"], metaplex_program_id)
     collection_account_pda, _collection_account_bump = PublicKey.find_program_address([b"metadata", bytes(metaplex_program_id), bytes(collection_mint_pubkey)], metaplex_program_id)
     gem_account_pubkey, _gem_account_bump = PublicKey.find_program_address([bytes(ingl_constants.GEM_ACCOUNT_CONST, 'UTF-8'), bytes(mint_keypair.public_key)], ingl_constants.INGL_PROGRAM_ID)
+    config_pubkey, _config_bump = PublicKey.find_program_address([bytes(ingl_constants.INGL_CONFIG_KEY, 'UTF-8')], ingl_constants.INGL_PROGRAM_ID)
+    uri_registry_pubkey, _uri_registry_bump = PublicKey.find_program_address([bytes(ingl_constants.METADATA_URI_REGISTRY_KEY, 'UTF-8')], ingl_constants.INGL_PROGRAM_ID)
+    mint_schedule_pubkey, _mint_schedule_bump = PublicKey.find_program_address([bytes(ingl_constants.MINT_SCHEDULE_KEY, 'UTF-8')], ingl_constants.INGL_PROGRAM_ID)
+    mint_wallet_record_pubkey, _mint_wallet_record_bump = PublicKey.find_program_address([bytes(ingl_constants.MINT_WALLET_RECORD_KEY, 'UTF-8'), bytes(payer_keypair.public_key)], ingl_constants.INGL_PROGRAM_ID)
+    # Without a referrer, the referrer account isn't read and any account can take its place.
+    if referrer_pubkey:
+        referrer_account_pubkey, _referrer_account_bump = PublicKey.find_program_address([bytes(ingl_constants.REFERRER_KEY, 'UTF-8'), bytes(referrer_pubkey)], ingl_constants.INGL_PROGRAM_ID)
+    else:
+        referrer_account_pubkey = system_program.SYS_PROGRAM_ID
 
     payer_account_meta = AccountMeta(payer_keypair.public_key, True, True)
     mint_account_meta = AccountMeta(mint_keypair.public_key, True, True)
@@ -102,6 +111,11 @@ async def mint_nft(payer_keypair: Keypair, mint_keypair: Keypair, mint_class: Cl
     mint_edition_meta = AccountMeta(mint_edition_pda, False, True)
     collection_mint_meta = AccountMeta(collection_mint_pubkey, False, True)
     collection_account_meta = AccountMeta(collection_account_pda, False, True)
+    config_meta = AccountMeta(config_pubkey, False, False)
+    uri_registry_meta = AccountMeta(uri_registry_pubkey, False, False)
+    mint_schedule_meta = AccountMeta(mint_schedule_pubkey, False, True)
+    mint_wallet_record_meta = AccountMeta(mint_wallet_record_pubkey, False, True)
+    referrer_account_meta = AccountMeta(referrer_account_pubkey, False, referrer_pubkey is not None)
 
 
     accounts = [
@@ -120,6 +134,11 @@ async def mint_nft(payer_keypair: Keypair, mint_keypair: Keypair, mint_class: Cl
         mint_edition_meta,
         collection_mint_meta,
         collection_account_meta,
+        config_meta,
+        uri_registry_meta,
+        mint_schedule_meta,
+        mint_wallet_record_meta,
+        referrer_account_meta,
 
         system_program_meta,
         spl_program_meta,
@@ -133,7 +152,7 @@ async def mint_nft(payer_keypair: Keypair, mint_keypair: Keypair, mint_class: Cl
         metadata_program_id,
     ]
     # print(accounts)
-    instruction_data = build_instruction(InstructionEnum.enum.MintNft(), mint_class)
+    instruction_data = build_instruction(InstructionEnum.enum.MintNft(), mint_class, referrer_pubkey)
     transaction = Transaction()
     transaction.add(TransactionInstruction(accounts, ingl_constants.INGL_PROGRAM_ID, instruction_data))
     try: 
@@ -236,19 +255,30 @@ async def deallocate_sol(payer_keypair: Keypair, mint_pubkey: PublicKey, client:
 
 
 
-async def register_validator_id(payer_keypair: Keypair, validator_pubkey: PublicKey, client: AsyncClient) -> String:
+async def register_validator_id(payer_keypair: Keypair, validator_keypair: Keypair, name: str, website: str, commission: int, self_bond: int, client: AsyncClient) -> String:
+    validator_pubkey = validator_keypair.public_key
     mint_authority_pubkey, _mint_authority_pubkey_bump = PublicKey.find_program_address([bytes(ingl_constants.INGL_MINT_AUTHORITY_KEY, 'UTF-8')], ingl_constants.INGL_PROGRAM_ID)
     global_gem_pubkey, _global_gem_bump = PublicKey.find_program_address([bytes(ingl_constants.GLOBAL_GEM_KEY, 'UTF-8')], ingl_constants.INGL_PROGRAM_ID)
 
     dupkey, dup_bump = PublicKey.find_program_address([ingl_constants.DUPKEYBYTES, bytes(validator_pubkey)], ingl_constants.INGL_PROGRAM_ID);
+    candidate_pubkey, _candidate_bump = PublicKey.find_program_address([bytes(ingl_constants.VALIDATOR_CANDIDATE_KEY, 'UTF-8'), bytes(validator_pubkey)], ingl_constants.INGL_PROGRAM_ID)
+    bond_pubkey, _bond_bump = PublicKey.find_program_address([bytes(ingl_constants.VALIDATOR_BOND_KEY, 'UTF-8'), bytes(validator_pubkey)], ingl_constants.INGL_PROGRAM_ID)
+    gem_info = await client.get_account_info(global_gem_pubkey)
+    candidate_count = GlobalGems.parse(base64.urlsafe_b64decode(gem_info['result']['value']['data'][0])).candidate_count
+    candidate_index_pubkey, _candidate_index_bump = PublicKey.find_program_address([bytes(ingl_constants.CANDIDATE_INDEX_KEY, 'UTF-8'), candidate_count.to_bytes(4,"big")], ingl_constants.INGL_PROGRAM_ID)
+    treasury_ledger_pubkey, _treasury_ledger_bump = PublicKey.find_program_address([bytes(ingl_constants.TREASURY_LEDGER_KEY, 'UTF-8')], ingl_constants.INGL_PROGRAM_ID)
 
     
     payer_account_meta = AccountMeta(payer_keypair.public_key, True, True)
     global_gem_meta = AccountMeta(global_gem_pubkey, False, True)
     system_program_meta = AccountMeta(system_program.SYS_PROGRAM_ID, False, False)
     mint_authority_meta = AccountMeta(mint_authority_pubkey, False, True)
-    validator_meta = AccountMeta(validator_pubkey, False, False)
+    validator_meta = AccountMeta(validator_pubkey, True, True)
     dup_meta = AccountMeta(dupkey, False, True)
+    candidate_meta = AccountMeta(candidate_pubkey, False, True)
+    bond_meta = AccountMeta(bond_pubkey, False, True)
+    candidate_index_meta = AccountMeta(candidate_index_pubkey, False, True)
+    treasury_ledger_meta = AccountMeta(treasury_ledger_pubkey, False, True)
 
     accounts = [
         payer_account_meta,
@@ -256,16 +286,23 @@ async def register_validator_id(payer_keypair: Keypair, validator_pubkey: Public
         mint_authority_meta,
         validator_meta,
         dup_meta,
+        candidate_meta,
+        bond_meta,
+        candidate_index_meta,
+        treasury_ledger_meta,
 
         system_program_meta,
         system_program_meta,
+        system_program_meta,
+        system_program_meta,
+        system_program_meta,
     ]
 
-    instruction_data = build_instruction(InstructionEnum.enum.RegisterValidatorId())
+    instruction_data = build_instruction(InstructionEnum.enum.RegisterValidatorId(name = name, website = website, commission = commission, self_bond = self_bond))
     transaction = Transaction()
     transaction.add(TransactionInstruction(accounts, ingl_constants.INGL_PROGRAM_ID, instruction_data))
     try:
-        t_dets = await client.send_transaction(transaction, payer_keypair)
+        t_dets = await client.send_transaction(transaction, payer_keypair, validator_keypair)
         await client.confirm_transaction(tx_sig = t_dets['result'], commitment= "finalized", sleep_seconds = 0.4, last_valid_block_height = None)
         await client.close()
         return f"Transaction Id: [link=https://explorer.solana.com/tx/{t_dets['result']+rpc_url.get_explorer_suffix()}]{t_dets['result']}[/link]"
@@ -304,17 +341,60 @@ async def create_validator_proposal(payer_keypair: Keypair, proposal_numeration:
         return(f"Error: {e}")
 
 
+async def create_proposal_page(payer_keypair: Keypair, proposal_numeration: int, page_number: int, client: AsyncClient) -> String:
+    proposal_pubkey, _proposal_bump = PublicKey.find_program_address([bytes(ingl_constants.PROPOSAL_KEY, 'UTF-8'), proposal_numeration.to_bytes(4,"big")], ingl_constants.INGL_PROGRAM_ID)
+    page_pubkey, _page_bump = PublicKey.find_program_address([bytes(ingl_constants.PROPOSAL_PAGE_KEY, 'UTF-8'), bytes(proposal_pubkey), page_number.to_bytes(4,"big")], ingl_constants.INGL_PROGRAM_ID)
+
+    proposal_info = await client.get_account_info(proposal_pubkey)
+    candidate_count = ValidatorProposal.parse(base64.urlsafe_b64decode(proposal_info['result']['value']['data'][0])).candidate_count
+
+    payer_account_meta = AccountMeta(payer_keypair.public_key, True, True)
+    proposal_meta = AccountMeta(proposal_pubkey, False, False)
+    page_meta = AccountMeta(page_pubkey, False, True)
+    system_program_meta = AccountMeta(system_program.SYS_PROGRAM_ID, False, False)
+
+    accounts = [
+        payer_account_meta,
+        proposal_meta,
+        page_meta,
+        system_program_meta,
+    ]
+
+    first_index = page_number * ingl_constants.CANDIDATES_PER_PAGE
+    for index in range(first_index, min(candidate_count, first_index + ingl_constants.CANDIDATES_PER_PAGE)):
+        index_pubkey, _index_bump = PublicKey.find_program_address([bytes(ingl_constants.CANDIDATE_INDEX_KEY, 'UTF-8'), index.to_bytes(4,"big")], ingl_constants.INGL_PROGRAM_ID)
+        index_info = await client.get_account_info(index_pubkey)
+        validator_id = CandidateIndex.parse(base64.urlsafe_b64decode(index_info['result']['value']['data'][0])).validator_id
+        candidate_pubkey, _candidate_bump = PublicKey.find_program_address([bytes(ingl_constants.VALIDATOR_CANDIDATE_KEY, 'UTF-8'), bytes(validator_id)], ingl_constants.INGL_PROGRAM_ID)
+        accounts.append(AccountMeta(candidate_pubkey, False, False))
+
+    instruction_data = build_instruction(InstructionEnum.enum.CreateProposalPage(page_number = page_number))
+    transaction = Transaction()
+    transaction.add(TransactionInstruction(accounts, ingl_constants.INGL_PROGRAM_ID, instruction_data))
+    try:
+        t_dets = await client.send_transaction(transaction, payer_keypair)
+        await client.confirm_transaction(tx_sig = t_dets['result'], commitment= "finalized", sleep_seconds = 0.4, last_valid_block_height = None)
+        await client.close()
+        return f"Transaction Id: [link=https://explorer.solana.com/tx/{t_dets['result']+rpc_url.get_explorer_suffix()}]{t_dets['result']}[/link]"
+    except Exception as e:
+        await client.close()
+        return(f"Error: {e}")
+
+
 async def vote_validator_proposal(payer_keypair: Keypair, proposal_numeration: int, mint_pubkeys: List[PublicKey], val_index:int, client: AsyncClient) -> String:
     proposal_pubkey, _proposal_bump = PublicKey.find_program_address([bytes(ingl_constants.PROPOSAL_KEY, 'UTF-8'), proposal_numeration.to_bytes(4,"big")], ingl_constants.INGL_PROGRAM_ID)
 
 
     
+    page_pubkey, _page_bump = PublicKey.find_program_address([bytes(ingl_constants.PROPOSAL_PAGE_KEY, 'UTF-8'), bytes(proposal_pubkey), (val_index // ingl_constants.CANDIDATES_PER_PAGE).to_bytes(4,"big")], ingl_constants.INGL_PROGRAM_ID)
     payer_account_meta = AccountMeta(payer_keypair.public_key, True, True)
     proposal_meta = AccountMeta(proposal_pubkey, False, True)
+    page_meta = AccountMeta(page_pubkey, False, True)
 
     accounts = [
         payer_account_meta,
-        proposal_meta
+        proposal_meta,
+        page_meta,
         ]
 
     for mint in mint_pubkeys:
@@ -343,6 +423,19 @@ async def finalize_proposal(payer_keypair: Keypair, proposal_numeration: int, cl
     global_gem_pubkey, _global_gem_bump = PublicKey.find_program_address([bytes(ingl_constants.GLOBAL_GEM_KEY, 'UTF-8')], ingl_constants.INGL_PROGRAM_ID)
     proposal_pubkey, _proposal_bump = PublicKey.find_program_address([bytes(ingl_constants.PROPOSAL_KEY, 'UTF-8'), proposal_numeration.to_bytes(4,"big")], ingl_constants.INGL_PROGRAM_ID)
 
+    gem_info = await client.get_account_info(global_gem_pubkey)
+    last_index = GlobalGems.parse(base64.urlsafe_b64decode(gem_info['result']['value']['data'][0])).candidate_count - 1
+    proposal_info = await client.get_account_info(proposal_pubkey)
+    winner = ValidatorProposal.parse(base64.urlsafe_b64decode(proposal_info['result']['value']['data'][0])).leader
+    winner_candidate_pubkey, _winner_candidate_bump = PublicKey.find_program_address([bytes(ingl_constants.VALIDATOR_CANDIDATE_KEY, 'UTF-8'), bytes(winner)], ingl_constants.INGL_PROGRAM_ID)
+    candidate_info = await client.get_account_info(winner_candidate_pubkey)
+    winner_index = ValidatorCandidate.parse(base64.urlsafe_b64decode(candidate_info['result']['value']['data'][0])).index
+    winner_index_pubkey, _winner_index_bump = PublicKey.find_program_address([bytes(ingl_constants.CANDIDATE_INDEX_KEY, 'UTF-8'), winner_index.to_bytes(4,"big")], ingl_constants.INGL_PROGRAM_ID)
+    last_index_pubkey, _last_index_bump = PublicKey.find_program_address([bytes(ingl_constants.CANDIDATE_INDEX_KEY, 'UTF-8'), last_index.to_bytes(4,"big")], ingl_constants.INGL_PROGRAM_ID)
+    index_info = await client.get_account_info(last_index_pubkey)
+    last_validator_id = CandidateIndex.parse(base64.urlsafe_b64decode(index_info['result']['value']['data'][0])).validator_id
+    last_candidate_pubkey, _last_candidate_bump = PublicKey.find_program_address([bytes(ingl_constants.VALIDATOR_CANDIDATE_KEY, 'UTF-8'), bytes(last_validator_id)], ingl_constants.INGL_PROGRAM_ID)
+
     global_gem_meta = AccountMeta(global_gem_pubkey, False, True)
     payer_account_meta = AccountMeta(payer_keypair.public_key, True, True)
     proposal_meta = AccountMeta(proposal_pubkey, False, True)
@@ -351,6 +444,10 @@ async def finalize_proposal(payer_keypair: Keypair, proposal_numeration: int, cl
         payer_account_meta,
         proposal_meta,
         global_gem_meta,
+        AccountMeta(winner_candidate_pubkey, False, True),
+        AccountMeta(winner_index_pubkey, False, True),
+        AccountMeta(last_index_pubkey, False, True),
+        AccountMeta(last_candidate_pubkey, False, True),
     ]
 
     instruction_data = build_instruction(InstructionEnum.enum.FinalizeProposal())
@@ -370,6 +467,7 @@ async def delegate_nft(payer_keypair: Keypair, mint_pubkey: PublicKey, expected_
     global_gem_pubkey, _global_gem_bump = PublicKey.find_program_address([bytes(ingl_constants.GLOBAL_GEM_KEY, 'UTF-8')], ingl_constants.INGL_PROGRAM_ID)
     mint_associated_account_pubkey = assoc_instructions.get_associated_token_address(payer_keypair.public_key, mint_pubkey)
     expected_vote_data_pubkey, _expected_vote_data_bump = PublicKey.find_program_address([bytes(ingl_constants.VOTE_DATA_ACCOUNT_KEY, 'UTF-8'), bytes(expected_vote_pubkey)], ingl_constants.INGL_PROGRAM_ID)
+    config_pubkey, _config_bump = PublicKey.find_program_address([bytes(ingl_constants.INGL_CONFIG_KEY, 'UTF-8')], ingl_constants.INGL_PROGRAM_ID)
 
     
     payer_account_meta = AccountMeta(payer_keypair.public_key, True, True)
@@ -382,6 +480,7 @@ async def delegate_nft(payer_keypair: Keypair, mint_pubkey: PublicKey, expected_
     vote_account_meta = AccountMeta(expected_vote_pubkey, False, True)
     sysvar_clock_meta = AccountMeta(solana.sysvar.SYSVAR_CLOCK_PUBKEY, False, False)
     stake_config_program_meta = AccountMeta(ingl_constants.STAKE_CONFIG_PROGRAM_ID, False, False)
+    config_meta = AccountMeta(config_pubkey, False, False)
 
 
     accounts = [
@@ -394,6 +493,7 @@ async def delegate_nft(payer_keypair: Keypair, mint_pubkey: PublicKey, expected_
         global_gem_meta,
         sysvar_clock_meta,
         stake_config_program_meta,
+        config_meta,
     ]
 
     # print(accounts)
@@ -634,6 +734,8 @@ async def finalize_rebalance(payer_keypair: Keypair, vote_account_pubkey: Public
     t_stake_key, _t_stake_bump = PublicKey.find_program_address([bytes(ingl_constants.T_STAKE_ACCOUNT_KEY, 'UTF-8'), bytes(expected_vote_pubkey)], ingl_constants.INGL_PROGRAM_ID)
     t_withdraw_key, _t_withdraw_bump = PublicKey.find_program_address([bytes(ingl_constants.T_WITHDRAW_KEY, 'UTF-8'), bytes(expected_vote_pubkey)], ingl_constants.INGL_PROGRAM_ID)
     pd_pool_pubkey, _pd_pool_bump = PublicKey.find_program_address([bytes(ingl_constants.PD_POOL_KEY, 'UTF-8')], ingl_constants.INGL_PROGRAM_ID)
+    global_gem_pubkey, _global_gem_bump = PublicKey.find_program_address([bytes(ingl_constants.GLOBAL_GEM_KEY, 'UTF-8')], ingl_constants.INGL_PROGRAM_ID)
+    liquidity_reserve_pubkey, _liquidity_reserve_bump = PublicKey.find_program_address([bytes(ingl_constants.LIQUIDITY_RESERVE_KEY, 'UTF-8')], ingl_constants.INGL_PROGRAM_ID)
     data = await client.get_account_info(expected_vote_data_pubkey)
     validator_id = PublicKey(InglVoteAccountData.parse(base64.urlsafe_b64decode(data['result']['value']['data'][0])).validator_id)
     print(f"Validator_Id: {validator_id}")
@@ -650,6 +752,8 @@ async def finalize_rebalance(payer_keypair: Keypair, vote_account_pubkey: Public
     pd_pool_meta = AccountMeta(pd_pool_pubkey, False, True)
     stake_program_meta = AccountMeta(ingl_constants.STAKE_PROGRAM_ID, False, False)
     sysvar_stake_history_meta = AccountMeta(solana.sysvar.SYSVAR_STAKE_HISTORY_PUBKEY, False, False)
+    global_gem_meta = AccountMeta(global_gem_pubkey, False, True)
+    liquidity_reserve_meta = AccountMeta(liquidity_reserve_pubkey, False, True)
 
     accounts = [
         payer_account_meta,
@@ -663,6 +767,8 @@ async def finalize_rebalance(payer_keypair: Keypair, vote_account_pubkey: Public
         stake_account_meta,
         t_withdraw_meta,
         sysvar_stake_history_meta,
+        global_gem_meta,
+        liquidity_reserve_meta,
         
         
         stake_program_meta,
@@ -689,9 +795,11 @@ async def process_rewards(payer_keypair: Keypair, vote_account_id: PublicKey, cl
     validator_id = PublicKey(InglVoteAccountData.parse(base64.urlsafe_b64decode(data['result']['value']['data'][0])).validator_id)
     authorized_withdrawer_key, _authorized_withdrawer_bump = PublicKey.find_program_address([bytes(ingl_constants.AUTHORIZED_WITHDRAWER_KEY, 'UTF-8')], ingl_constants.INGL_PROGRAM_ID)
     treasury_key, _treasury_bump = PublicKey.find_program_address([bytes(ingl_constants.TREASURY_ACCOUNT_KEY, 'UTF-8')], ingl_constants.INGL_PROGRAM_ID)
+    treasury_ledger_pubkey, _treasury_ledger_bump = PublicKey.find_program_address([bytes(ingl_constants.TREASURY_LEDGER_KEY, 'UTF-8')], ingl_constants.INGL_PROGRAM_ID)
     print(f"Validator_Id: {validator_id}")
 
     treasury_meta = AccountMeta(treasury_key, False, True)
+    treasury_ledger_meta = AccountMeta(treasury_ledger_pubkey, False, True)
     payer_account_meta = AccountMeta(payer_keypair.public_key, True, True)
     validator_meta = AccountMeta(validator_id, False, True)
     vote_account_meta = AccountMeta(vote_account_id, False, True)
@@ -710,6 +818,7 @@ async def process_rewards(payer_keypair: Keypair, vote_account_id: PublicKey, cl
         authorized_withdrawer_meta,
         mint_authority_meta,
         treasury_meta,
+        treasury_ledger_meta,
         
         
         vote_program_meta,
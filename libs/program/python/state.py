@@ -26,11 +26,30 @@ class Constants:
     T_STAKE_ACCOUNT_KEY = "Temporary_stake_account_key"
     T_WITHDRAW_KEY = "Temporary_withdraw"
     DUPKEYBYTES = b"dupkey"
+    VOTE_BALLOT_KEY = "vote_ballot"
+    INGL_CONFIG_KEY = "ingl_config"
+    GOVERNANCE_PROPOSAL_KEY = "governance_proposal"
+    GOVERNANCE_VOTE_KEY = "governance_vote"
+    COUNCIL_VETO_KEY = "council_veto"
+    VALIDATOR_CANDIDATE_KEY = "validator_candidate"
+    VALIDATOR_BOND_KEY = "validator_bond"
+    CANDIDATE_INDEX_KEY = "candidate_index"
+    PROPOSAL_PAGE_KEY = "proposal_page"
+    RARITY_TABLE_KEY = "rarity_table"
+    METADATA_URI_REGISTRY_KEY = "metadata_uri_registry"
+    MINT_SCHEDULE_KEY = "mint_schedule"
+    MINT_WALLET_RECORD_KEY = "mint_wallet_record"
+    REFERRER_KEY = "referrer"
+    WRAPPED_SOL_ESCROW_KEY = "wrapped_sol_escrow"
+    LIQUIDITY_RESERVE_KEY = "liquidity_reserve"
+    TREASURY_LEDGER_KEY = "treasury_ledger"
+    INGL_TREASURY_ACCOUNT_KEY = "ingl_treasury_account_key"
 
     VALIDATOR_ID_SHARE = 15
     TREASURY_SHARE = 13
     TEAM_SHARE = 12
     NFTS_SHARE = 60
+    CANDIDATES_PER_PAGE = 20
 
 ClassEnum = Enum(
     "Ruby",
@@ -39,10 +58,87 @@ ClassEnum = Enum(
     "Emerald",
     "Serendibite",
     "Benitoite",
+    "Custom" / CStruct("sol" / U32),
 
     enum_name = "ClassEnum",
 )
 
+RarityEnum = Enum(
+    "Common",
+    "Uncommon",
+    "Rare",
+    "Exalted",
+    "Mythic",
+
+    enum_name = "RarityEnum",
+)
+
+RedeemFeeCurve = Enum(
+    "Linear",
+    "QuarterCircle",
+    "Step" / CStruct("steps" / U8),
+
+    enum_name = "RedeemFeeCurve",
+)
+
+CustomClass = CStruct(
+    "sol" / U32,
+    "name" / String,
+)
+
+ConfigUpdate = Enum(
+    "GovernanceVotingPeriod" / CStruct("value" / U32),
+    "GovernanceTimelock" / CStruct("value" / U32),
+    "GovernanceQuorum" / CStruct("value" / U32),
+    "Authority" / CStruct("value" / U8[32]),
+    "CouncilVetoThreshold" / CStruct("value" / U32),
+    "CouncilPauseThreshold" / CStruct("value" / U32),
+    "RegistrationRefundPercentage" / CStruct("value" / U8),
+    "OracleProgram" / CStruct("value" / U8[32]),
+    "RarityFeeds" / CStruct("value" / Vec(U8[32])),
+    "MaxFeedStaleness" / CStruct("value" / U32),
+    "RarityBoosts" / CStruct("value" / U8[5]),
+    "RerollFee" / CStruct("value" / U64),
+    "MaxRarityRerolls" / CStruct("value" / U8),
+    "AddCustomClass" / CStruct("value" / CustomClass),
+    "RemoveCustomClass" / CStruct("value" / U32),
+    "ReferralShare" / CStruct("value" / U8),
+    "MaxReferralReward" / CStruct("value" / U64),
+    "RedeemFeeCurve" / CStruct("value" / RedeemFeeCurve),
+    "RedeemFeePercent" / CStruct("value" / U8),
+    "RedeemFeeDuration" / CStruct("value" / U32),
+    "LiquidityReserveShare" / CStruct("value" / U8),
+    "InstantExitDiscount" / CStruct("value" / U8),
+    "Paused" / CStruct("value" / Bool),
+
+    enum_name = "ConfigUpdate",
+)
+
+TreasuryAccount = Enum(
+    "InglTreasury",
+    "RewardsTreasury",
+    "MintAuthority",
+
+    enum_name = "TreasuryAccount",
+)
+
+UriRegistryUpdate = Enum(
+    "BaseUri" / CStruct("base_uri" / Option(String)),
+    "Entry" / CStruct("class" / ClassEnum, "rarity" / Option(RarityEnum), "uri" / Option(String)),
+
+    enum_name = "UriRegistryUpdate",
+)
+
+GovernanceAction = Enum(
+    "UpdateConfig" / CStruct("update" / ConfigUpdate),
+    "TreasurySpend" / CStruct("treasury" / TreasuryAccount, "recipient" / U8[32], "lamports" / U64),
+    "UpdateUriRegistry" / CStruct("update" / UriRegistryUpdate),
+    "RotateValidator" / CStruct("vote_account" / U8[32], "new_validator_id" / U8[32]),
+    "SetRarityTable" / CStruct("class" / ClassEnum, "weights" / U16[5]),
+
+    enum_name = "GovernanceAction",
+)
+
 def keypair_from_json(filepath):
     keypair = Keypair.from_secret_key(json.load(open(filepath)))
     return keypair
@@ -57,7 +153,11 @@ GlobalGems = CStruct(
     "is_proposal_ongoing" / Bool,
     "proposal_numeration" / U32,
     "pending_delegation_total" / U64,
-    "validator_list" / Vec(U8[32])
+    "candidate_count" / U32,
+    "redemption_queue_length" / U32,
+    "queued_redemptions" / U32,
+    "queued_redemption_lamports" / U64,
+    "reserve_advanced_total" / U64,
 )
 
 VoteRewards = CStruct(
@@ -65,6 +165,7 @@ VoteRewards = CStruct(
     "epoch_number" / U64,
     "total_reward" / U64,
     "total_stake" / U64,
+    "nft_reward" / U64,
 )
 
 InglVoteAccountData = CStruct(
@@ -77,19 +178,159 @@ InglVoteAccountData = CStruct(
     "last_total_staked" / U64,
     "is_t_stake_initialized" / Bool,
     "pending_delegation_total" / U64,
+    "boosted_delegated" / U64,
     "vote_rewards" / Vec(VoteRewards),
+    "last_rebalance_epoch" / U64,
+    "reserve_advances" / U64,
+    "unwinding_reserve_advances" / U64,
 )
 
 
 ValidatorProposal = CStruct(
     "validation_phrase" / U32,
-    "validator_ids" / Vec(U8[32]),
+    "candidate_count" / U32,
     "date_created" / U32,
     "date_finalized" / Option(U32),
-    "votes" / Vec(U32),
+    "leader" / Option(U8[32]),
+    "leader_votes" / U32,
     "winner" / Option(U8[32]),
 )
 
+ProposalPage = CStruct(
+    "validation_phrase" / U32,
+    "proposal_id" / U8[32],
+    "page_number" / U32,
+    "validator_ids" / Vec(U8[32]),
+    "votes" / Vec(U32),
+)
+
+CandidateIndex = CStruct(
+    "validation_phrase" / U32,
+    "validator_id" / U8[32],
+)
+
+RarityTable = CStruct(
+    "validation_phrase" / U32,
+    "class" / ClassEnum,
+    "weights" / U16[5],
+)
+
+UriOverride = CStruct(
+    "class" / ClassEnum,
+    "rarity" / Option(RarityEnum),
+    "uri" / String,
+)
+
+InglConfig = CStruct(
+    "validation_phrase" / U32,
+    "authority" / U8[32],
+    "governance_voting_period" / U32,
+    "governance_timelock" / U32,
+    "governance_quorum" / U32,
+    "governance_proposal_numeration" / U32,
+    "council_veto_threshold" / U32,
+    "council_pause_threshold" / U32,
+    "is_paused" / Bool,
+    "registration_refund_percentage" / U8,
+    "oracle_program_id" / U8[32],
+    "rarity_feeds" / Vec(U8[32]),
+    "max_feed_staleness" / U32,
+    "rarity_boosts" / U8[5],
+    "reroll_fee" / U64,
+    "max_rarity_rerolls" / U8,
+    "custom_classes" / Vec(CustomClass),
+    "referral_share" / U8,
+    "max_referral_reward" / U64,
+    "redeem_fee_curve" / RedeemFeeCurve,
+    "redeem_fee_percent" / U8,
+    "redeem_fee_duration" / U32,
+    "liquidity_reserve_share" / U8,
+    "instant_exit_discount" / U8,
+)
+
+MetadataUriRegistry = CStruct(
+    "validation_phrase" / U32,
+    "base_uri" / Option(String),
+    "entries" / Vec(UriOverride),
+)
+
+MintPhase = CStruct(
+    "start" / U32,
+    "end" / U32,
+)
+
+ClassCap = CStruct(
+    "class" / ClassEnum,
+    "max_supply" / U32,
+)
+
+ClassSupply = CStruct(
+    "class" / ClassEnum,
+    "max_supply" / U32,
+    "minted" / U32,
+)
+
+MintSchedule = CStruct(
+    "validation_phrase" / U32,
+    "class_supplies" / Vec(ClassSupply),
+    "allowlist_phase" / Option(MintPhase),
+    "public_phase" / Option(MintPhase),
+    "wallet_cap" / Option(U32),
+    "allowlist_root" / Option(U8[32]),
+)
+
+MintWalletRecord = CStruct(
+    "validation_phrase" / U32,
+    "minted" / U32,
+    "allowlist_minted" / U32,
+)
+
+Referrer = CStruct(
+    "validation_phrase" / U32,
+    "wallet" / U8[32],
+    "referred_gems" / U32,
+    "referred_volume" / U64,
+    "accrued_rewards" / U64,
+    "claimed_rewards" / U64,
+)
+
+TreasuryLedger = CStruct(
+    "validation_phrase" / U32,
+    "redeem_fees" / U64,
+    "reward_share" / U64,
+    "team_share" / U64,
+    "registration_fees" / U64,
+    "reroll_fees" / U64,
+    "ingl_treasury_spent" / U64,
+    "rewards_treasury_spent" / U64,
+    "mint_authority_spent" / U64,
+)
+
+ValidatorCandidate = CStruct(
+    "validation_phrase" / U32,
+    "validator_id" / U8[32],
+    "name" / String,
+    "website" / String,
+    "commission" / U8,
+    "self_bond" / U64,
+    "date_registered" / U32,
+    "index" / Option(U32),
+)
+
+GovernanceProposal = CStruct(
+    "validation_phrase" / U32,
+    "numeration" / U32,
+    "proposer" / U8[32],
+    "action" / GovernanceAction,
+    "date_created" / U32,
+    "voting_end" / U32,
+    "executable_date" / U32,
+    "votes_for" / U32,
+    "votes_against" / U32,
+    "council_vetoes" / U32,
+    "date_executed" / Option(U32),
+)
+
 def private_key_from_json(filepath):
     return base58.b58encode(keypair_from_json(filepath).secret_key).decode()
 
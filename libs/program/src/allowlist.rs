@@ -0,0 +1,119 @@
+use crate::state::Class;
+use solana_program::{keccak::hashv, pubkey::Pubkey};
+#[cfg(not(target_arch = "bpf"))]
+use std::str::FromStr;
+
+// Leaves and inner nodes are hashed with different prefixes so that an inner node can't be passed off as a leaf.
+const LEAF_PREFIX: &[u8] = &[0];
+const NODE_PREFIX: &[u8] = &[1];
+
+/// Leaf of the allowlist tree, letting `wallet` mint up to `max_count` gems of `class` during the allowlist phase.
+pub fn allowlist_leaf(wallet: &Pubkey, class: Class, max_count: u32) -> [u8; 32] {
+    hashv(&[LEAF_PREFIX, wallet.as_ref(), &class.get_seed(), &max_count.to_le_bytes()]).to_bytes()
+}
+
+/// Children are hashed in sorted order, so proofs don't need to say which side each sibling is on.
+fn hash_pair(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
+    if a <= b {
+        hashv(&[NODE_PREFIX, a, b]).to_bytes()
+    } else {
+        hashv(&[NODE_PREFIX, b, a]).to_bytes()
+    }
+}
+
+pub fn verify_allowlist_proof(root: &[u8; 32], leaf: [u8; 32], proof: &[[u8; 32]]) -> bool {
+    proof.iter().fold(leaf, |node, sibling| hash_pair(&node, sibling)) == *root
+}
+
+#[cfg(not(target_arch = "bpf"))]
+pub struct AllowlistEntry {
+    pub wallet: Pubkey,
+    pub class: Class,
+    pub max_count: u32,
+    pub proof: Vec<[u8; 32]>,
+}
+
+/// Off chain helper building the allowlist tree from `wallet,class,max_count` lines, e.g. `<pubkey>,Ruby,2`
+/// or `<pubkey>,Custom1000,1` for a custom class of 1000 SOL. A header line starting with "wallet" is skipped.
+/// Returns the root to set in the mint schedule and each entry with the proof its wallet mints with.
+#[cfg(not(target_arch = "bpf"))]
+pub fn build_allowlist_from_csv(csv: &str) -> Result<([u8; 32], Vec<AllowlistEntry>), String> {
+    let mut entries = Vec::new();
+    for (line_number, line) in csv.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || (line_number == 0 && line.starts_with("wallet")) {
+            continue;
+        }
+        let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+        if fields.len() != 3 {
+            return Err(format!("line {}: expected wallet,class,max_count", line_number + 1));
+        }
+        entries.push(AllowlistEntry {
+            wallet: Pubkey::from_str(fields[0]).map_err(|_| format!("line {}: invalid wallet", line_number + 1))?,
+            class: parse_class(fields[1]).ok_or(format!("line {}: invalid class", line_number + 1))?,
+            max_count: fields[2].parse().map_err(|_| format!("line {}: invalid max_count", line_number + 1))?,
+            proof: Vec::new(),
+        });
+    }
+    if entries.is_empty() {
+        return Err(String::from("the allowlist is empty"));
+    }
+
+    let mut level: Vec<[u8; 32]> = entries.iter().map(|entry| allowlist_leaf(&entry.wallet, entry.class, entry.max_count)).collect();
+    let mut positions: Vec<usize> = (0..entries.len()).collect();
+    while level.len() > 1 {
+        for (entry, position) in entries.iter_mut().zip(positions.iter_mut()) {
+            // The last node of an odd level has no sibling and moves up as is.
+            if let Some(sibling) = level.get(*position ^ 1) {
+                entry.proof.push(*sibling);
+            }
+            *position /= 2;
+        }
+        level = level
+            .chunks(2)
+            .map(|pair| match pair {
+                [a, b] => hash_pair(a, b),
+                [a] => *a,
+                _ => unreachable!(),
+            })
+            .collect();
+    }
+    Ok((level[0], entries))
+}
+
+#[cfg(not(target_arch = "bpf"))]
+fn parse_class(name: &str) -> Option<Class> {
+    Some(match name {
+        "Ruby" => Class::Ruby,
+        "Diamond" => Class::Diamond,
+        "Sapphire" => Class::Sapphire,
+        "Emerald" => Class::Emerald,
+        "Serendibite" => Class::Serendibite,
+        "Benitoite" => Class::Benitoite,
+        _ => Class::Custom { sol: name.strip_prefix("Custom")?.parse().ok()? },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn proofs_from_csv_verify_against_root() {
+        let wallets: Vec<Pubkey> = (0..5).map(|_| Pubkey::new_unique()).collect();
+        let csv = format!(
+            "wallet,class,max_count\n{},Ruby,1\n{},Benitoite,3\n{},Custom1000,1\n{},Emerald,2\n{},Diamond,1\n",
+            wallets[0], wallets[1], wallets[2], wallets[3], wallets[4]
+        );
+        let (root, entries) = build_allowlist_from_csv(&csv).unwrap();
+        assert_eq!(entries.len(), 5);
+        assert_eq!(entries[2].class, Class::Custom { sol: 1000 });
+
+        for entry in entries.iter() {
+            assert!(verify_allowlist_proof(&root, allowlist_leaf(&entry.wallet, entry.class, entry.max_count), &entry.proof));
+            assert!(!verify_allowlist_proof(&root, allowlist_leaf(&entry.wallet, entry.class, entry.max_count + 1), &entry.proof));
+        }
+        assert!(!verify_allowlist_proof(&root, allowlist_leaf(&wallets[1], Class::Ruby, 1), &entries[0].proof));
+        assert!(build_allowlist_from_csv("wallet,class,max_count\n").is_err());
+    }
+}
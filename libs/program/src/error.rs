@@ -27,6 +27,33 @@ pub enum InglError{
 
     #[error("Account data validation failed")]
     InvalidValPhrase,
+
+    #[error("Signer is not authorized to act on this account")]
+    Unauthorized,
+
+    #[error("The proposal did not pass")]
+    ProposalNotPassed,
+
+    #[error("The proposal was vetoed by the council")]
+    ProposalVetoed,
+
+    #[error("The program is paused by the council")]
+    ProgramPaused,
+
+    #[error("The oracle feed holds no usable price history")]
+    OracleFeedUnavailable,
+
+    #[error("The oracle feed price is too old")]
+    StaleOracleFeed,
+
+    #[error("The class is not listed in the class catalogue")]
+    UnlistedClass,
+
+    #[error("The allowlist proof does not match the mint schedule's root")]
+    InvalidAllowlistProof,
+
+    #[error("The gem is queued for redemption")]
+    RedemptionQueued,
 }
 
 
@@ -61,6 +88,33 @@ impl InglError{
             Self::InvalidValPhrase => {
                 msg!("Error: keyword={:} Validation Phrase Found in the sent account is different from that expected", if let Some(key) = keyword{key} else {""});
             }
+            Self::Unauthorized => {
+                msg!("Error: keyword={:} Signer is not authorized to act on this account", keyword.unwrap_or_default());
+            }
+            Self::ProposalNotPassed => {
+                msg!("Error: keyword={:} The proposal did not gather enough votes to be executed", keyword.unwrap_or_default());
+            }
+            Self::ProposalVetoed => {
+                msg!("Error: keyword={:} The proposal was vetoed by the council", keyword.unwrap_or_default());
+            }
+            Self::ProgramPaused => {
+                msg!("Error: keyword={:} The program is currently paused by the council", keyword.unwrap_or_default());
+            }
+            Self::OracleFeedUnavailable => {
+                msg!("Error: keyword={:} The oracle feed holds no price history for the requested time", keyword.unwrap_or_default());
+            }
+            Self::StaleOracleFeed => {
+                msg!("Error: keyword={:} The oracle feed price is older than the allowed staleness", keyword.unwrap_or_default());
+            }
+            Self::UnlistedClass => {
+                msg!("Error: keyword={:} The custom class is not listed in the config's class catalogue", keyword.unwrap_or_default());
+            }
+            Self::InvalidAllowlistProof => {
+                msg!("Error: keyword={:} The wallet, class and max count are not in the mint schedule's allowlist", keyword.unwrap_or_default());
+            }
+            Self::RedemptionQueued => {
+                msg!("Error: keyword={:} The gem is queued for redemption and can only be claimed", keyword.unwrap_or_default());
+            }
         }
         ProgramError::from(self)
     }
@@ -5,11 +5,13 @@ use solana_program::{
 };
 use serde::{Deserialize, Serialize};
 
-use crate::state::{VoteInit, Class, constants::vote_program, VoteState};
+use crate::state::{VoteInit, Class, ClassCap, MintPhase, GovernanceAction, TreasuryAccount, UriRegistryUpdate, constants::{self, vote_program, CANDIDATES_PER_PAGE, GEM_ACCOUNT_CONST, PROPOSAL_PAGE_KEY, VOTE_BALLOT_KEY}, VoteState};
+#[cfg(not(target_arch = "bpf"))]
+use solana_program::message::v0::MessageAddressTableLookup;
 
 #[derive(BorshSerialize, BorshDeserialize)]
 pub enum InstructionEnum {
-    MintNft(Class),
+    MintNft(Class, Option<Pubkey>), //The gem's class and the wallet of its referrer, if any.
     MintNewCollection,
     Redeem,
     ImprintRarity,
@@ -20,7 +22,7 @@ pub enum InstructionEnum {
     DelegateNFT,
     UnDelegateNFT,
     InitRarityImprint,
-    RegisterValidatorId,
+    RegisterValidatorId{name: String, website: String, commission: u8, self_bond: u64},
     CreateValidatorSelectionProposal,
     VoteValidatorProposal{num_nfts: u8, validator_index: u32},
     FinalizeProposal,
@@ -30,7 +32,37 @@ pub enum InstructionEnum {
     CloseProposal,
     InitRebalance,
     FinalizeRebalance,
-    InjectTestingData{num_nfts: u32}
+    InjectTestingData{num_nfts: u32},
+    AddGemsToBallot{num_nfts: u8},
+    CastBallot{validator_index: u32},
+    SetVotingDelegate{delegate: Option<Pubkey>},
+    InitConfig,
+    CreateGovernanceProposal(GovernanceAction),
+    VoteGovernanceProposal{num_nfts: u8, approve: bool},
+    ExecuteProposal,
+    VetoGovernanceProposal,
+    SetPause{is_paused: bool, num_members: u8},
+    WithdrawCandidacy,
+    RefundRegistration,
+    CreateProposalPage{page_number: u32},
+    UpdateUriRegistry(UriRegistryUpdate),
+    RefreshGemMetadata{num_gems: u8},
+    CancelRarityImprint,
+    RerollRarity,
+    FuseGems{class: Class, num_gems: u8},
+    SplitGem{classes: Vec<Class>},
+    SetMintSchedule{class_caps: Vec<ClassCap>, allowlist_phase: Option<MintPhase>, public_phase: Option<MintPhase>, wallet_cap: Option<u32>, allowlist_root: Option<[u8; 32]>},
+    AllowlistMintNft{class: Class, max_count: u32, proof: Vec<[u8; 32]>, referrer: Option<Pubkey>},
+    RegisterReferrer,
+    ClaimReferralRewards,
+    MintNftWithWrappedSol(Class, Option<Pubkey>),
+    QuoteRedeem,
+    RedeemQueued,
+    ClaimRedemption,
+    InstantRedeem,
+    TreasuryWithdraw{treasury: TreasuryAccount, lamports: u64},
+    RemoveGemsFromBallot{num_nfts: u8},
+    ReleaseValidatorBond,
 }
 
 
@@ -163,4 +195,91 @@ pub fn split(
     ];
     
     Instruction::new_with_bincode(solana_program::stake::program::id(), &StakeInstruction::Split(lamports), account_metas)
-}
\ No newline at end of file
+}
+
+/// Builds an `AddGemsToBallot` instruction for gems held in `voter`'s associated token accounts.
+pub fn add_gems_to_ballot(voter: &Pubkey, proposal: &Pubkey, mints: &[Pubkey]) -> Instruction {
+    let (ballot_key, _ballot_bump) = Pubkey::find_program_address(&[VOTE_BALLOT_KEY.as_ref(), proposal.as_ref(), voter.as_ref()], &constants::id());
+    let mut account_metas = vec![
+        AccountMeta::new(*voter, true),
+        AccountMeta::new_readonly(*proposal, false),
+        AccountMeta::new(ballot_key, false),
+        AccountMeta::new_readonly(solana_program::system_program::id(), false),
+    ];
+    for mint in mints {
+        let (gem_account_key, _gem_account_bump) = Pubkey::find_program_address(&[GEM_ACCOUNT_CONST.as_ref(), mint.as_ref()], &constants::id());
+        account_metas.push(AccountMeta::new_readonly(*mint, false));
+        account_metas.push(AccountMeta::new_readonly(spl_associated_token_account::get_associated_token_address(voter, mint), false));
+        account_metas.push(AccountMeta::new(gem_account_key, false));
+    }
+
+    Instruction::new_with_borsh(
+        constants::id(),
+        &InstructionEnum::AddGemsToBallot { num_nfts: mints.len() as u8 },
+        account_metas,
+    )
+}
+
+/// Builds a `RemoveGemsFromBallot` instruction taking `mints` back off `voter`'s uncast ballot.
+pub fn remove_gems_from_ballot(voter: &Pubkey, proposal: &Pubkey, mints: &[Pubkey]) -> Instruction {
+    let (ballot_key, _ballot_bump) = Pubkey::find_program_address(&[VOTE_BALLOT_KEY.as_ref(), proposal.as_ref(), voter.as_ref()], &constants::id());
+    let mut account_metas = vec![
+        AccountMeta::new(*voter, true),
+        AccountMeta::new_readonly(*proposal, false),
+        AccountMeta::new(ballot_key, false),
+    ];
+    for mint in mints {
+        let (gem_account_key, _gem_account_bump) = Pubkey::find_program_address(&[GEM_ACCOUNT_CONST.as_ref(), mint.as_ref()], &constants::id());
+        account_metas.push(AccountMeta::new_readonly(*mint, false));
+        account_metas.push(AccountMeta::new(gem_account_key, false));
+    }
+
+    Instruction::new_with_borsh(
+        constants::id(),
+        &InstructionEnum::RemoveGemsFromBallot { num_nfts: mints.len() as u8 },
+        account_metas,
+    )
+}
+
+pub fn cast_ballot(voter: &Pubkey, proposal: &Pubkey, validator_index: u32) -> Instruction {
+    let (ballot_key, _ballot_bump) = Pubkey::find_program_address(&[VOTE_BALLOT_KEY.as_ref(), proposal.as_ref(), voter.as_ref()], &constants::id());
+    let (page_key, _page_bump) = Pubkey::find_program_address(&[PROPOSAL_PAGE_KEY.as_ref(), proposal.as_ref(), &(validator_index / CANDIDATES_PER_PAGE).to_be_bytes()], &constants::id());
+    let account_metas = vec![
+        AccountMeta::new(*voter, true),
+        AccountMeta::new(*proposal, false),
+        AccountMeta::new(page_key, false),
+        AccountMeta::new(ballot_key, false),
+    ];
+
+    Instruction::new_with_borsh(
+        constants::id(),
+        &InstructionEnum::CastBallot { validator_index },
+        account_metas,
+    )
+}
+
+/// Resolves which accounts of `instructions` can be loaded from an address lookup table holding `table_addresses`,
+/// so that more gems fit in a single v0 transaction. Signers and invoked programs are always left as static keys.
+#[cfg(not(target_arch = "bpf"))]
+pub fn address_table_lookup(lookup_table: &Pubkey, table_addresses: &[Pubkey], instructions: &[Instruction]) -> MessageAddressTableLookup {
+    let mut lookup = MessageAddressTableLookup {
+        account_key: *lookup_table,
+        writable_indexes: Vec::new(),
+        readonly_indexes: Vec::new(),
+    };
+    for (index, address) in table_addresses.iter().enumerate().take(u8::MAX as usize + 1) {
+        if table_addresses[..index].contains(address) || instructions.iter().any(|ix| &ix.program_id == address) {
+            continue;
+        }
+        let metas: Vec<&AccountMeta> = instructions.iter().flat_map(|ix| ix.accounts.iter()).filter(|meta| &meta.pubkey == address).collect();
+        if metas.is_empty() || metas.iter().any(|meta| meta.is_signer) {
+            continue;
+        }
+        if metas.iter().any(|meta| meta.is_writable) {
+            lookup.writable_indexes.push(index as u8);
+        } else {
+            lookup.readonly_indexes.push(index as u8);
+        }
+    }
+    lookup
+}
@@ -3,6 +3,8 @@ pub mod error;
 pub mod instruction;
 pub mod nfts;
 pub mod processor;
+pub mod randomness;
+pub mod allowlist;
 pub mod utils;
 
 use processor::process_instruction;
@@ -1,4 +1,4 @@
-use crate::state::{Class, Rarity};
+use crate::state::{Class, MetadataUriRegistry, Rarity};
 
 pub fn get_uri<'life>(class: Class, rarity: Option<Rarity>) -> &'life str {
     match class {
@@ -66,10 +66,36 @@ pub fn get_uri<'life>(class: Class, rarity: Option<Rarity>) -> &'life str {
             },
             None => "https://arweave.net/i-0Sj0OhvFw6003cR0PA0PAmQHXy3kww1E_kaL_EtmY",
         },
+        // Custom classes show the Ruby artwork until governance registers their own.
+        Class::Custom { .. } => get_uri(Class::Ruby, rarity),
     }
 }
 
 
+impl MetadataUriRegistry {
+    /// Uri of the gem's artwork: the registry entry for its class and rarity, else its path under the base uri,
+    /// else the artwork the program was deployed with.
+    pub fn get_uri(&self, class: Class, rarity: Option<Rarity>) -> String {
+        if let Some(entry) = self.entries.iter().find(|entry| entry.class == class && entry.rarity == rarity) {
+            return entry.uri.clone();
+        }
+        match &self.base_uri {
+            Some(base_uri) => match rarity {
+                Some(rarity) => format!("{}/{}/{:?}.json", base_uri.trim_end_matches('/'), uri_path_name(class), rarity),
+                None => format!("{}/{}/Unrevealed.json", base_uri.trim_end_matches('/'), uri_path_name(class)),
+            },
+            None => String::from(get_uri(class, rarity)),
+        }
+    }
+}
+
+/// Folder of a class under the registry's base uri, e.g. "Ruby" or "Custom1000".
+fn uri_path_name(class: Class) -> String {
+    match class {
+        Class::Custom { sol } => format!("Custom{}", sol),
+        _ => format!("{:?}", class),
+    }
+}
 
 impl Class {
     pub fn get_rarity(self, random_value: u64) -> Option<Rarity> {
@@ -116,6 +142,8 @@ impl Class {
                 500..=1499 => Rarity::Exalted,
                 0..=499 => Rarity::Mythic,
             },
+            // Until governance sets a rarity table for them, custom classes draw like rubies.
+            Class::Custom { .. } => return Class::Ruby.get_rarity(random_value),
         })
     }
 }
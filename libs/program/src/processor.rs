@@ -1,41 +1,38 @@
 use crate::{
+    allowlist::{allowlist_leaf, verify_allowlist_proof},
     error::InglError,
     instruction::{
-        split, vote_create_account, vote_initialize_account, vote_withdraw, InstructionEnum,
+        split, vote_create_account, vote_initialize_account, vote_update_validator_identity, vote_withdraw, InstructionEnum,
     },
-    nfts,
     state::{
-        constants::*, Class, FundsLocation, GemAccountV0_0_1, GemAccountVersions, GlobalGems,
-        InglVoteAccountData, ValidatorProposal, ValidatorVote, VoteInit, VoteRewards,
+        constants::*, CandidateIndex, Class, ClassCap, ConfigUpdate, FundsLocation, GemAccountV0_0_1, GemAccountVersions, GlobalGems, GovernanceAction,
+        GovernanceProposal, InglConfig, InglVoteAccountData, MetadataUriRegistry, MintPhase, MintSchedule, MintWalletRecord, ProposalPage, QueuedRedemption, Rarity, RarityTable, RedeemFeeCurve, Referrer, TreasuryAccount, TreasuryLedger, UriRegistryUpdate, ValidatorCandidate, ValidatorProposal, ValidatorVote, VoteBallot, VoteInit, VoteRewards, VotingDelegate,
     },
-    utils::{assert_owned_by, assert_program_owned, assert_pubkeys_exactitude, assert_is_signer, assert_pda_input},
+    randomness::{mix_randomness, uniform_random_value, PriceFeedRandomness, SlotHashRandomness},
+    utils::{assert_owned_by, assert_program_owned, assert_pubkeys_exactitude, assert_is_signer, assert_pda_input, assert_upgrade_authority},
 };
 use std::str::FromStr;
 
 use anchor_lang::AnchorDeserialize;
 use borsh::BorshSerialize;
 use mpl_token_metadata::state::{Collection, Creator, DataV2, Metadata, PREFIX};
-use num_traits::Pow;
 use solana_program::{
     account_info::{next_account_info, AccountInfo},
     clock::Clock,
     entrypoint::ProgramResult,
     msg,
-    program::{invoke, invoke_signed},
+    program::{invoke, invoke_signed, set_return_data},
     program_error::ProgramError,
     pubkey::Pubkey,
     rent::Rent,
     system_instruction, system_program,
     sysvar::{self, Sysvar},
-    hash::hashv,
     stake::{state::{Authorized, Lockup, StakeState}, self},
 };
 use solana_program::{native_token::LAMPORTS_PER_SOL, program_pack::Pack};
 use spl_associated_token_account::{get_associated_token_address, *};
 use spl_token::{error::TokenError, state::Account};
-use switchboard_v2::{
-    AggregatorHistoryBuffer, AggregatorHistoryRow, SWITCHBOARD_V2_DEVNET, SWITCHBOARD_V2_MAINNET,
-};
+use switchboard_v2::SWITCHBOARD_V2_DEVNET;
 
 pub fn process_instruction(
     program_id: &Pubkey,
@@ -44,14 +41,14 @@ pub fn process_instruction(
 ) -> ProgramResult {
     Ok(match InstructionEnum::decode(instruction_data) {
         InstructionEnum::MintNewCollection => mint_collection(program_id, accounts)?, //1
-        InstructionEnum::MintNft(class) => mint_nft(program_id, accounts, class)?,    //4
+        InstructionEnum::MintNft(class, referrer) => mint_nft(program_id, accounts, class, referrer)?,    //4
         InstructionEnum::InitRarityImprint => init_rarity_imprint(program_id, accounts)?,
         InstructionEnum::ImprintRarity => imprint_rarity(program_id, accounts)?, //
         InstructionEnum::AllocateNFT => allocate_sol(program_id, accounts)?,     //8
         InstructionEnum::DeAllocateNFT => deallocate_sol(program_id, accounts)?, //10
         InstructionEnum::CreateVoteAccount => create_vote_account(program_id, accounts)?, //7
         InstructionEnum::Redeem => redeem_nft(program_id, accounts)?,
-        InstructionEnum::RegisterValidatorId => register_validator_id(program_id, accounts)?, //2
+        InstructionEnum::RegisterValidatorId{name, website, commission, self_bond} => register_validator_id(program_id, accounts, name, website, commission, self_bond)?, //2
         InstructionEnum::CreateValidatorSelectionProposal => create_validator_selection_proposal(program_id, accounts)?, //3
         InstructionEnum::VoteValidatorProposal{num_nfts, validator_index} => vote_validator_proposal(program_id, accounts, num_nfts, validator_index)?, //5
         InstructionEnum::FinalizeProposal => finalize_proposal(program_id, accounts)?,//6
@@ -63,15 +60,49 @@ pub fn process_instruction(
         InstructionEnum::InitRebalance => init_rebalance(program_id, accounts)?,
         InstructionEnum::FinalizeRebalance => finalize_rebalance(program_id, accounts)?,
         InstructionEnum::InjectTestingData{num_nfts} => inject_testing_data(program_id, accounts, num_nfts)?,
+        InstructionEnum::AddGemsToBallot{num_nfts} => add_gems_to_ballot(program_id, accounts, num_nfts)?,
+        InstructionEnum::CastBallot{validator_index} => cast_ballot(program_id, accounts, validator_index)?,
+        InstructionEnum::SetVotingDelegate{delegate} => set_voting_delegate(program_id, accounts, delegate)?,
+        InstructionEnum::InitConfig => init_config(program_id, accounts)?,
+        InstructionEnum::CreateGovernanceProposal(action) => create_governance_proposal(program_id, accounts, action)?,
+        InstructionEnum::VoteGovernanceProposal{num_nfts, approve} => vote_governance_proposal(program_id, accounts, num_nfts, approve)?,
+        InstructionEnum::ExecuteProposal => execute_proposal(program_id, accounts)?,
+        InstructionEnum::VetoGovernanceProposal => veto_governance_proposal(program_id, accounts)?,
+        InstructionEnum::SetPause{is_paused, num_members} => set_pause(program_id, accounts, is_paused, num_members)?,
+        InstructionEnum::WithdrawCandidacy => withdraw_candidacy(program_id, accounts)?,
+        InstructionEnum::RefundRegistration => refund_registration(program_id, accounts)?,
+        InstructionEnum::CreateProposalPage{page_number} => create_proposal_page(program_id, accounts, page_number)?,
+        InstructionEnum::UpdateUriRegistry(update) => update_uri_registry(program_id, accounts, update)?,
+        InstructionEnum::RefreshGemMetadata{num_gems} => refresh_gem_metadata(program_id, accounts, num_gems)?,
+        InstructionEnum::CancelRarityImprint => cancel_rarity_imprint(program_id, accounts)?,
+        InstructionEnum::RerollRarity => reroll_rarity(program_id, accounts)?,
+        InstructionEnum::FuseGems{class, num_gems} => fuse_gems(program_id, accounts, class, num_gems)?,
+        InstructionEnum::SplitGem{classes} => split_gem(program_id, accounts, classes)?,
+        InstructionEnum::SetMintSchedule{class_caps, allowlist_phase, public_phase, wallet_cap, allowlist_root} => set_mint_schedule(program_id, accounts, class_caps, allowlist_phase, public_phase, wallet_cap, allowlist_root)?,
+        InstructionEnum::AllowlistMintNft{class, max_count, proof, referrer} => allowlist_mint_nft(program_id, accounts, class, max_count, proof, referrer)?,
+        InstructionEnum::RegisterReferrer => register_referrer(program_id, accounts)?,
+        InstructionEnum::MintNftWithWrappedSol(class, referrer) => mint_nft_with_wrapped_sol(program_id, accounts, class, referrer)?,
+        InstructionEnum::ClaimReferralRewards => claim_referral_rewards(program_id, accounts)?,
+        InstructionEnum::QuoteRedeem => quote_redeem(program_id, accounts)?,
+        InstructionEnum::RedeemQueued => redeem_queued(program_id, accounts)?,
+        InstructionEnum::ClaimRedemption => claim_redemption(program_id, accounts)?,
+        InstructionEnum::InstantRedeem => instant_redeem(program_id, accounts)?,
+        InstructionEnum::TreasuryWithdraw{treasury, lamports} => treasury_withdraw(program_id, accounts, treasury, lamports)?,
+        InstructionEnum::RemoveGemsFromBallot{num_nfts} => remove_gems_from_ballot(program_id, accounts, num_nfts)?,
+        InstructionEnum::ReleaseValidatorBond => release_validator_bond(program_id, accounts)?,
         _ => Err(ProgramError::InvalidInstructionData)?,
     })
 }
 
 pub fn finalize_proposal(_program_id:&Pubkey, accounts: &[AccountInfo]) -> ProgramResult{
     let account_info_iter = &mut accounts.iter();
-    let _payer_account_info = next_account_info(account_info_iter)?;
+    let payer_account_info = next_account_info(account_info_iter)?;
     let proposal_account_info = next_account_info(account_info_iter)?;
     let global_gem_account_info = next_account_info(account_info_iter)?;
+    let winner_candidate_account_info = next_account_info(account_info_iter)?;
+    let winner_index_account_info = next_account_info(account_info_iter)?;
+    let last_index_account_info = next_account_info(account_info_iter)?;
+    let last_candidate_account_info = next_account_info(account_info_iter)?;
 
     assert_program_owned(proposal_account_info)?;
     let mut proposal_data = ValidatorProposal::decode(proposal_account_info)?;
@@ -89,69 +120,337 @@ pub fn finalize_proposal(_program_id:&Pubkey, accounts: &[AccountInfo]) -> Progr
     let (_expected_proposal_id, _expected_proposal_bump) = assert_pda_input(&[PROPOSAL_KEY.as_ref(),&(global_gem_account_data.proposal_numeration - 1).to_be_bytes(),], proposal_account_info);
     proposal_data.date_finalized = Some(Clock::get()?.unix_timestamp as u32);
 
-    let (winner_index, _) = proposal_data.votes.iter().enumerate().fold((0, 0), |max, (ind, &val)| if val > max.1 {(ind, val)} else {max});
-    proposal_data.winner = Some(proposal_data.validator_ids[winner_index]);
+    // The leader is kept up to date on every vote, so the winner is known without reading the proposal pages.
+    let winner = match proposal_data.leader {
+        Some(leader) => leader,
+        None => Err(InglError::TooEarly.utilize(Some("No votes were cast")))?,
+    };
+    proposal_data.winner = Some(winner);
     proposal_data.serialize(&mut &mut proposal_account_info.data.borrow_mut()[..])?;
 
-    let index = global_gem_account_data.validator_list.iter().position(|x| *x == proposal_data.winner.unwrap()).unwrap();
-    global_gem_account_data.validator_list.remove(index);
+    let (_expected_winner_candidate_key, _expected_winner_candidate_bump) = assert_pda_input(&[VALIDATOR_CANDIDATE_KEY.as_ref(), winner.as_ref()], winner_candidate_account_info);
+    remove_candidate(&mut global_gem_account_data, winner_candidate_account_info, winner_index_account_info, last_index_account_info, last_candidate_account_info, payer_account_info)?;
     global_gem_account_data.is_proposal_ongoing = false;
     global_gem_account_data.serialize(&mut &mut global_gem_account_info.data.borrow_mut()[..])?;
     Ok(())
 }
 
+/// Takes a candidate out of the candidate set by moving the last candidate into its index.
+/// The freed CandidateIndex account is closed to `rent_recipient_info`.
+fn remove_candidate(
+    global_gem_data: &mut GlobalGems,
+    candidate_account_info: &AccountInfo,
+    index_account_info: &AccountInfo,
+    last_index_account_info: &AccountInfo,
+    last_candidate_account_info: &AccountInfo,
+    rent_recipient_info: &AccountInfo,
+) -> ProgramResult {
+    let mut candidate_data = ValidatorCandidate::decode(candidate_account_info)?;
+    let index = match candidate_data.index {
+        Some(index) => index,
+        None => Err(InglError::Unauthorized.utilize(Some("Not an unelected candidate")))?,
+    };
+    let last_index = global_gem_data.candidate_count.checked_sub(1).ok_or(InglError::BeyondBounds)?;
+    let (_expected_index_key, _expected_index_bump) = assert_pda_input(&[CANDIDATE_INDEX_KEY.as_ref(), &index.to_be_bytes()], index_account_info);
+    let (_expected_last_index_key, _expected_last_index_bump) = assert_pda_input(&[CANDIDATE_INDEX_KEY.as_ref(), &last_index.to_be_bytes()], last_index_account_info);
+
+    if index != last_index {
+        let last_index_data = CandidateIndex::decode(last_index_account_info)?;
+        let (_expected_last_candidate_key, _expected_last_candidate_bump) = assert_pda_input(&[VALIDATOR_CANDIDATE_KEY.as_ref(), last_index_data.validator_id.as_ref()], last_candidate_account_info);
+        let mut last_candidate_data = ValidatorCandidate::decode(last_candidate_account_info)?;
+        last_candidate_data.index = Some(index);
+        last_candidate_data.serialize(&mut &mut last_candidate_account_info.data.borrow_mut()[..])?;
+        last_index_data.serialize(&mut &mut index_account_info.data.borrow_mut()[..])?;
+    }
+
+    let dest_starting_lamports = rent_recipient_info.lamports();
+    **rent_recipient_info.lamports.borrow_mut() = dest_starting_lamports
+        .checked_add(last_index_account_info.lamports())
+        .unwrap();
+    **last_index_account_info.lamports.borrow_mut() = 0;
+    last_index_account_info.data.borrow_mut().fill(0);
+
+    candidate_data.index = None;
+    candidate_data.serialize(&mut &mut candidate_account_info.data.borrow_mut()[..])?;
+    global_gem_data.candidate_count = last_index;
+    Ok(())
+}
 
 pub fn vote_validator_proposal(_program_id: &Pubkey, accounts: &[AccountInfo], num_nfts: u8, validator_index: u32) -> ProgramResult{
     let account_info_iter = &mut accounts.iter();
     let payer_account_info = next_account_info(account_info_iter)?;
     let proposal_account_info = next_account_info(account_info_iter)?;
-    
+    let page_account_info = next_account_info(account_info_iter)?;
+
+    assert_is_signer(payer_account_info)?;
     assert_program_owned(proposal_account_info)?;
     let mut proposal_data = ValidatorProposal::decode(proposal_account_info)?;
-    if let Some(_) = proposal_data.date_finalized {
+    if proposal_data.date_finalized.is_some() {
         Err(InglError::TooLate.utilize(Some("Proposal Voted Already Ended")))?
     }
-    for _ in 0..num_nfts{ // 9 NFTs limit due to transaction size limit
-        let mint_account_info = next_account_info(account_info_iter)?;
-        let associated_token_account_info = next_account_info(account_info_iter)?;
-        let gem_account_data_info = next_account_info(account_info_iter)?;
 
+    // 9 NFTs limit due to transaction size limit. Use AddGemsToBallot and CastBallot for more.
+    let vote_weight = collect_gem_votes(payer_account_info, proposal_account_info.key, account_info_iter, num_nfts, Some(validator_index))?;
+    add_candidate_votes(proposal_account_info, &mut proposal_data, page_account_info, validator_index, vote_weight)?;
 
-        let (_gem_account_pubkey, _gem_account_bump) = assert_pda_input(&[GEM_ACCOUNT_CONST.as_ref(), mint_account_info.key.as_ref()], gem_account_data_info);
-        assert_program_owned(gem_account_data_info)?;
-        assert_owned_by(mint_account_info, &spl_program::id())?;
-        assert_owned_by(associated_token_account_info, &spl_program::id())?;
+    proposal_data.serialize(&mut &mut proposal_account_info.data.borrow_mut()[..])?;
+    Ok(())
+}
 
-        assert_pubkeys_exactitude(
-            &get_associated_token_address(payer_account_info.key, mint_account_info.key),
-            associated_token_account_info.key,
-        )
-        .expect("Error: @associated_token_address");
-        let associated_token_address_data =
-            Account::unpack(&associated_token_account_info.data.borrow())?;
-        if associated_token_address_data.amount != 1 {
-            Err(ProgramError::InsufficientFunds)?
+/// Adds `vote_weight` to the candidate at `validator_index` of the proposal, on its proposal page, and updates the proposal's leader.
+fn add_candidate_votes(
+    proposal_account_info: &AccountInfo,
+    proposal_data: &mut ValidatorProposal,
+    page_account_info: &AccountInfo,
+    validator_index: u32,
+    vote_weight: u32,
+) -> ProgramResult {
+    if validator_index >= proposal_data.candidate_count {
+        Err(InglError::BeyondBounds.utilize(Some("validator_index")))?
+    }
+    let page_number = validator_index / CANDIDATES_PER_PAGE;
+    let (_expected_page_key, _expected_page_bump) = assert_pda_input(&[PROPOSAL_PAGE_KEY.as_ref(), proposal_account_info.key.as_ref(), &page_number.to_be_bytes()], page_account_info);
+    let mut page_data = ProposalPage::decode(page_account_info)?;
+
+    let position = (validator_index % CANDIDATES_PER_PAGE) as usize;
+    if page_data.validator_ids[position] == Pubkey::default() {
+        Err(InglError::BeyondBounds.utilize(Some("Candidate is not eligible")))?
+    }
+    page_data.votes[position] = page_data.votes[position].checked_add(vote_weight).unwrap();
+    if page_data.votes[position] > proposal_data.leader_votes {
+        proposal_data.leader = Some(page_data.validator_ids[position]);
+        proposal_data.leader_votes = page_data.votes[position];
+    }
+    page_data.serialize(&mut &mut page_account_info.data.borrow_mut()[..])?;
+    Ok(())
+}
+
+/// Checks that the gem is held by the voter, or by an owner who made the voter their voting delegate,
+/// and returns the gem's data.
+fn assert_gem_voter(
+    voter_account_info: &AccountInfo,
+    mint_account_info: &AccountInfo,
+    associated_token_account_info: &AccountInfo,
+    gem_account_data_info: &AccountInfo,
+) -> Result<GemAccountV0_0_1, ProgramError> {
+    let (_gem_account_pubkey, _gem_account_bump) = assert_pda_input(&[GEM_ACCOUNT_CONST.as_ref(), mint_account_info.key.as_ref()], gem_account_data_info);
+    assert_program_owned(gem_account_data_info)?;
+    assert_owned_by(mint_account_info, &spl_program::id())?;
+    assert_owned_by(associated_token_account_info, &spl_program::id())?;
+
+    let associated_token_address_data =
+        Account::unpack(&associated_token_account_info.data.borrow())?;
+    if associated_token_address_data.amount != 1 {
+        Err(ProgramError::InsufficientFunds)?
+    }
+
+    let gem_account_data: GemAccountV0_0_1 = GemAccountV0_0_1::validate(GemAccountVersions::decode_unchecked(&gem_account_data_info.data.borrow())?)?;
+
+    if associated_token_account_info.key != &get_associated_token_address(voter_account_info.key, mint_account_info.key) {
+        match &gem_account_data.voting_delegate {
+            Some(VotingDelegate{owner, delegate}) if delegate == voter_account_info.key && owner == &associated_token_address_data.owner => {
+                assert_pubkeys_exactitude(
+                    &get_associated_token_address(owner, mint_account_info.key),
+                    associated_token_account_info.key,
+                )
+                .expect("Error: @associated_token_address");
+            }
+            _ => Err(InglError::Unauthorized.utilize(Some("voting delegate")))?,
         }
+    }
+    Ok(gem_account_data)
+}
 
-        let mut gem_account_data: GemAccountV0_0_1 = GemAccountV0_0_1::validate(GemAccountVersions::decode_unchecked(&gem_account_data_info.data.borrow())?)?;
+/// Verifies the next `num_nfts` (mint, associated token account, gem account) triples with `assert_gem_voter`,
+/// marks them as having voted on the proposal and returns their accumulated vote weight.
+/// The vote is only recorded in the gems' history when the chosen validator is already known,
+/// otherwise the gems are marked as held by the voter's ballot until it is cast.
+fn collect_gem_votes<'a, 'b: 'a, I: Iterator<Item = &'a AccountInfo<'b>>>(
+    voter_account_info: &AccountInfo,
+    proposal_id: &Pubkey,
+    account_info_iter: &mut I,
+    num_nfts: u8,
+    validator_index: Option<u32>,
+) -> Result<u32, ProgramError> {
+    let mut vote_weight: u32 = 0;
+    for _ in 0..num_nfts{
+        let mint_account_info = next_account_info(account_info_iter)?;
+        let associated_token_account_info = next_account_info(account_info_iter)?;
+        let gem_account_data_info = next_account_info(account_info_iter)?;
+
+        let mut gem_account_data = assert_gem_voter(voter_account_info, mint_account_info, associated_token_account_info, gem_account_data_info)?;
 
-        if let Some(proposal_id) = gem_account_data.last_voted_proposal{
-            if &proposal_id == proposal_account_info.key{
+        if let Some(last_proposal_id) = gem_account_data.last_voted_proposal{
+            if &last_proposal_id == proposal_id{
                 Err(InglError::AlreadyVoted.utilize(Some(mint_account_info.key.to_string().as_ref() as &str)))?
             }
         }
 
-        gem_account_data.last_voted_proposal = Some(*proposal_account_info.key);
-        gem_account_data.all_votes.push(ValidatorVote{validation_phrase: VALIDATOR_VOTE_VAL_PHRASE, proposal_id: *proposal_account_info.key, validator_index: validator_index});
-        proposal_data.votes[validator_index as usize] = proposal_data.votes[validator_index as usize].checked_add((gem_account_data.class.get_class_lamports() / LAMPORTS_PER_SOL) as u32).unwrap();
+        gem_account_data.last_voted_proposal = Some(*proposal_id);
+        gem_account_data.ballot_voter = match validator_index{
+            Some(validator_index) => {
+                gem_account_data.record_vote(ValidatorVote{validation_phrase: VALIDATOR_VOTE_VAL_PHRASE, proposal_id: *proposal_id, validator_index: validator_index});
+                None
+            }
+            None => Some(*voter_account_info.key),
+        };
+        vote_weight = vote_weight.checked_add(gem_account_data.class.get_vote_weight()).unwrap();
         gem_account_data.serialize(&mut &mut gem_account_data_info.data.borrow_mut()[..])?;
     }
+    Ok(vote_weight)
+}
+
+pub fn add_gems_to_ballot(program_id: &Pubkey, accounts: &[AccountInfo], num_nfts: u8) -> ProgramResult{
+    let account_info_iter = &mut accounts.iter();
+    let payer_account_info = next_account_info(account_info_iter)?;
+    let proposal_account_info = next_account_info(account_info_iter)?;
+    let ballot_account_info = next_account_info(account_info_iter)?;
+    let _system_program_account_info = next_account_info(account_info_iter)?;
+
+    assert_is_signer(payer_account_info)?;
+    assert_program_owned(proposal_account_info)?;
+    let proposal_data = ValidatorProposal::decode(proposal_account_info)?;
+    if proposal_data.date_finalized.is_some() {
+        Err(InglError::TooLate.utilize(Some("Proposal Voted Already Ended")))?
+    }
+
+    let (ballot_pubkey, ballot_bump) = assert_pda_input(&[VOTE_BALLOT_KEY.as_ref(), proposal_account_info.key.as_ref(), payer_account_info.key.as_ref()], ballot_account_info);
+
+    let mut ballot_data = if ballot_account_info.data_is_empty(){
+        invoke_signed(
+            &system_instruction::create_account(payer_account_info.key, &ballot_pubkey, Rent::get()?.minimum_balance(VoteBallot::space()), VoteBallot::space() as u64, program_id),
+            &[payer_account_info.clone(), ballot_account_info.clone()],
+            &[&[VOTE_BALLOT_KEY.as_ref(), proposal_account_info.key.as_ref(), payer_account_info.key.as_ref(), &[ballot_bump]]],
+        )?;
+        VoteBallot{
+            validation_phrase: VOTE_BALLOT_VAL_PHRASE,
+            proposal_id: *proposal_account_info.key,
+            voter: *payer_account_info.key,
+            total_weight: 0,
+            gem_count: 0,
+            cast_validator_index: None,
+        }
+    } else {
+        VoteBallot::decode(ballot_account_info)?
+    };
+    if ballot_data.cast_validator_index.is_some() {
+        Err(InglError::AlreadyVoted.utilize(Some("Ballot Already Cast")))?
+    }
+
+    let vote_weight = collect_gem_votes(payer_account_info, proposal_account_info.key, account_info_iter, num_nfts, None)?;
+    ballot_data.total_weight = ballot_data.total_weight.checked_add(vote_weight).unwrap();
+    ballot_data.gem_count = ballot_data.gem_count.checked_add(num_nfts as u32).unwrap();
+
+    ballot_data.serialize(&mut &mut ballot_account_info.data.borrow_mut()[..])?;
+    Ok(())
+}
+
+pub fn cast_ballot(_program_id: &Pubkey, accounts: &[AccountInfo], validator_index: u32) -> ProgramResult{
+    let account_info_iter = &mut accounts.iter();
+    let payer_account_info = next_account_info(account_info_iter)?;
+    let proposal_account_info = next_account_info(account_info_iter)?;
+    let page_account_info = next_account_info(account_info_iter)?;
+    let ballot_account_info = next_account_info(account_info_iter)?;
+
+    assert_is_signer(payer_account_info)?;
+    assert_program_owned(proposal_account_info)?;
+    let mut proposal_data = ValidatorProposal::decode(proposal_account_info)?;
+    if proposal_data.date_finalized.is_some() {
+        Err(InglError::TooLate.utilize(Some("Proposal Voted Already Ended")))?
+    }
+
+    let (_ballot_pubkey, _ballot_bump) = assert_pda_input(&[VOTE_BALLOT_KEY.as_ref(), proposal_account_info.key.as_ref(), payer_account_info.key.as_ref()], ballot_account_info);
+    let mut ballot_data = VoteBallot::decode(ballot_account_info)?;
+    if ballot_data.cast_validator_index.is_some() {
+        Err(InglError::AlreadyVoted.utilize(Some("Ballot Already Cast")))?
+    }
 
+    add_candidate_votes(proposal_account_info, &mut proposal_data, page_account_info, validator_index, ballot_data.total_weight)?;
     proposal_data.serialize(&mut &mut proposal_account_info.data.borrow_mut()[..])?;
+
+    // The ballot is kept once cast, so that it can't be recreated to pull its gems back and vote with them twice.
+    ballot_data.cast_validator_index = Some(validator_index);
+    ballot_data.serialize(&mut &mut ballot_account_info.data.borrow_mut()[..])?;
+
+    Ok(())
+}
+
+/// Takes gems back off the voter's ballot before it is cast, so that their weight isn't lost if the ballot never is.
+pub fn remove_gems_from_ballot(_program_id: &Pubkey, accounts: &[AccountInfo], num_nfts: u8) -> ProgramResult{
+    let account_info_iter = &mut accounts.iter();
+    let payer_account_info = next_account_info(account_info_iter)?;
+    let proposal_account_info = next_account_info(account_info_iter)?;
+    let ballot_account_info = next_account_info(account_info_iter)?;
+
+    assert_is_signer(payer_account_info)?;
+    assert_program_owned(proposal_account_info)?;
+    let proposal_data = ValidatorProposal::decode(proposal_account_info)?;
+    if proposal_data.date_finalized.is_some() {
+        Err(InglError::TooLate.utilize(Some("Proposal Voted Already Ended")))?
+    }
+
+    let (_ballot_pubkey, _ballot_bump) = assert_pda_input(&[VOTE_BALLOT_KEY.as_ref(), proposal_account_info.key.as_ref(), payer_account_info.key.as_ref()], ballot_account_info);
+    let mut ballot_data = VoteBallot::decode(ballot_account_info)?;
+    if ballot_data.cast_validator_index.is_some() {
+        Err(InglError::AlreadyVoted.utilize(Some("Ballot Already Cast")))?
+    }
+
+    for _ in 0..num_nfts{
+        let mint_account_info = next_account_info(account_info_iter)?;
+        let gem_account_data_info = next_account_info(account_info_iter)?;
+
+        let (_gem_account_pubkey, _gem_account_bump) = assert_pda_input(&[GEM_ACCOUNT_CONST.as_ref(), mint_account_info.key.as_ref()], gem_account_data_info);
+        assert_program_owned(gem_account_data_info)?;
+        let mut gem_account_data: GemAccountV0_0_1 = GemAccountV0_0_1::validate(GemAccountVersions::decode_unchecked(&gem_account_data_info.data.borrow())?)?;
+        if gem_account_data.last_voted_proposal != Some(*proposal_account_info.key) || gem_account_data.ballot_voter != Some(*payer_account_info.key) {
+            Err(InglError::Unauthorized.utilize(Some(mint_account_info.key.to_string().as_ref() as &str)))?
+        }
+
+        // Only one validator proposal is ongoing at a time, so the gem had no other open vote to restore.
+        gem_account_data.last_voted_proposal = None;
+        gem_account_data.ballot_voter = None;
+        ballot_data.total_weight = ballot_data.total_weight.checked_sub(gem_account_data.class.get_vote_weight()).unwrap();
+        ballot_data.gem_count = ballot_data.gem_count.checked_sub(1).unwrap();
+        gem_account_data.serialize(&mut &mut gem_account_data_info.data.borrow_mut()[..])?;
+    }
+
+    ballot_data.serialize(&mut &mut ballot_account_info.data.borrow_mut()[..])?;
     Ok(())
 }
 
 
+pub fn set_voting_delegate(_program_id: &Pubkey, accounts: &[AccountInfo], delegate: Option<Pubkey>) -> ProgramResult{
+    let account_info_iter = &mut accounts.iter();
+    let payer_account_info = next_account_info(account_info_iter)?;
+    let mint_account_info = next_account_info(account_info_iter)?;
+    let associated_token_account_info = next_account_info(account_info_iter)?;
+    let gem_account_data_info = next_account_info(account_info_iter)?;
+
+    assert_is_signer(payer_account_info)?;
+    assert_program_owned(gem_account_data_info)?;
+    assert_owned_by(mint_account_info, &spl_program::id())?;
+    assert_owned_by(associated_token_account_info, &spl_program::id())?;
+
+    let (_gem_account_pubkey, _gem_account_bump) = assert_pda_input(&[GEM_ACCOUNT_CONST.as_ref(), mint_account_info.key.as_ref()], gem_account_data_info);
+
+    assert_pubkeys_exactitude(
+        &get_associated_token_address(payer_account_info.key, mint_account_info.key),
+        associated_token_account_info.key,
+    )
+    .expect("Error: @associated_token_address");
+    let associated_token_address_data =
+        Account::unpack(&associated_token_account_info.data.borrow())?;
+    if associated_token_address_data.amount != 1 {
+        Err(ProgramError::InsufficientFunds)?
+    }
+
+    let mut gem_account_data: GemAccountV0_0_1 = GemAccountV0_0_1::validate(GemAccountVersions::decode_unchecked(&gem_account_data_info.data.borrow())?)?;
+    gem_account_data.voting_delegate = delegate.map(|delegate| VotingDelegate{owner: *payer_account_info.key, delegate});
+    gem_account_data.serialize(&mut &mut gem_account_data_info.data.borrow_mut()[..])?;
+
+    Ok(())
+}
+
 pub fn create_validator_selection_proposal(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult{
     let account_info_iter = &mut accounts.iter();
     let payer_account_info = next_account_info(account_info_iter)?;
@@ -171,10 +470,10 @@ pub fn create_validator_selection_proposal(program_id: &Pubkey, accounts: &[Acco
 
     let (expected_proposal_id, expected_proposal_bump) = assert_pda_input(&[PROPOSAL_KEY.as_ref(), &global_gem_data.proposal_numeration.to_be_bytes()], proposal_account_info);
     
-    let space = 10240;
+    let space = ValidatorProposal::space();
     let rent_lamports = Rent::get()?.minimum_balance(space);
 
-    if global_gem_data.validator_list.len() == 0 {
+    if global_gem_data.candidate_count == 0 {
         Err(InglError::TooEarly.utilize(Some(
             "Validator list can't be empty. Wait for validator registrations",
         )))?
@@ -190,12 +489,14 @@ pub fn create_validator_selection_proposal(program_id: &Pubkey, accounts: &[Acco
         ]],
     )?;
 
+    // Candidates registered after this point are left for the next proposal, as their indices are beyond candidate_count.
     let proposal_data = ValidatorProposal{
         validation_phrase: VALIDATOR_PROPOSAL_VAL_PHRASE,
-        validator_ids : global_gem_data.clone().validator_list, // Vec([id1, id2, id3, id4, id5])
+        candidate_count: global_gem_data.candidate_count,
         date_created : Clock::get()?.unix_timestamp as u32,
         date_finalized : None,
-        votes: vec![0; global_gem_data.clone().validator_list.len()], //Vec([2, 3, 5, 2, 1]) The total Sol backing the NFTs used to vote.
+        leader: None,
+        leader_votes: 0,
         winner: None
     };
 
@@ -208,712 +509,1808 @@ pub fn create_validator_selection_proposal(program_id: &Pubkey, accounts: &[Acco
     Ok(())
 }
 
-pub fn register_validator_id(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult{
+/// Lists the candidates of one page of a validator proposal. Candidate accounts are expected in index order,
+/// from `page_number * CANDIDATES_PER_PAGE` up to the page end or the proposal's candidate_count.
+pub fn create_proposal_page(program_id: &Pubkey, accounts: &[AccountInfo], page_number: u32) -> ProgramResult{
     let account_info_iter = &mut accounts.iter();
     let payer_account_info = next_account_info(account_info_iter)?;
-    let global_gem_account_info = next_account_info(account_info_iter)?;
-    let mint_authority_account_info = next_account_info(account_info_iter)?;
-    let validator_info = next_account_info(account_info_iter)?; //Remove this and change it back to payer only after hackathon.
-    let dup_prevention_account = next_account_info(account_info_iter)?;
+    let proposal_account_info = next_account_info(account_info_iter)?;
+    let page_account_info = next_account_info(account_info_iter)?;
+    let _system_program_account_info = next_account_info(account_info_iter)?;
 
-    let (expected_dup_key, expected_dup_bump) = assert_pda_input(&[DUPKEYBYTES, validator_info.key.as_ref()], dup_prevention_account);
+    assert_is_signer(payer_account_info)?;
+    let proposal_data = ValidatorProposal::decode(proposal_account_info)?;
+    if proposal_data.date_finalized.is_some() {
+        Err(InglError::TooLate.utilize(Some("Proposal Voted Already Ended")))?
+    }
+    if page_number >= proposal_data.page_count() {
+        Err(InglError::BeyondBounds.utilize(Some("page_number")))?
+    }
 
+    let (expected_page_key, expected_page_bump) = assert_pda_input(&[PROPOSAL_PAGE_KEY.as_ref(), proposal_account_info.key.as_ref(), &page_number.to_be_bytes()], page_account_info);
+    let space = ProposalPage::space();
     invoke_signed(
-        &system_instruction::create_account(payer_account_info.key, &expected_dup_key, Rent::get()?.minimum_balance(1), 1,program_id),
-        &[payer_account_info.clone(), dup_prevention_account.clone()],
-        &[&[DUPKEYBYTES, validator_info.key.as_ref(), &[expected_dup_bump]]]
+        &system_instruction::create_account(payer_account_info.key, &expected_page_key, Rent::get()?.minimum_balance(space), space as u64, program_id),
+        &[payer_account_info.clone(), page_account_info.clone()],
+        &[&[PROPOSAL_PAGE_KEY.as_ref(), proposal_account_info.key.as_ref(), &page_number.to_be_bytes(), &[expected_page_bump]]],
     )?;
 
-    let (_global_gem_pubkey, _global_gem_bump) = assert_pda_input(&[GLOBAL_GEM_KEY.as_ref()], global_gem_account_info );
-    let (_mint_authority_key, _mint_authority_bump) = assert_pda_input(&[INGL_MINT_AUTHORITY_KEY.as_ref()], mint_authority_account_info);
+    let first_index = page_number * CANDIDATES_PER_PAGE;
+    let end_index = proposal_data.candidate_count.min(first_index + CANDIDATES_PER_PAGE);
+    let mut validator_ids: Vec<Pubkey> = Vec::new();
+    for index in first_index..end_index {
+        let candidate_account_info = next_account_info(account_info_iter)?;
+        let candidate_data = ValidatorCandidate::decode(candidate_account_info)?;
+        let (_expected_candidate_key, _expected_candidate_bump) = assert_pda_input(&[VALIDATOR_CANDIDATE_KEY.as_ref(), candidate_data.validator_id.as_ref()], candidate_account_info);
+        if candidate_data.index != Some(index) {
+            Err(InglError::BeyondBounds.utilize(Some("Candidate accounts out of order")))?
+        }
+        validator_ids.push(if candidate_data.is_eligible() { candidate_data.validator_id } else { Pubkey::default() });
+    }
 
-    invoke(
-        &system_instruction::transfer(
-            payer_account_info.key,
-            mint_authority_account_info.key,
-            2 * LAMPORTS_PER_SOL,
-        ),
-        &[
-            payer_account_info.clone(),
-            mint_authority_account_info.clone(),
-        ],
+    let page_data = ProposalPage{
+        validation_phrase: PROPOSAL_PAGE_VAL_PHRASE,
+        proposal_id: *proposal_account_info.key,
+        page_number,
+        votes: vec![0; validator_ids.len()],
+        validator_ids,
+    };
+    page_data.serialize(&mut &mut page_account_info.data.borrow_mut()[..])?;
+    Ok(())
+}
+
+pub fn init_config(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult{
+    let account_info_iter = &mut accounts.iter();
+    let payer_account_info = next_account_info(account_info_iter)?;
+    let config_account_info = next_account_info(account_info_iter)?;
+    let _system_program_account_info = next_account_info(account_info_iter)?;
+    let treasury_ledger_account_info = next_account_info(account_info_iter)?;
+    let program_data_account_info = next_account_info(account_info_iter)?;
+
+    // The first authority holds pause, treasury and config powers, so it can't be whoever initializes the config first.
+    assert_upgrade_authority(program_data_account_info, payer_account_info)?;
+    let (config_pubkey, config_bump) = assert_pda_input(&[INGL_CONFIG_KEY.as_ref()], config_account_info);
+    let (treasury_ledger_pubkey, treasury_ledger_bump) = assert_pda_input(&[TREASURY_LEDGER_KEY.as_ref()], treasury_ledger_account_info);
+
+    let space = InglConfig::space();
+    invoke_signed(
+        &system_instruction::create_account(payer_account_info.key, &config_pubkey, Rent::get()?.minimum_balance(space), space as u64, program_id),
+        &[payer_account_info.clone(), config_account_info.clone()],
+        &[&[INGL_CONFIG_KEY.as_ref(), &[config_bump]]],
     )?;
 
-    assert_program_owned(global_gem_account_info)?;
-    assert_is_signer(payer_account_info)?;
+    let config_data = InglConfig{
+        validation_phrase: INGL_CONFIG_VAL_PHRASE,
+        authority: *payer_account_info.key,
+        governance_voting_period: DEFAULT_GOVERNANCE_VOTING_PERIOD,
+        governance_timelock: DEFAULT_GOVERNANCE_TIMELOCK,
+        governance_quorum: DEFAULT_GOVERNANCE_QUORUM,
+        governance_proposal_numeration: 0,
+        council_veto_threshold: DEFAULT_COUNCIL_VETO_THRESHOLD,
+        council_pause_threshold: DEFAULT_COUNCIL_PAUSE_THRESHOLD,
+        is_paused: false,
+        registration_refund_percentage: DEFAULT_REGISTRATION_REFUND_PERCENTAGE,
+        oracle_program_id: SWITCHBOARD_V2_DEVNET,
+        rarity_feeds: [BTC_FEED_PUBLIC_KEY, SOL_FEED_PUBLIC_KEY, ETH_FEED_PUBLIC_KEY, BNB_FEED_PUBLIC_KEY]
+            .iter()
+            .map(|feed| Pubkey::from_str(feed).unwrap())
+            .collect(),
+        max_feed_staleness: DEFAULT_MAX_FEED_STALENESS,
+        rarity_boosts: [0; 5],
+        reroll_fee: DEFAULT_REROLL_FEE,
+        max_rarity_rerolls: DEFAULT_MAX_RARITY_REROLLS,
+        custom_classes: Vec::new(),
+        referral_share: DEFAULT_REFERRAL_SHARE,
+        max_referral_reward: DEFAULT_MAX_REFERRAL_REWARD,
+        redeem_fee_curve: RedeemFeeCurve::QuarterCircle,
+        redeem_fee_percent: FEE_MULTIPLYER,
+        redeem_fee_duration: DEFAULT_REDEEM_FEE_DURATION,
+        liquidity_reserve_share: DEFAULT_LIQUIDITY_RESERVE_SHARE,
+        instant_exit_discount: DEFAULT_INSTANT_EXIT_DISCOUNT,
+    };
+    config_data.serialize(&mut &mut config_account_info.data.borrow_mut()[..])?;
 
-    let mut global_gem_data = GlobalGems::decode(global_gem_account_info)?;
+    let space = TreasuryLedger::space();
+    invoke_signed(
+        &system_instruction::create_account(payer_account_info.key, &treasury_ledger_pubkey, Rent::get()?.minimum_balance(space), space as u64, program_id),
+        &[payer_account_info.clone(), treasury_ledger_account_info.clone()],
+        &[&[TREASURY_LEDGER_KEY.as_ref(), &[treasury_ledger_bump]]],
+    )?;
+    let treasury_ledger_data = TreasuryLedger {
+        validation_phrase: TREASURY_LEDGER_VAL_PHRASE,
+        redeem_fees: 0,
+        reward_share: 0,
+        team_share: 0,
+        registration_fees: 0,
+        reroll_fees: 0,
+        ingl_treasury_spent: 0,
+        rewards_treasury_spent: 0,
+        mint_authority_spent: 0,
+    };
+    treasury_ledger_data.serialize(&mut &mut treasury_ledger_account_info.data.borrow_mut()[..])?;
+    Ok(())
+}
+
+fn update_treasury_ledger(treasury_ledger_account_info: &AccountInfo, update: impl FnOnce(&mut TreasuryLedger)) -> ProgramResult {
+    let (_treasury_ledger_pubkey, _treasury_ledger_bump) = assert_pda_input(&[TREASURY_LEDGER_KEY.as_ref()], treasury_ledger_account_info);
+    let mut treasury_ledger_data = TreasuryLedger::decode(treasury_ledger_account_info)?;
+    update(&mut treasury_ledger_data);
+    treasury_ledger_data.serialize(&mut &mut treasury_ledger_account_info.data.borrow_mut()[..])?;
+    Ok(())
+}
 
-    if let Some(_) = global_gem_data.validator_list.iter().position(|x| x == validator_info.key){
-        Err(InglError::TooLate.utilize(Some("Already Created")))?
+/// Pays out of a treasury pda, which is left rent exempt, and records the spend in the treasury ledger.
+fn spend_treasury<'a>(
+    treasury: TreasuryAccount,
+    treasury_account_info: &AccountInfo<'a>,
+    recipient_account_info: &AccountInfo<'a>,
+    treasury_ledger_account_info: &AccountInfo<'a>,
+    lamports: u64,
+) -> ProgramResult {
+    let (treasury_pubkey, treasury_bump) = assert_pda_input(&[treasury.seed().as_ref()], treasury_account_info);
+    let spendable = treasury_account_info.lamports().saturating_sub(Rent::get()?.minimum_balance(treasury_account_info.data_len()));
+    if lamports > spendable {
+        msg!("The treasury can spend at most {:?} lamports", spendable);
+        Err(ProgramError::InsufficientFunds)?
     }
-    
-    global_gem_data.validator_list.push(*validator_info.key);
-    global_gem_data.serialize(&mut &mut global_gem_account_info.data.borrow_mut()[..])?;
+    invoke_signed(
+        &system_instruction::transfer(&treasury_pubkey, recipient_account_info.key, lamports),
+        &[treasury_account_info.clone(), recipient_account_info.clone()],
+        &[&[treasury.seed().as_ref(), &[treasury_bump]]],
+    )?;
+    update_treasury_ledger(treasury_ledger_account_info, |ledger| ledger.record_spend(treasury, lamports))
+}
 
+/// Lets the config authority spend from a treasury without going through a governance proposal.
+pub fn treasury_withdraw(_program_id: &Pubkey, accounts: &[AccountInfo], treasury: TreasuryAccount, lamports: u64) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let authority_account_info = next_account_info(account_info_iter)?;
+    let config_account_info = next_account_info(account_info_iter)?;
+    let treasury_account_info = next_account_info(account_info_iter)?;
+    let recipient_account_info = next_account_info(account_info_iter)?;
+    let treasury_ledger_account_info = next_account_info(account_info_iter)?;
+    let _system_program_account_info = next_account_info(account_info_iter)?;
+
+    assert_is_signer(authority_account_info)?;
+    let (_config_pubkey, _config_bump) = assert_pda_input(&[INGL_CONFIG_KEY.as_ref()], config_account_info);
+    let config_data = InglConfig::decode(config_account_info)?;
+    config_data.assert_not_paused()?;
+    if config_data.authority != *authority_account_info.key {
+        Err(InglError::Unauthorized.utilize(Some("treasury withdraw")))?
+    }
+    if lamports == 0 {
+        Err(InglError::BeyondBounds.utilize(Some("treasury withdraw lamports")))?
+    }
 
-    Ok(())
+    spend_treasury(treasury, treasury_account_info, recipient_account_info, treasury_ledger_account_info, lamports)
 }
 
-pub fn create_vote_account(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+pub fn create_governance_proposal(program_id: &Pubkey, accounts: &[AccountInfo], action: GovernanceAction) -> ProgramResult{
     let account_info_iter = &mut accounts.iter();
-    
-    let validator_info = next_account_info(account_info_iter)?;
-    let vote_account_info = next_account_info(account_info_iter)?;
-    let sysvar_rent_info = next_account_info(account_info_iter)?;
-    let sysvar_clock_info = next_account_info(account_info_iter)?;
-    let global_gem_account_info = next_account_info(account_info_iter)?;
+    let payer_account_info = next_account_info(account_info_iter)?;
+    let config_account_info = next_account_info(account_info_iter)?;
     let proposal_account_info = next_account_info(account_info_iter)?;
-    let mint_associated_token_account = next_account_info(account_info_iter)?;
-    let council_mint_account_info = next_account_info(account_info_iter)?;
-    let council_mint_authority_info = next_account_info(account_info_iter)?;
-    let system_program_account_info = next_account_info(account_info_iter)?;
-    let spl_token_program_account_info = next_account_info(account_info_iter)?;
-    let ingl_vote_data_account_info = next_account_info(account_info_iter)?;
-    let stake_account_info = next_account_info(account_info_iter)?;
-    let pd_pool_account_info = next_account_info(account_info_iter)?;
-    let sysvar_stake_history_info = next_account_info(account_info_iter)?;
-    let sysvar_stake_config_info = next_account_info(account_info_iter)?;
+    let mint_account_info = next_account_info(account_info_iter)?;
+    let associated_token_account_info = next_account_info(account_info_iter)?;
+    let gem_account_data_info = next_account_info(account_info_iter)?;
+    let _system_program_account_info = next_account_info(account_info_iter)?;
 
-    assert_program_owned(global_gem_account_info)?;
-    assert_program_owned(proposal_account_info)?;
-    assert_pubkeys_exactitude(sysvar_clock_info.key, &sysvar::clock::id())?;
-    assert_pubkeys_exactitude(sysvar_stake_history_info.key, &sysvar::stake_history::id())?;
-    assert_pubkeys_exactitude(sysvar_rent_info.key, &sysvar::rent::id())?;
-    assert_pubkeys_exactitude(system_program_account_info.key, &system_program::id())?;
-    assert_pubkeys_exactitude(spl_token_program_account_info.key, &spl_token::id())?;
-    assert_pubkeys_exactitude(
-        sysvar_stake_config_info.key,
-        &solana_program::stake::config::id(),
-    )?;
+    assert_is_signer(payer_account_info)?;
+    let (_config_pubkey, _config_bump) = assert_pda_input(&[INGL_CONFIG_KEY.as_ref()], config_account_info);
+    let mut config_data = InglConfig::decode(config_account_info)?;
+
+    // Only gem holders (or their voting delegates) can submit proposals.
+    assert_gem_voter(payer_account_info, mint_account_info, associated_token_account_info, gem_account_data_info)?;
+
+    match &action {
+        GovernanceAction::TreasurySpend{lamports, ..} if *lamports == 0 => Err(InglError::BeyondBounds.utilize(Some("treasury spend lamports")))?,
+        GovernanceAction::UpdateUriRegistry(update) if !update.is_valid() => Err(InglError::BeyondBounds.utilize(Some("uri length")))?,
+        GovernanceAction::UpdateConfig(ConfigUpdate::RegistrationRefundPercentage(percentage)) if *percentage > 100 => Err(InglError::BeyondBounds.utilize(Some("refund percentage")))?,
+        GovernanceAction::UpdateConfig(ConfigUpdate::RarityBoosts(boosts)) if boosts.iter().any(|boost| *boost > MAX_RARITY_BOOST) => Err(InglError::BeyondBounds.utilize(Some("rarity boost")))?,
+        GovernanceAction::UpdateConfig(ConfigUpdate::RarityFeeds(feeds)) if feeds.is_empty() || feeds.len() > MAX_RARITY_FEEDS => Err(InglError::BeyondBounds.utilize(Some("rarity feed count")))?,
+        GovernanceAction::UpdateConfig(ConfigUpdate::AddCustomClass(custom_class)) if !custom_class.is_valid() => Err(InglError::BeyondBounds.utilize(Some("custom class")))?,
+        GovernanceAction::UpdateConfig(ConfigUpdate::ReferralShare(share)) if *share > MAX_REFERRAL_SHARE => Err(InglError::BeyondBounds.utilize(Some("referral share")))?,
+        GovernanceAction::UpdateConfig(ConfigUpdate::RedeemFeeCurve(curve)) if !curve.is_valid() => Err(InglError::BeyondBounds.utilize(Some("redeem fee steps")))?,
+        GovernanceAction::UpdateConfig(ConfigUpdate::RedeemFeePercent(percent)) if *percent > 100 => Err(InglError::BeyondBounds.utilize(Some("redeem fee percent")))?,
+        GovernanceAction::UpdateConfig(ConfigUpdate::LiquidityReserveShare(share)) if *share > 100 => Err(InglError::BeyondBounds.utilize(Some("liquidity reserve share")))?,
+        GovernanceAction::UpdateConfig(ConfigUpdate::InstantExitDiscount(discount)) if *discount > MAX_INSTANT_EXIT_DISCOUNT => Err(InglError::BeyondBounds.utilize(Some("instant exit discount")))?,
+        GovernanceAction::SetRarityTable{weights, ..} if !RarityTable::are_weights_valid(weights) => Err(InglError::BeyondBounds.utilize(Some("rarity weights")))?,
+        _ => (),
+    }
 
-    let (_pd_pool_pubkey, _pd_pool_bump) = assert_pda_input(&[PD_POOL_KEY.as_ref()], pd_pool_account_info);
+    let numeration = config_data.governance_proposal_numeration;
+    let (expected_proposal_id, expected_proposal_bump) = assert_pda_input(&[GOVERNANCE_PROPOSAL_KEY.as_ref(), &numeration.to_be_bytes()], proposal_account_info);
 
-    let (_global_gem_pubkey, _global_gem_bump) = assert_pda_input(&[GLOBAL_GEM_KEY.as_ref()], global_gem_account_info);
+    let now = Clock::get()?.unix_timestamp as u32;
+    let voting_end = now.checked_add(config_data.governance_voting_period).unwrap();
+    let proposal_data = GovernanceProposal{
+        validation_phrase: GOVERNANCE_PROPOSAL_VAL_PHRASE,
+        numeration,
+        proposer: *payer_account_info.key,
+        action,
+        date_created: now,
+        voting_end,
+        executable_date: voting_end.checked_add(config_data.governance_timelock).unwrap(),
+        votes_for: 0,
+        votes_against: 0,
+        council_vetoes: 0,
+        date_executed: None,
+    };
 
-    let global_gem_data = GlobalGems::decode(global_gem_account_info)?;    
-    
-    let (expected_vote_data_pubkey, expected_vote_data_bump) = assert_pda_input(&[VOTE_DATA_ACCOUNT_KEY.as_ref(), vote_account_info.key.as_ref()], ingl_vote_data_account_info);
+    let space = proposal_data.try_to_vec()?.len();
+    invoke_signed(
+        &system_instruction::create_account(payer_account_info.key, &expected_proposal_id, Rent::get()?.minimum_balance(space), space as u64, program_id),
+        &[payer_account_info.clone(), proposal_account_info.clone()],
+        &[&[GOVERNANCE_PROPOSAL_KEY.as_ref(), &numeration.to_be_bytes(), &[expected_proposal_bump]]],
+    )?;
+    proposal_data.serialize(&mut &mut proposal_account_info.data.borrow_mut()[..])?;
 
-    let (_expected_proposal_id, _expected_proposal_bump) = assert_pda_input(&[PROPOSAL_KEY.as_ref(), &(global_gem_data.proposal_numeration-1).to_be_bytes()], proposal_account_info);
+    config_data.governance_proposal_numeration += 1;
+    config_data.serialize(&mut &mut config_account_info.data.borrow_mut()[..])?;
+    Ok(())
+}
 
-    let proposal_data = ValidatorProposal::decode(proposal_account_info)?;
+pub fn vote_governance_proposal(program_id: &Pubkey, accounts: &[AccountInfo], num_nfts: u8, approve: bool) -> ProgramResult{
+    let account_info_iter = &mut accounts.iter();
+    let payer_account_info = next_account_info(account_info_iter)?;
+    let proposal_account_info = next_account_info(account_info_iter)?;
+    let _system_program_account_info = next_account_info(account_info_iter)?;
 
-    assert_pubkeys_exactitude(validator_info.key, &proposal_data.winner.unwrap()).expect("validator id, not that expected");
+    assert_is_signer(payer_account_info)?;
+    let mut proposal_data = GovernanceProposal::decode(proposal_account_info)?;
+    if Clock::get()?.unix_timestamp as u32 >= proposal_data.voting_end {
+        Err(InglError::TooLate.utilize(Some("Governance proposal voting ended")))?
+    }
 
-    let (expected_vote_pubkey, expected_vote_pubkey_bump) = assert_pda_input(&[VOTE_ACCOUNT_KEY.as_ref(), &(global_gem_data.proposal_numeration-1).to_be_bytes()], vote_account_info);
-    let (authorized_withdrawer, _authorized_withdrawer_nonce) = Pubkey::find_program_address(&[AUTHORIZED_WITHDRAWER_KEY.as_ref()], program_id);
+    let mut vote_weight: u32 = 0;
+    for _ in 0..num_nfts{
+        let mint_account_info = next_account_info(account_info_iter)?;
+        let associated_token_account_info = next_account_info(account_info_iter)?;
+        let gem_account_data_info = next_account_info(account_info_iter)?;
+        let vote_record_account_info = next_account_info(account_info_iter)?;
 
-    let (_expected_mint_key, _expected_mint_bump) = assert_pda_input(&[COUNCIL_MINT_KEY.as_ref()], council_mint_account_info);
+        let mut gem_account_data = assert_gem_voter(payer_account_info, mint_account_info, associated_token_account_info, gem_account_data_info)?;
 
-    let (_expected_council_mint_authority_key, mint_authority_bump) = assert_pda_input(&[COUNCIL_MINT_AUTHORITY_KEY.as_ref()], council_mint_authority_info);
-    
-    let expected_assoc_key = get_associated_token_address(vote_account_info.key, council_mint_account_info.key);
-    assert_pubkeys_exactitude(&expected_assoc_key, mint_associated_token_account.key).expect("Council associated token is not that expected");
-    
-    let (_expected_stake_key, expected_stake_bump) = assert_pda_input(&[STAKE_ACCOUNT_KEY.as_ref(), expected_vote_pubkey.as_ref()], stake_account_info);
+        let (expected_vote_record_key, expected_vote_record_bump) = assert_pda_input(&[GOVERNANCE_VOTE_KEY.as_ref(), proposal_account_info.key.as_ref(), mint_account_info.key.as_ref()], vote_record_account_info);
+        if vote_record_account_info.lamports() > 0 {
+            Err(InglError::AlreadyVoted.utilize(Some(mint_account_info.key.to_string().as_ref() as &str)))?
+        }
+        invoke_signed(
+            &system_instruction::create_account(payer_account_info.key, &expected_vote_record_key, Rent::get()?.minimum_balance(1), 1, program_id),
+            &[payer_account_info.clone(), vote_record_account_info.clone()],
+            &[&[GOVERNANCE_VOTE_KEY.as_ref(), proposal_account_info.key.as_ref(), mint_account_info.key.as_ref(), &[expected_vote_record_bump]]],
+        )?;
 
-    let space = 10240; //Change this size to 100_000+ through reallocs
+        vote_weight = vote_weight.checked_add(gem_account_data.class.get_vote_weight()).unwrap();
+        gem_account_data.governance_vote_end = gem_account_data.governance_vote_end.max(Some(proposal_data.voting_end));
+        gem_account_data.serialize(&mut &mut gem_account_data_info.data.borrow_mut()[..])?;
+    }
 
-    let lamports = Rent::get()?.minimum_balance(space);
+    if approve {
+        proposal_data.votes_for = proposal_data.votes_for.checked_add(vote_weight).unwrap();
+    } else {
+        proposal_data.votes_against = proposal_data.votes_against.checked_add(vote_weight).unwrap();
+    }
+    proposal_data.serialize(&mut &mut proposal_account_info.data.borrow_mut()[..])?;
+    Ok(())
+}
 
-    invoke_signed(
-        &system_instruction::create_account(validator_info.key, &expected_vote_data_pubkey, lamports, space as u64, program_id),
-        &[validator_info.clone(), ingl_vote_data_account_info.clone()],
-        &[&[VOTE_DATA_ACCOUNT_KEY.as_ref(), vote_account_info.key.as_ref(), &[expected_vote_data_bump]]]
-    )?;
-    let ingl_vote_data = InglVoteAccountData {
-        validation_phrase: INGL_VOTE_ACCOUNT_DATA_VAL_PHRASE,
-        total_delegated: 0,
-        last_withdraw_epoch: Clock::get()?.epoch,
-        dealloced: 0,
-        pending_validator_rewards: None,
-        validator_id: *validator_info.key,
-        pending_delegation_total: 0,
-        is_t_stake_initialized: false,
-        vote_rewards: Vec::new(),
-        last_total_staked: LAMPORTS_PER_SOL + Rent::get()?.minimum_balance(std::mem::size_of::<StakeState>() as usize),
-    };
+pub fn execute_proposal(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult{
+    let account_info_iter = &mut accounts.iter();
+    let payer_account_info = next_account_info(account_info_iter)?;
+    let config_account_info = next_account_info(account_info_iter)?;
+    let proposal_account_info = next_account_info(account_info_iter)?;
 
-    ingl_vote_data.serialize(&mut &mut ingl_vote_data_account_info.data.borrow_mut()[..])?;
+    let (_config_pubkey, _config_bump) = assert_pda_input(&[INGL_CONFIG_KEY.as_ref()], config_account_info);
+    let mut config_data = InglConfig::decode(config_account_info)?;
+    let mut proposal_data = GovernanceProposal::decode(proposal_account_info)?;
+    let (_expected_proposal_id, _expected_proposal_bump) = assert_pda_input(&[GOVERNANCE_PROPOSAL_KEY.as_ref(), &proposal_data.numeration.to_be_bytes()], proposal_account_info);
 
-    invoke(
-        &spl_associated_token_account::instruction::create_associated_token_account(
-            validator_info.key,
-            vote_account_info.key,
-            council_mint_account_info.key,
-        ),
-        &[
-            validator_info.clone(),
-            mint_associated_token_account.clone(),
-            vote_account_info.clone(),
-            council_mint_account_info.clone(),
-            system_program_account_info.clone(),
-            spl_token_program_account_info.clone(),
-        ],
-    )?;
+    if proposal_data.date_executed.is_some() {
+        Err(InglError::TooLate.utilize(Some("Proposal already executed")))?
+    }
+    let now = Clock::get()?.unix_timestamp as u32;
+    if now < proposal_data.executable_date {
+        Err(InglError::TooEarly.utilize(Some("Proposal timelock")))?
+    }
+    if !proposal_data.is_passed(config_data.governance_quorum) {
+        Err(InglError::ProposalNotPassed.utilize(Some("execute_proposal")))?
+    }
+    if config_data.council_veto_threshold > 0 && proposal_data.council_vetoes >= config_data.council_veto_threshold {
+        Err(InglError::ProposalVetoed.utilize(Some("execute_proposal")))?
+    }
 
-    invoke_signed(
-        &spl_token::instruction::mint_to(&spl_token::id(), council_mint_account_info.key, mint_associated_token_account.key, council_mint_authority_info.key, &[], 1)?,
-        &[
-            council_mint_account_info.clone(),
-            mint_associated_token_account.clone(),
-            council_mint_authority_info.clone(),
-        ],
-        &[&[COUNCIL_MINT_AUTHORITY_KEY.as_ref(), &[mint_authority_bump]]]
-    )?;
+    match proposal_data.action.clone() {
+        GovernanceAction::UpdateConfig(update) => config_data.apply(update)?,
+        GovernanceAction::TreasurySpend { treasury, recipient, lamports } => {
+            let treasury_account_info = next_account_info(account_info_iter)?;
+            let recipient_account_info = next_account_info(account_info_iter)?;
+            let _system_program_account_info = next_account_info(account_info_iter)?;
+            let treasury_ledger_account_info = next_account_info(account_info_iter)?;
 
-    let vote_init = VoteInit {
-        node_pubkey: *validator_info.key,
-        authorized_voter: *validator_info.key,
-        commission: 10,
-        authorized_withdrawer,
-    };
-    invoke_signed(
-        &vote_create_account(validator_info.key, vote_account_info.key),
-        &[validator_info.clone(), vote_account_info.clone()],
-        &[&[
-            VOTE_ACCOUNT_KEY.as_ref(),
-            &(global_gem_data.proposal_numeration-1).to_be_bytes(),
-            &[expected_vote_pubkey_bump],
-        ]],
-    )?;
-    invoke(
-        &vote_initialize_account(vote_account_info.key, &vote_init),
-        &[
-            vote_account_info.clone(),
-            sysvar_rent_info.clone(),
-            sysvar_clock_info.clone(),
-            validator_info.clone(),
-        ],
-    )?;
+            assert_pubkeys_exactitude(&recipient, recipient_account_info.key)?;
+            spend_treasury(treasury, treasury_account_info, recipient_account_info, treasury_ledger_account_info, lamports)?;
+        }
+        GovernanceAction::UpdateUriRegistry(update) => {
+            let uri_registry_account_info = next_account_info(account_info_iter)?;
+            let _system_program_account_info = next_account_info(account_info_iter)?;
 
-    let authorized = &Authorized {
-        staker: *pd_pool_account_info.key,
-        withdrawer: *pd_pool_account_info.key,
-    };
-    let lockup = &Lockup {
-        unix_timestamp: 0,
-        epoch: 0,
-        custodian: *pd_pool_account_info.key,
-    };
-    
-    let lamports = LAMPORTS_PER_SOL + Rent::get()?.minimum_balance(std::mem::size_of::<StakeState>() as usize);
-    msg!("creating account");
-    invoke_signed(
-        &system_instruction::create_account(
-            validator_info.key,
-            stake_account_info.key,
-            lamports,
-            std::mem::size_of::<StakeState>() as u64,
-            &stake::program::id(),
-        ),
-        &[validator_info.clone(), stake_account_info.clone()],
-        &[&[
-            STAKE_ACCOUNT_KEY.as_ref(),
-            expected_vote_pubkey.as_ref(),
-            &[expected_stake_bump],
-        ]],
-    )?;
-    msg!("Initializing stake");
-    invoke(
-        &solana_program::stake::instruction::initialize(stake_account_info.key, authorized, lockup),
-        &[stake_account_info.clone(), sysvar_rent_info.clone()],
-    )?;
+            apply_uri_registry_update(program_id, payer_account_info, uri_registry_account_info, update)?;
+        }
+        GovernanceAction::RotateValidator { vote_account, new_validator_id } => {
+            let vote_account_info = next_account_info(account_info_iter)?;
+            let ingl_vote_data_account_info = next_account_info(account_info_iter)?;
+            let new_validator_info = next_account_info(account_info_iter)?;
+            let authorized_withdrawer_info = next_account_info(account_info_iter)?;
+
+            assert_pubkeys_exactitude(&vote_account, vote_account_info.key)?;
+            assert_pubkeys_exactitude(&new_validator_id, new_validator_info.key)?;
+            assert_is_signer(new_validator_info)?;
+            let (_expected_vote_data_pubkey, _expected_vote_data_bump) = assert_pda_input(&[VOTE_DATA_ACCOUNT_KEY.as_ref(), vote_account_info.key.as_ref()], ingl_vote_data_account_info);
+            let (authorized_withdrawer, authorized_withdrawer_bump) = assert_pda_input(&[AUTHORIZED_WITHDRAWER_KEY.as_ref()], authorized_withdrawer_info);
+
+            let mut ingl_vote_account_data = InglVoteAccountData::decode(ingl_vote_data_account_info)?;
+            if ingl_vote_account_data.pending_validator_rewards.is_some() {
+                Err(InglError::TooEarly.utilize(Some("Rebalancing is ongoing")))?
+            }
+
+            invoke_signed(
+                &vote_update_validator_identity(vote_account_info.key, &authorized_withdrawer, new_validator_info.key),
+                &[
+                    vote_account_info.clone(),
+                    new_validator_info.clone(),
+                    authorized_withdrawer_info.clone(),
+                ],
+                &[&[AUTHORIZED_WITHDRAWER_KEY.as_ref(), &[authorized_withdrawer_bump]]],
+            )?;
 
+            ingl_vote_account_data.validator_id = new_validator_id;
+            ingl_vote_account_data.serialize(&mut &mut ingl_vote_data_account_info.data.borrow_mut()[..])?;
+        }
+        GovernanceAction::SetRarityTable { class, weights } => {
+            let rarity_table_account_info = next_account_info(account_info_iter)?;
+            let _system_program_account_info = next_account_info(account_info_iter)?;
+
+            let class_seed = class.get_seed();
+            let (rarity_table_pubkey, rarity_table_bump) = assert_pda_input(&[RARITY_TABLE_KEY.as_ref(), &class_seed], rarity_table_account_info);
+            if rarity_table_account_info.data_is_empty() {
+                let space = RarityTable::space();
+                invoke_signed(
+                    &system_instruction::create_account(payer_account_info.key, &rarity_table_pubkey, Rent::get()?.minimum_balance(space), space as u64, program_id),
+                    &[payer_account_info.clone(), rarity_table_account_info.clone()],
+                    &[&[RARITY_TABLE_KEY.as_ref(), &class_seed, &[rarity_table_bump]]],
+                )?;
+            }
+            let rarity_table_data = RarityTable {
+                validation_phrase: RARITY_TABLE_VAL_PHRASE,
+                class,
+                weights,
+            };
+            rarity_table_data.serialize(&mut &mut rarity_table_account_info.data.borrow_mut()[..])?;
+        }
+    }
 
+    proposal_data.date_executed = Some(now);
+    proposal_data.serialize(&mut &mut proposal_account_info.data.borrow_mut()[..])?;
+    config_data.serialize(&mut &mut config_account_info.data.borrow_mut()[..])?;
     Ok(())
 }
 
-pub fn allocate_sol(_program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
-    let account_info_iter = &mut accounts.iter();
-    let payer_account_info = next_account_info(account_info_iter)?;
-    let mint_account_info = next_account_info(account_info_iter)?;
-    let gem_account_data_info = next_account_info(account_info_iter)?;
-    let associated_token_account_info = next_account_info(account_info_iter)?;
-    let global_gem_account_info = next_account_info(account_info_iter)?;
-    let pd_pool_account_info = next_account_info(account_info_iter)?;
-    let minting_pool_account_info = next_account_info(account_info_iter)?;
-
-    assert_program_owned(gem_account_data_info)?;
-    assert_program_owned(global_gem_account_info)?;
-    assert_owned_by(mint_account_info, &spl_program::id())?;
-    assert_owned_by(associated_token_account_info, &spl_program::id())?;
-
-    assert_is_signer(payer_account_info).unwrap();
-
-    let (_gem_account_pubkey, _gem_account_bump) = assert_pda_input(&[GEM_ACCOUNT_CONST.as_ref(), mint_account_info.key.as_ref()], gem_account_data_info);
+/// Checks that `validator_info` signs for an ingl vote account whose council token is still held in its associated token account.
+fn assert_council_member(
+    program_id: &Pubkey,
+    validator_info: &AccountInfo,
+    vote_account_info: &AccountInfo,
+    ingl_vote_data_account_info: &AccountInfo,
+    council_token_account_info: &AccountInfo,
+) -> ProgramResult {
+    assert_is_signer(validator_info)?;
+    let (_expected_vote_data_pubkey, _expected_vote_data_bump) = assert_pda_input(&[VOTE_DATA_ACCOUNT_KEY.as_ref(), vote_account_info.key.as_ref()], ingl_vote_data_account_info);
+    let ingl_vote_account_data = InglVoteAccountData::decode(ingl_vote_data_account_info)?;
+    assert_pubkeys_exactitude(&ingl_vote_account_data.validator_id, validator_info.key).expect("validator_id");
 
+    let (council_mint_key, _council_mint_bump) = Pubkey::find_program_address(&[COUNCIL_MINT_KEY.as_ref()], program_id);
+    assert_owned_by(council_token_account_info, &spl_program::id())?;
     assert_pubkeys_exactitude(
-        &get_associated_token_address(payer_account_info.key, mint_account_info.key),
-        associated_token_account_info.key,
+        &get_associated_token_address(vote_account_info.key, &council_mint_key),
+        council_token_account_info.key,
     )
-    .expect("Error: @associated_token_address");
-
-    let associated_token_address_data =
-        Account::unpack(&associated_token_account_info.data.borrow())?;
-    if associated_token_address_data.amount != 1 {
+    .expect("Council associated token is not that expected");
+    let council_token_data = Account::unpack(&council_token_account_info.data.borrow())?;
+    if council_token_data.amount < 1 {
         Err(ProgramError::InsufficientFunds)?
     }
+    Ok(())
+}
 
-    let (_global_gem_pubkey, _global_gem_bump) = assert_pda_input(&[GLOBAL_GEM_KEY.as_ref()], global_gem_account_info);
-
-    let (pd_pool_pubkey, _pd_pool_bump) = assert_pda_input(&[PD_POOL_KEY.as_ref()], pd_pool_account_info);
-
-    let mut gem_account_data: GemAccountV0_0_1 = GemAccountV0_0_1::validate(
-        GemAccountVersions::decode_unchecked(&gem_account_data_info.data.borrow())?,
-    )?;
-    let mut global_gem_account_data = GlobalGems::decode(global_gem_account_info)?;
+pub fn veto_governance_proposal(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult{
+    let account_info_iter = &mut accounts.iter();
+    let validator_info = next_account_info(account_info_iter)?;
+    let vote_account_info = next_account_info(account_info_iter)?;
+    let ingl_vote_data_account_info = next_account_info(account_info_iter)?;
+    let council_token_account_info = next_account_info(account_info_iter)?;
+    let proposal_account_info = next_account_info(account_info_iter)?;
+    let veto_record_account_info = next_account_info(account_info_iter)?;
+    let _system_program_account_info = next_account_info(account_info_iter)?;
 
-    let (minting_pool_id, minting_pool_bump) = assert_pda_input(&[INGL_MINTING_POOL_KEY.as_ref()], minting_pool_account_info);
+    assert_council_member(program_id, validator_info, vote_account_info, ingl_vote_data_account_info, council_token_account_info)?;
 
-    match gem_account_data.funds_location {
-        FundsLocation::MintingPool => {
-            gem_account_data.funds_location = FundsLocation::PDPool;
-        }
-        _ => Err(InglError::InvalidFundsLocation.utilize(Some("gem's funds location.")))?,
+    let mut proposal_data = GovernanceProposal::decode(proposal_account_info)?;
+    if proposal_data.date_executed.is_some() || Clock::get()?.unix_timestamp as u32 >= proposal_data.executable_date {
+        Err(InglError::TooLate.utilize(Some("Vetoes are only possible before the timelock ends")))?
     }
 
-    let mint_cost = gem_account_data.class.clone().get_class_lamports();
-    //tranfer token from mint_pool, to pd_pool
+    let (expected_veto_record_key, expected_veto_record_bump) = assert_pda_input(&[COUNCIL_VETO_KEY.as_ref(), proposal_account_info.key.as_ref(), vote_account_info.key.as_ref()], veto_record_account_info);
+    if veto_record_account_info.lamports() > 0 {
+        Err(InglError::AlreadyVoted.utilize(Some("council veto")))?
+    }
     invoke_signed(
-        &system_instruction::transfer(&minting_pool_id, &pd_pool_pubkey, mint_cost),
-        &[
-            minting_pool_account_info.clone(),
-            pd_pool_account_info.clone(),
-        ],
-        &[&[INGL_MINTING_POOL_KEY.as_ref(), &[minting_pool_bump]]],
+        &system_instruction::create_account(validator_info.key, &expected_veto_record_key, Rent::get()?.minimum_balance(1), 1, program_id),
+        &[validator_info.clone(), veto_record_account_info.clone()],
+        &[&[COUNCIL_VETO_KEY.as_ref(), proposal_account_info.key.as_ref(), vote_account_info.key.as_ref(), &[expected_veto_record_bump]]],
     )?;
 
-    let clock = Clock::get()?;
-
-    gem_account_data.date_allocated = Some(clock.unix_timestamp as u32);
-    gem_account_data.redeemable_date = clock.unix_timestamp as u32 + ALLOCATE_LOCK_TIME;
-
-    global_gem_account_data.pd_pool_total += mint_cost;
-
-    gem_account_data.serialize(&mut &mut gem_account_data_info.data.borrow_mut()[..])?;
-    global_gem_account_data.serialize(&mut &mut global_gem_account_info.data.borrow_mut()[..])?;
-
+    proposal_data.council_vetoes = proposal_data.council_vetoes.checked_add(1).unwrap();
+    proposal_data.serialize(&mut &mut proposal_account_info.data.borrow_mut()[..])?;
     Ok(())
 }
 
-pub fn deallocate_sol(_program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+/// Emergency switch, halting minting and gem changes while set. Governance proposals keep running, so holders can lift a pause.
+/// Requires `council_pause_threshold` distinct council members to sign, or a majority of the council tokens in circulation
+/// when the threshold is 0. Each member is passed as a (validator, vote account, ingl vote data, council token account) group.
+pub fn set_pause(program_id: &Pubkey, accounts: &[AccountInfo], is_paused: bool, num_members: u8) -> ProgramResult{
     let account_info_iter = &mut accounts.iter();
-    let payer_account_info = next_account_info(account_info_iter)?;
-    let mint_account_info = next_account_info(account_info_iter)?;
-    let gem_account_data_info = next_account_info(account_info_iter)?;
-    let associated_token_account_info = next_account_info(account_info_iter)?;
-    let global_gem_account_info = next_account_info(account_info_iter)?;
-    let pd_pool_account_info = next_account_info(account_info_iter)?;
-    let minting_pool_account_info = next_account_info(account_info_iter)?;
+    let config_account_info = next_account_info(account_info_iter)?;
+    let council_mint_account_info = next_account_info(account_info_iter)?;
 
-    assert_program_owned(gem_account_data_info)?;
-    assert_program_owned(global_gem_account_info)?;
-    assert_owned_by(mint_account_info, &spl_program::id())?;
-    assert_owned_by(associated_token_account_info, &spl_program::id())?;
+    let (_config_pubkey, _config_bump) = assert_pda_input(&[INGL_CONFIG_KEY.as_ref()], config_account_info);
+    let mut config_data = InglConfig::decode(config_account_info)?;
+    let (_council_mint_key, _council_mint_bump) = assert_pda_input(&[COUNCIL_MINT_KEY.as_ref()], council_mint_account_info);
+    assert_owned_by(council_mint_account_info, &spl_program::id())?;
+    let required_approvals = match config_data.council_pause_threshold {
+        0 => (spl_token::state::Mint::unpack(&council_mint_account_info.data.borrow())?.supply / 2 + 1) as u32,
+        threshold => threshold,
+    };
 
-    assert_is_signer(payer_account_info).unwrap();
+    let mut approving_vote_accounts: Vec<Pubkey> = Vec::new();
+    for _ in 0..num_members{
+        let validator_info = next_account_info(account_info_iter)?;
+        let vote_account_info = next_account_info(account_info_iter)?;
+        let ingl_vote_data_account_info = next_account_info(account_info_iter)?;
+        let council_token_account_info = next_account_info(account_info_iter)?;
 
-    let (_gem_account_pubkey, _gem_account_bump) = assert_pda_input(&[GEM_ACCOUNT_CONST.as_ref(), mint_account_info.key.as_ref()], gem_account_data_info);
+        assert_council_member(program_id, validator_info, vote_account_info, ingl_vote_data_account_info, council_token_account_info)?;
+        if approving_vote_accounts.contains(vote_account_info.key) {
+            Err(InglError::AlreadyVoted.utilize(Some("council member listed twice")))?
+        }
+        approving_vote_accounts.push(*vote_account_info.key);
+    }
+    if (approving_vote_accounts.len() as u32) < required_approvals {
+        Err(InglError::Unauthorized.utilize(Some("Not enough council approvals")))?
+    }
 
-    assert_pubkeys_exactitude(
-        &get_associated_token_address(payer_account_info.key, mint_account_info.key),
-        associated_token_account_info.key,
-    )
-    .expect("Error: @associated_token_address");
+    config_data.is_paused = is_paused;
+    config_data.serialize(&mut &mut config_account_info.data.borrow_mut()[..])?;
+    Ok(())
+}
 
-    let associated_token_address_data =
-        Account::unpack(&associated_token_account_info.data.borrow())?;
-    if associated_token_address_data.amount != 1 {
-        Err(ProgramError::InsufficientFunds)?
+/// The registry only exists once a first uri has been set. Until then, gems keep the artwork the program was deployed with.
+fn load_uri_registry(uri_registry_account_info: &AccountInfo) -> Result<MetadataUriRegistry, ProgramError> {
+    let (_uri_registry_pubkey, _uri_registry_bump) = assert_pda_input(&[METADATA_URI_REGISTRY_KEY.as_ref()], uri_registry_account_info);
+    if uri_registry_account_info.data_is_empty() {
+        Ok(MetadataUriRegistry::new())
+    } else {
+        MetadataUriRegistry::decode(uri_registry_account_info)
     }
+}
 
-    let (_global_gem_pubkey, _global_gem_bump) = assert_pda_input(&[GLOBAL_GEM_KEY.as_ref()], global_gem_account_info);
+/// Applies the update to the registry, creating the registry account on its first update.
+fn apply_uri_registry_update<'a>(
+    program_id: &Pubkey,
+    payer_account_info: &AccountInfo<'a>,
+    uri_registry_account_info: &AccountInfo<'a>,
+    update: UriRegistryUpdate,
+) -> ProgramResult {
+    let mut uri_registry_data = load_uri_registry(uri_registry_account_info)?;
+    if uri_registry_account_info.data_is_empty() {
+        let (uri_registry_pubkey, uri_registry_bump) = assert_pda_input(&[METADATA_URI_REGISTRY_KEY.as_ref()], uri_registry_account_info);
+        let space = MetadataUriRegistry::space();
+        invoke_signed(
+            &system_instruction::create_account(payer_account_info.key, &uri_registry_pubkey, Rent::get()?.minimum_balance(space), space as u64, program_id),
+            &[payer_account_info.clone(), uri_registry_account_info.clone()],
+            &[&[METADATA_URI_REGISTRY_KEY.as_ref(), &[uri_registry_bump]]],
+        )?;
+    }
+    uri_registry_data.apply(update)?;
+    uri_registry_data.serialize(&mut &mut uri_registry_account_info.data.borrow_mut()[..])?;
+    Ok(())
+}
 
-    let (pd_pool_pubkey, pd_pool_bump) = assert_pda_input(&[PD_POOL_KEY.as_ref()], pd_pool_account_info);
+/// Lets the config authority change the artwork directly, next to the governance route.
+pub fn update_uri_registry(program_id: &Pubkey, accounts: &[AccountInfo], update: UriRegistryUpdate) -> ProgramResult{
+    let account_info_iter = &mut accounts.iter();
+    let authority_account_info = next_account_info(account_info_iter)?;
+    let config_account_info = next_account_info(account_info_iter)?;
+    let uri_registry_account_info = next_account_info(account_info_iter)?;
+    let _system_program_account_info = next_account_info(account_info_iter)?;
+
+    assert_is_signer(authority_account_info)?;
+    let (_config_pubkey, _config_bump) = assert_pda_input(&[INGL_CONFIG_KEY.as_ref()], config_account_info);
+    let config_data = InglConfig::decode(config_account_info)?;
+    if config_data.authority != *authority_account_info.key {
+        Err(InglError::Unauthorized.utilize(Some("config authority")))?
+    }
+    if !update.is_valid() {
+        Err(InglError::BeyondBounds.utilize(Some("uri length")))?
+    }
 
-    let mut gem_account_data: GemAccountV0_0_1 = GemAccountV0_0_1::validate(
-        GemAccountVersions::decode_unchecked(&gem_account_data_info.data.borrow())?,
-    )?;
-    let mut global_gem_account_data = GlobalGems::decode(global_gem_account_info)?;
+    apply_uri_registry_update(program_id, authority_account_info, uri_registry_account_info, update)
+}
 
-    let (minting_pool_id, _minting_pool_bump) = assert_pda_input(&[INGL_MINTING_POOL_KEY.as_ref()], minting_pool_account_info);
+/// Lets the config authority set the launch controls of gem mints. Mint counters carry over for classes that stay capped.
+pub fn set_mint_schedule(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    class_caps: Vec<ClassCap>,
+    allowlist_phase: Option<MintPhase>,
+    public_phase: Option<MintPhase>,
+    wallet_cap: Option<u32>,
+    allowlist_root: Option<[u8; 32]>,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let authority_account_info = next_account_info(account_info_iter)?;
+    let config_account_info = next_account_info(account_info_iter)?;
+    let mint_schedule_account_info = next_account_info(account_info_iter)?;
+    let _system_program_account_info = next_account_info(account_info_iter)?;
+
+    assert_is_signer(authority_account_info)?;
+    let (_config_pubkey, _config_bump) = assert_pda_input(&[INGL_CONFIG_KEY.as_ref()], config_account_info);
+    let config_data = InglConfig::decode(config_account_info)?;
+    if config_data.authority != *authority_account_info.key {
+        Err(InglError::Unauthorized.utilize(Some("config authority")))?
+    }
 
-    match gem_account_data.funds_location {
-        FundsLocation::PDPool => {
-            gem_account_data.funds_location = FundsLocation::MintingPool;
+    if class_caps.len() > MAX_SCHEDULED_CLASSES {
+        Err(InglError::BeyondBounds.utilize(Some("class cap count")))?
+    }
+    for (i, cap) in class_caps.iter().enumerate() {
+        if class_caps[..i].iter().any(|other| other.class == cap.class) {
+            Err(InglError::BeyondBounds.utilize(Some("duplicate class cap")))?
         }
-        _ => Err(InglError::InvalidFundsLocation.utilize(Some("gem's funds location.")))?,
+    }
+    if [allowlist_phase, public_phase].iter().flatten().any(|phase| !phase.is_valid()) {
+        Err(InglError::BeyondBounds.utilize(Some("mint phase")))?
     }
 
-    let mint_cost = gem_account_data.class.clone().get_class_lamports();
-    //tranfer token from mint_pool, to pd_pool
-    invoke_signed(
-        &system_instruction::transfer(&pd_pool_pubkey, &minting_pool_id, mint_cost),
-        &[
-            pd_pool_account_info.clone(),
-            minting_pool_account_info.clone(),
-        ],
-        &[&[PD_POOL_KEY.as_ref(), &[pd_pool_bump]]],
-    )?;
+    let (mint_schedule_pubkey, mint_schedule_bump) = assert_pda_input(&[MINT_SCHEDULE_KEY.as_ref()], mint_schedule_account_info);
+    let mut mint_schedule_data = if mint_schedule_account_info.data_is_empty() {
+        let space = MintSchedule::space();
+        invoke_signed(
+            &system_instruction::create_account(authority_account_info.key, &mint_schedule_pubkey, Rent::get()?.minimum_balance(space), space as u64, program_id),
+            &[authority_account_info.clone(), mint_schedule_account_info.clone()],
+            &[&[MINT_SCHEDULE_KEY.as_ref(), &[mint_schedule_bump]]],
+        )?;
+        MintSchedule {
+            validation_phrase: MINT_SCHEDULE_VAL_PHRASE,
+            class_supplies: Vec::new(),
+            allowlist_phase: None,
+            public_phase: None,
+            wallet_cap: None,
+            allowlist_root: None,
+        }
+    } else {
+        MintSchedule::decode(mint_schedule_account_info)?
+    };
 
-    let clock = Clock::get()?;
+    mint_schedule_data.set_class_caps(class_caps);
+    mint_schedule_data.allowlist_phase = allowlist_phase;
+    mint_schedule_data.public_phase = public_phase;
+    mint_schedule_data.wallet_cap = wallet_cap;
+    mint_schedule_data.allowlist_root = allowlist_root;
+    mint_schedule_data.serialize(&mut &mut mint_schedule_account_info.data.borrow_mut()[..])?;
+    Ok(())
+}
 
-    if (clock.unix_timestamp as u32) < gem_account_data.redeemable_date {
-        Err(InglError::TooEarly.utilize(Some("Deallocating Earlier than allowed")))?;
+fn assert_mint_phase_active(phase: Option<MintPhase>, now: u32, keyword: &str) -> ProgramResult {
+    match phase {
+        Some(phase) if phase.is_active(now) => Ok(()),
+        Some(phase) if now >= phase.end => Err(InglError::TooLate.utilize(Some(keyword))),
+        _ => Err(InglError::TooEarly.utilize(Some(keyword))),
     }
-    global_gem_account_data.pd_pool_total -= mint_cost;
+}
 
-    gem_account_data.serialize(&mut &mut gem_account_data_info.data.borrow_mut()[..])?;
-    global_gem_account_data.serialize(&mut &mut global_gem_account_info.data.borrow_mut()[..])?;
+/// Checks a mint against the mint schedule, if one is set, and counts it towards the class supply and the payer's wallet cap.
+/// Allowlist mints carry the payer's allowlisted max_count and its proof, and are only open during the allowlist phase.
+fn record_scheduled_mint<'a>(
+    program_id: &Pubkey,
+    payer_account_info: &AccountInfo<'a>,
+    mint_schedule_account_info: &AccountInfo<'a>,
+    mint_wallet_record_account_info: &AccountInfo<'a>,
+    class: Class,
+    allowlist_claim: Option<(u32, &[[u8; 32]])>,
+) -> ProgramResult {
+    let (_mint_schedule_pubkey, _mint_schedule_bump) = assert_pda_input(&[MINT_SCHEDULE_KEY.as_ref()], mint_schedule_account_info);
+    if mint_schedule_account_info.data_is_empty() {
+        if allowlist_claim.is_some() {
+            Err(InglError::TooEarly.utilize(Some("allowlist mint phase")))?
+        }
+        return Ok(());
+    }
+    let mut mint_schedule_data = MintSchedule::decode(mint_schedule_account_info)?;
+
+    let now = Clock::get()?.unix_timestamp as u32;
+    match allowlist_claim {
+        Some((max_count, proof)) => {
+            assert_mint_phase_active(mint_schedule_data.allowlist_phase, now, "allowlist mint phase")?;
+            let leaf = allowlist_leaf(payer_account_info.key, class, max_count);
+            if !matches!(&mint_schedule_data.allowlist_root, Some(root) if verify_allowlist_proof(root, leaf, proof)) {
+                Err(InglError::InvalidAllowlistProof.utilize(Some(&payer_account_info.key.to_string())))?
+            }
+        }
+        None => assert_mint_phase_active(mint_schedule_data.public_phase, now, "public mint phase")?,
+    }
+
+    mint_schedule_data.record_mint(class)?;
+    mint_schedule_data.serialize(&mut &mut mint_schedule_account_info.data.borrow_mut()[..])?;
+
+    let (mint_wallet_record_pubkey, mint_wallet_record_bump) = assert_pda_input(&[MINT_WALLET_RECORD_KEY.as_ref(), payer_account_info.key.as_ref()], mint_wallet_record_account_info);
+    let mut mint_wallet_record_data = if mint_wallet_record_account_info.data_is_empty() {
+        let space = MintWalletRecord::space();
+        invoke_signed(
+            &system_instruction::create_account(payer_account_info.key, &mint_wallet_record_pubkey, Rent::get()?.minimum_balance(space), space as u64, program_id),
+            &[payer_account_info.clone(), mint_wallet_record_account_info.clone()],
+            &[&[MINT_WALLET_RECORD_KEY.as_ref(), payer_account_info.key.as_ref(), &[mint_wallet_record_bump]]],
+        )?;
+        MintWalletRecord {
+            validation_phrase: MINT_WALLET_RECORD_VAL_PHRASE,
+            minted: 0,
+            allowlist_minted: 0,
+        }
+    } else {
+        MintWalletRecord::decode(mint_wallet_record_account_info)?
+    };
 
+    mint_wallet_record_data.minted += 1;
+    if matches!(mint_schedule_data.wallet_cap, Some(wallet_cap) if mint_wallet_record_data.minted > wallet_cap) {
+        Err(InglError::BeyondBounds.utilize(Some("wallet mint cap")))?
+    }
+    if let Some((max_count, _proof)) = allowlist_claim {
+        mint_wallet_record_data.allowlist_minted += 1;
+        if mint_wallet_record_data.allowlist_minted > max_count {
+            Err(InglError::BeyondBounds.utilize(Some("allowlist max count")))?
+        }
+    }
+    mint_wallet_record_data.serialize(&mut &mut mint_wallet_record_account_info.data.borrow_mut()[..])?;
     Ok(())
 }
 
-pub fn mint_nft(program_id: &Pubkey, accounts: &[AccountInfo], class: Class) -> ProgramResult {
+/// Opens the payer's referrer account, which gem buyers can then pass to `mint_nft` to credit the payer.
+pub fn register_referrer(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
     let account_info_iter = &mut accounts.iter();
     let payer_account_info = next_account_info(account_info_iter)?;
-    let mint_account_info = next_account_info(account_info_iter)?;
-    let mint_authority_account_info = next_account_info(account_info_iter)?;
-    let associated_token_account_info = next_account_info(account_info_iter)?;
-    let spl_token_program_account_info = next_account_info(account_info_iter)?;
-    let sysvar_rent_account_info = next_account_info(account_info_iter)?;
-    let system_program_account_info = next_account_info(account_info_iter)?;
-    let metadata_account_info = next_account_info(account_info_iter)?;
-    let minting_pool_account_info = next_account_info(account_info_iter)?;
-    let global_gem_account_info = next_account_info(account_info_iter)?;
-    let gem_account_info = next_account_info(account_info_iter)?;
-    let ingl_edition_account_info = next_account_info(account_info_iter)?;
-    let nft_edition_account_info = next_account_info(account_info_iter)?;
-    let ingl_collection_mint_info = next_account_info(account_info_iter)?;
-    let ingl_collection_account_info = next_account_info(account_info_iter)?;
-
-    assert_program_owned(global_gem_account_info)?;
-    assert_owned_by(ingl_edition_account_info, &metaplex::id())?;
-    assert_owned_by(ingl_collection_account_info, &metaplex::id())?;
-    assert_owned_by(ingl_collection_mint_info, &spl_program::id())?;
-    assert_pubkeys_exactitude(&system_program::id(), system_program_account_info.key).expect("Error: @system_program_account_info");
-    assert_pubkeys_exactitude(&spl_token::id(), spl_token_program_account_info.key).expect("Error: @spl_token_program_account_info");
-    assert_pubkeys_exactitude(sysvar_rent_account_info.key, &sysvar::rent::id())?;
+    let referrer_account_info = next_account_info(account_info_iter)?;
+    let _system_program_account_info = next_account_info(account_info_iter)?;
 
-    let (_global_gem_pubkey, _global_gem_bump) = assert_pda_input(&[GLOBAL_GEM_KEY.as_ref()], global_gem_account_info);
-    let (minting_pool_id, _minting_pool_bump) = assert_pda_input(&[INGL_MINTING_POOL_KEY.as_ref()], minting_pool_account_info);
-    let (mint_authority_key, mint_authority_bump) = assert_pda_input(&[INGL_MINT_AUTHORITY_KEY.as_ref()], mint_authority_account_info);
-    let (ingl_nft_collection_key, _ingl_nft_bump) = assert_pda_input(&[INGL_NFT_COLLECTION_KEY.as_ref()], ingl_collection_mint_info);
-
-    
-    let clock = Clock::get()?;
-    // Getting timestamp
-    let current_timestamp = clock.unix_timestamp as u32;
-
-    let (gem_account_pubkey, gem_account_bump) = assert_pda_input(&[GEM_ACCOUNT_CONST.as_ref(), mint_account_info.key.as_ref()], gem_account_info);
-    let space = 500;
-    let rent_lamports = Rent::get()?.minimum_balance(space);
+    assert_is_signer(payer_account_info)?;
+    let (referrer_pubkey, referrer_bump) = assert_pda_input(&[REFERRER_KEY.as_ref(), payer_account_info.key.as_ref()], referrer_account_info);
+    if !referrer_account_info.data_is_empty() {
+        Err(ProgramError::AccountAlreadyInitialized)?
+    }
 
+    let space = Referrer::space();
     invoke_signed(
-        &system_instruction::create_account(
-            payer_account_info.key,
-            &gem_account_pubkey,
-            rent_lamports,
-            space as u64,
-            program_id,
-        ),
-        &[payer_account_info.clone(), gem_account_info.clone()],
-        &[&[
-            GEM_ACCOUNT_CONST.as_ref(),
-            mint_account_info.key.as_ref(),
-            &[gem_account_bump],
-        ]],
+        &system_instruction::create_account(payer_account_info.key, &referrer_pubkey, Rent::get()?.minimum_balance(space), space as u64, program_id),
+        &[payer_account_info.clone(), referrer_account_info.clone()],
+        &[&[REFERRER_KEY.as_ref(), payer_account_info.key.as_ref(), &[referrer_bump]]],
     )?;
 
-    let mut global_gem_data = GlobalGems::decode(global_gem_account_info)?;
+    let referrer_data = Referrer {
+        validation_phrase: REFERRER_VAL_PHRASE,
+        wallet: *payer_account_info.key,
+        referred_gems: 0,
+        referred_volume: 0,
+        accrued_rewards: 0,
+        claimed_rewards: 0,
+    };
+    referrer_data.serialize(&mut &mut referrer_account_info.data.borrow_mut()[..])?;
+    Ok(())
+}
 
-    let space = 82;
-    let rent_lamports = Rent::get()?.minimum_balance(space);
+/// Credits a mint to the `referrer` wallet, whose referrer account was passed. Without a referrer, the account isn't read.
+/// Returns the wallet of the crediting referrer, to be stored on the gem.
+fn record_referral(payer_account_info: &AccountInfo, referrer_account_info: &AccountInfo, class: Class, referrer: Option<Pubkey>) -> Result<Option<Pubkey>, ProgramError> {
+    let referrer = match referrer {
+        Some(referrer) => referrer,
+        None => return Ok(None),
+    };
+    if referrer == *payer_account_info.key {
+        Err(InglError::Unauthorized.utilize(Some("self referral")))?
+    }
+    let (_referrer_pubkey, _referrer_bump) = assert_pda_input(&[REFERRER_KEY.as_ref(), referrer.as_ref()], referrer_account_info);
+    let mut referrer_data = Referrer::decode(referrer_account_info)?;
 
+    referrer_data.referred_gems += 1;
+    referrer_data.referred_volume = referrer_data.referred_volume.checked_add(class.get_class_lamports()).unwrap();
+    referrer_data.serialize(&mut &mut referrer_account_info.data.borrow_mut()[..])?;
+    Ok(Some(referrer_data.wallet))
+}
 
-    assert_pubkeys_exactitude(
-        &get_associated_token_address(payer_account_info.key, mint_account_info.key),
-        associated_token_account_info.key,
-    )
-    .expect("Error: @associated_token_account_info");
+/// Pays a referrer the rewards accrued on their referred gems' redeem fees, out of the ingl treasury.
+pub fn claim_referral_rewards(_program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let payer_account_info = next_account_info(account_info_iter)?;
+    let referrer_account_info = next_account_info(account_info_iter)?;
+    let program_treasury_account_info = next_account_info(account_info_iter)?;
+    let _system_program_account_info = next_account_info(account_info_iter)?;
+    let treasury_ledger_account_info = next_account_info(account_info_iter)?;
 
+    assert_is_signer(payer_account_info)?;
+    let (_referrer_pubkey, _referrer_bump) = assert_pda_input(&[REFERRER_KEY.as_ref(), payer_account_info.key.as_ref()], referrer_account_info);
+    let mut referrer_data = Referrer::decode(referrer_account_info)?;
 
-    let mpl_token_metadata_id = mpl_token_metadata::id();
-    let metadata_seeds = &[
-        PREFIX.as_bytes(),
-        mpl_token_metadata_id.as_ref(),
-        mint_account_info.key.as_ref(),
-    ];
+    let unclaimed = referrer_data.accrued_rewards.checked_sub(referrer_data.claimed_rewards).unwrap();
+    if unclaimed == 0 {
+        Err(InglError::BeyondBounds.utilize(Some("no referral rewards to claim")))?
+    }
+    spend_treasury(TreasuryAccount::InglTreasury, program_treasury_account_info, payer_account_info, treasury_ledger_account_info, unclaimed)?;
 
-    let (nft_metadata_key, _nft_metadata_bump) =
-        Pubkey::find_program_address(metadata_seeds, &mpl_token_metadata::id());
+    referrer_data.claimed_rewards = referrer_data.accrued_rewards;
+    referrer_data.serialize(&mut &mut referrer_account_info.data.borrow_mut()[..])?;
+    Ok(())
+}
 
-    assert_pubkeys_exactitude(&nft_metadata_key, metadata_account_info.key)
-        .expect("Error: @meta_data_account_info");
+pub fn register_validator_id(program_id: &Pubkey, accounts: &[AccountInfo], name: String, website: String, commission: u8, self_bond: u64) -> ProgramResult{
+    let account_info_iter = &mut accounts.iter();
+    let payer_account_info = next_account_info(account_info_iter)?;
+    let global_gem_account_info = next_account_info(account_info_iter)?;
+    let mint_authority_account_info = next_account_info(account_info_iter)?;
+    let validator_info = next_account_info(account_info_iter)?; //Remove this and change it back to payer only after hackathon.
+    let dup_prevention_account = next_account_info(account_info_iter)?;
+    let candidate_account_info = next_account_info(account_info_iter)?;
+    let bond_account_info = next_account_info(account_info_iter)?;
+    let candidate_index_account_info = next_account_info(account_info_iter)?;
+    let treasury_ledger_account_info = next_account_info(account_info_iter)?;
 
-    let mint_cost = class.clone().get_class_lamports();
-    global_gem_data.counter += 1;
-    global_gem_data.total_raised += mint_cost;
+    assert_is_signer(payer_account_info)?;
+    assert_is_signer(validator_info)?;
 
-    global_gem_data.serialize(&mut &mut global_gem_account_info.data.borrow_mut()[..])?;
-    //tranfer token from one account to an other
-    invoke(
-        &system_instruction::transfer(payer_account_info.key, &minting_pool_id, mint_cost),
-        &[
-            payer_account_info.clone(),
-            minting_pool_account_info.clone(),
-        ],
-    )?;
+    if name.len() > MAX_CANDIDATE_NAME_LENGTH || website.len() > MAX_CANDIDATE_WEBSITE_LENGTH {
+        Err(InglError::BeyondBounds.utilize(Some("candidate name or website")))?
+    }
+    if commission > MAX_VALIDATOR_COMMISSION {
+        Err(InglError::BeyondBounds.utilize(Some("commission")))?
+    }
+    if self_bond < MIN_VALIDATOR_SELF_BOND {
+        Err(InglError::BeyondBounds.utilize(Some("self bond below minimum")))?
+    }
 
-    //create the mint account
-    invoke(
-        &system_instruction::create_account(
-            payer_account_info.key,
-            mint_account_info.key,
-            rent_lamports,
-            space as u64,
-            spl_token_program_account_info.key,
-        ),
-        &[payer_account_info.clone(), mint_account_info.clone()],
-    )?;
+    let (expected_dup_key, expected_dup_bump) = assert_pda_input(&[DUPKEYBYTES, validator_info.key.as_ref()], dup_prevention_account);
 
-    invoke(
-        &spl_token::instruction::initialize_mint(
-            &spl_token::id(),
-            &mint_account_info.key,
-            &mint_authority_key,
-            Some(&mint_authority_key),
-            0,
-        )?,
-        &[mint_account_info.clone(), sysvar_rent_account_info.clone()],
+    invoke_signed(
+        &system_instruction::create_account(payer_account_info.key, &expected_dup_key, Rent::get()?.minimum_balance(1), 1,program_id),
+        &[payer_account_info.clone(), dup_prevention_account.clone()],
+        &[&[DUPKEYBYTES, validator_info.key.as_ref(), &[expected_dup_bump]]]
     )?;
 
+    let (_global_gem_pubkey, _global_gem_bump) = assert_pda_input(&[GLOBAL_GEM_KEY.as_ref()], global_gem_account_info );
+    let (_mint_authority_key, _mint_authority_bump) = assert_pda_input(&[INGL_MINT_AUTHORITY_KEY.as_ref()], mint_authority_account_info);
+    let (expected_candidate_key, expected_candidate_bump) = assert_pda_input(&[VALIDATOR_CANDIDATE_KEY.as_ref(), validator_info.key.as_ref()], candidate_account_info);
+    let (expected_bond_key, expected_bond_bump) = assert_pda_input(&[VALIDATOR_BOND_KEY.as_ref(), validator_info.key.as_ref()], bond_account_info);
+
     invoke(
-        &spl_associated_token_account::instruction::create_associated_token_account(
-            payer_account_info.key,
+        &system_instruction::transfer(
             payer_account_info.key,
-            mint_account_info.key,
+            mint_authority_account_info.key,
+            VALIDATOR_REGISTRATION_FEE,
         ),
         &[
             payer_account_info.clone(),
-            associated_token_account_info.clone(),
-            payer_account_info.clone(),
-            mint_account_info.clone(),
-            system_program_account_info.clone(),
-            spl_token_program_account_info.clone(),
+            mint_authority_account_info.clone(),
         ],
     )?;
+    update_treasury_ledger(treasury_ledger_account_info, |ledger| ledger.registration_fees = ledger.registration_fees.saturating_add(VALIDATOR_REGISTRATION_FEE))?;
 
-    // msg!("Mint new collection token");
+    // The self bond is escrowed from the validator's identity account in a program owned pda.
     invoke_signed(
-        &spl_token::instruction::mint_to(
-            spl_token_program_account_info.key,
-            mint_account_info.key,
-            associated_token_account_info.key,
-            &mint_authority_key,
-            &[],
-            1,
-        )?,
-        &[
-            mint_account_info.clone(),
-            associated_token_account_info.clone(),
-            mint_authority_account_info.clone(),
-        ],
-        &[&[INGL_MINT_AUTHORITY_KEY.as_ref(), &[mint_authority_bump]]],
+        &system_instruction::create_account(validator_info.key, &expected_bond_key, Rent::get()?.minimum_balance(0) + self_bond, 0, program_id),
+        &[validator_info.clone(), bond_account_info.clone()],
+        &[&[VALIDATOR_BOND_KEY.as_ref(), validator_info.key.as_ref(), &[expected_bond_bump]]],
     )?;
 
-    let mut creators = Vec::new();
-    creators.push(Creator {
-        address: mint_authority_key,
-        verified: true,
-        share: 100,
-    });
+    let space = ValidatorCandidate::space();
+    invoke_signed(
+        &system_instruction::create_account(payer_account_info.key, &expected_candidate_key, Rent::get()?.minimum_balance(space), space as u64, program_id),
+        &[payer_account_info.clone(), candidate_account_info.clone()],
+        &[&[VALIDATOR_CANDIDATE_KEY.as_ref(), validator_info.key.as_ref(), &[expected_candidate_bump]]],
+    )?;
 
+    assert_program_owned(global_gem_account_info)?;
+    let mut global_gem_data = GlobalGems::decode(global_gem_account_info)?;
 
-    let metadata_seeds = &[
-        PREFIX.as_ref(),
-        mpl_token_metadata_id.as_ref(),
-        ingl_nft_collection_key.as_ref(),
-    ];
+    let index = global_gem_data.candidate_count;
+    let (expected_index_key, expected_index_bump) = assert_pda_input(&[CANDIDATE_INDEX_KEY.as_ref(), &index.to_be_bytes()], candidate_index_account_info);
+    let space = CandidateIndex::space();
+    invoke_signed(
+        &system_instruction::create_account(payer_account_info.key, &expected_index_key, Rent::get()?.minimum_balance(space), space as u64, program_id),
+        &[payer_account_info.clone(), candidate_index_account_info.clone()],
+        &[&[CANDIDATE_INDEX_KEY.as_ref(), &index.to_be_bytes(), &[expected_index_bump]]],
+    )?;
+    let index_data = CandidateIndex{
+        validation_phrase: CANDIDATE_INDEX_VAL_PHRASE,
+        validator_id: *validator_info.key,
+    };
+    index_data.serialize(&mut &mut candidate_index_account_info.data.borrow_mut()[..])?;
 
-    let (collection_metadata_key, _collection_metadata_bump) =
-        Pubkey::find_program_address(metadata_seeds, &mpl_token_metadata_id);
+    let candidate_data = ValidatorCandidate{
+        validation_phrase: VALIDATOR_CANDIDATE_VAL_PHRASE,
+        validator_id: *validator_info.key,
+        name,
+        website,
+        commission,
+        self_bond,
+        date_registered: Clock::get()?.unix_timestamp as u32,
+        index: Some(index),
+    };
+    candidate_data.serialize(&mut &mut candidate_account_info.data.borrow_mut()[..])?;
 
-    assert_pubkeys_exactitude(&collection_metadata_key, ingl_collection_account_info.key)
-        .expect("Error: @collection_metadata_info");
+    global_gem_data.candidate_count = global_gem_data.candidate_count.checked_add(1).unwrap();
+    global_gem_data.serialize(&mut &mut global_gem_account_info.data.borrow_mut()[..])?;
 
-    // msg!("starting metadata creation");
-    invoke_signed(
-        &mpl_token_metadata::instruction::create_metadata_accounts_v3(
-            mpl_token_metadata_id,
-            nft_metadata_key,
-            *mint_account_info.key,
-            *mint_authority_account_info.key,
-            *payer_account_info.key,
-            *mint_authority_account_info.key,
-            String::from("Ingl Gem #") + &global_gem_data.counter.to_string(),
-            String::from("I-Gem#") + &global_gem_data.counter.to_string(),
-            String::from(nfts::get_uri(class, None)),
-            Some(creators),
-            300,
-            true,
-            true,
-            Some(Collection {
-                verified: false,
-                key: ingl_nft_collection_key,
-            }),
-            None,
-            None,
+    Ok(())
+}
+
+/// Removes an unelected candidate from the candidate set and returns its self bond.
+/// The dup prevention account is flagged so that the registration fee can then be refunded.
+pub fn withdraw_candidacy(_program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult{
+    let account_info_iter = &mut accounts.iter();
+    let validator_info = next_account_info(account_info_iter)?;
+    let global_gem_account_info = next_account_info(account_info_iter)?;
+    let dup_prevention_account = next_account_info(account_info_iter)?;
+    let candidate_account_info = next_account_info(account_info_iter)?;
+    let bond_account_info = next_account_info(account_info_iter)?;
+    let candidate_index_account_info = next_account_info(account_info_iter)?;
+    let last_index_account_info = next_account_info(account_info_iter)?;
+    let last_candidate_account_info = next_account_info(account_info_iter)?;
+
+    assert_is_signer(validator_info)?;
+    let (_global_gem_pubkey, _global_gem_bump) = assert_pda_input(&[GLOBAL_GEM_KEY.as_ref()], global_gem_account_info);
+    let (_expected_dup_key, _expected_dup_bump) = assert_pda_input(&[DUPKEYBYTES, validator_info.key.as_ref()], dup_prevention_account);
+    let (_expected_candidate_key, _expected_candidate_bump) = assert_pda_input(&[VALIDATOR_CANDIDATE_KEY.as_ref(), validator_info.key.as_ref()], candidate_account_info);
+    let (_expected_bond_key, _expected_bond_bump) = assert_pda_input(&[VALIDATOR_BOND_KEY.as_ref(), validator_info.key.as_ref()], bond_account_info);
+    assert_program_owned(dup_prevention_account)?;
+
+    let mut global_gem_data = GlobalGems::decode(global_gem_account_info)?;
+    if global_gem_data.is_proposal_ongoing {
+        Err(InglError::TooEarly.utilize(Some("A Proposal Is Currently Ongoing")))?
+    }
+    // Elected validators are taken out of the candidate set on finalization, so only unelected candidates pass this.
+    remove_candidate(&mut global_gem_data, candidate_account_info, candidate_index_account_info, last_index_account_info, last_candidate_account_info, validator_info)?;
+    global_gem_data.serialize(&mut &mut global_gem_account_info.data.borrow_mut()[..])?;
+
+    for closed_account_info in [candidate_account_info, bond_account_info] {
+        assert_program_owned(closed_account_info)?;
+        let dest_starting_lamports = validator_info.lamports();
+        **validator_info.lamports.borrow_mut() = dest_starting_lamports
+            .checked_add(closed_account_info.lamports())
+            .unwrap();
+        **closed_account_info.lamports.borrow_mut() = 0;
+        closed_account_info.data.borrow_mut().fill(0);
+    }
+
+    dup_prevention_account.data.borrow_mut()[0] = 1;
+    Ok(())
+}
+
+/// Returns an elected validator's self bond once its vote account has been created. Candidacies can only be withdrawn
+/// while unelected, so this is the elected validators' way out of the bond escrow.
+pub fn release_validator_bond(_program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult{
+    let account_info_iter = &mut accounts.iter();
+    let validator_info = next_account_info(account_info_iter)?;
+    let candidate_account_info = next_account_info(account_info_iter)?;
+    let bond_account_info = next_account_info(account_info_iter)?;
+    let vote_account_info = next_account_info(account_info_iter)?;
+    let ingl_vote_data_account_info = next_account_info(account_info_iter)?;
+
+    assert_is_signer(validator_info)?;
+    let (_expected_candidate_key, _expected_candidate_bump) = assert_pda_input(&[VALIDATOR_CANDIDATE_KEY.as_ref(), validator_info.key.as_ref()], candidate_account_info);
+    let (_expected_bond_key, _expected_bond_bump) = assert_pda_input(&[VALIDATOR_BOND_KEY.as_ref(), validator_info.key.as_ref()], bond_account_info);
+    let (_expected_vote_data_key, _expected_vote_data_bump) = assert_pda_input(&[VOTE_DATA_ACCOUNT_KEY.as_ref(), vote_account_info.key.as_ref()], ingl_vote_data_account_info);
+    assert_program_owned(bond_account_info)?;
+
+    let candidate_data = ValidatorCandidate::decode(candidate_account_info)?;
+    if candidate_data.index.is_some() {
+        Err(InglError::Unauthorized.utilize(Some("Unelected candidates withdraw their candidacy instead")))?
+    }
+    let ingl_vote_account_data = InglVoteAccountData::decode(ingl_vote_data_account_info)?;
+    assert_pubkeys_exactitude(&ingl_vote_account_data.validator_id, validator_info.key)?;
+
+    let dest_starting_lamports = validator_info.lamports();
+    **validator_info.lamports.borrow_mut() = dest_starting_lamports
+        .checked_add(bond_account_info.lamports())
+        .unwrap();
+    **bond_account_info.lamports.borrow_mut() = 0;
+    bond_account_info.data.borrow_mut().fill(0);
+    Ok(())
+}
+
+/// Refunds `registration_refund_percentage` of the registration fee to a withdrawn candidate and closes its dup prevention account,
+/// which also makes registering again possible.
+pub fn refund_registration(_program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult{
+    let account_info_iter = &mut accounts.iter();
+    let validator_info = next_account_info(account_info_iter)?;
+    let config_account_info = next_account_info(account_info_iter)?;
+    let mint_authority_account_info = next_account_info(account_info_iter)?;
+    let dup_prevention_account = next_account_info(account_info_iter)?;
+    let _system_program_account_info = next_account_info(account_info_iter)?;
+    let treasury_ledger_account_info = next_account_info(account_info_iter)?;
+
+    assert_is_signer(validator_info)?;
+    let (_config_pubkey, _config_bump) = assert_pda_input(&[INGL_CONFIG_KEY.as_ref()], config_account_info);
+    let config_data = InglConfig::decode(config_account_info)?;
+    let (_expected_dup_key, _expected_dup_bump) = assert_pda_input(&[DUPKEYBYTES, validator_info.key.as_ref()], dup_prevention_account);
+    assert_program_owned(dup_prevention_account)?;
+
+    if dup_prevention_account.data.borrow()[0] != 1 {
+        Err(InglError::TooEarly.utilize(Some("Candidacy must be withdrawn first")))?
+    }
+
+    let refund = VALIDATOR_REGISTRATION_FEE * config_data.registration_refund_percentage as u64 / 100;
+    if refund > 0 {
+        spend_treasury(TreasuryAccount::MintAuthority, mint_authority_account_info, validator_info, treasury_ledger_account_info, refund)?;
+        update_treasury_ledger(treasury_ledger_account_info, |ledger| ledger.registration_fees = ledger.registration_fees.saturating_sub(refund))?;
+    }
+
+    let dest_starting_lamports = validator_info.lamports();
+    **validator_info.lamports.borrow_mut() = dest_starting_lamports
+        .checked_add(dup_prevention_account.lamports())
+        .unwrap();
+    **dup_prevention_account.lamports.borrow_mut() = 0;
+    dup_prevention_account.data.borrow_mut().fill(0);
+    Ok(())
+}
+
+pub fn create_vote_account(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    
+    let validator_info = next_account_info(account_info_iter)?;
+    let vote_account_info = next_account_info(account_info_iter)?;
+    let sysvar_rent_info = next_account_info(account_info_iter)?;
+    let sysvar_clock_info = next_account_info(account_info_iter)?;
+    let global_gem_account_info = next_account_info(account_info_iter)?;
+    let proposal_account_info = next_account_info(account_info_iter)?;
+    let mint_associated_token_account = next_account_info(account_info_iter)?;
+    let council_mint_account_info = next_account_info(account_info_iter)?;
+    let council_mint_authority_info = next_account_info(account_info_iter)?;
+    let system_program_account_info = next_account_info(account_info_iter)?;
+    let spl_token_program_account_info = next_account_info(account_info_iter)?;
+    let ingl_vote_data_account_info = next_account_info(account_info_iter)?;
+    let stake_account_info = next_account_info(account_info_iter)?;
+    let pd_pool_account_info = next_account_info(account_info_iter)?;
+    let sysvar_stake_history_info = next_account_info(account_info_iter)?;
+    let sysvar_stake_config_info = next_account_info(account_info_iter)?;
+
+    assert_program_owned(global_gem_account_info)?;
+    assert_program_owned(proposal_account_info)?;
+    assert_pubkeys_exactitude(sysvar_clock_info.key, &sysvar::clock::id())?;
+    assert_pubkeys_exactitude(sysvar_stake_history_info.key, &sysvar::stake_history::id())?;
+    assert_pubkeys_exactitude(sysvar_rent_info.key, &sysvar::rent::id())?;
+    assert_pubkeys_exactitude(system_program_account_info.key, &system_program::id())?;
+    assert_pubkeys_exactitude(spl_token_program_account_info.key, &spl_token::id())?;
+    assert_pubkeys_exactitude(
+        sysvar_stake_config_info.key,
+        &solana_program::stake::config::id(),
+    )?;
+
+    let (_pd_pool_pubkey, _pd_pool_bump) = assert_pda_input(&[PD_POOL_KEY.as_ref()], pd_pool_account_info);
+
+    let (_global_gem_pubkey, _global_gem_bump) = assert_pda_input(&[GLOBAL_GEM_KEY.as_ref()], global_gem_account_info);
+
+    let global_gem_data = GlobalGems::decode(global_gem_account_info)?;    
+    
+    let (expected_vote_data_pubkey, expected_vote_data_bump) = assert_pda_input(&[VOTE_DATA_ACCOUNT_KEY.as_ref(), vote_account_info.key.as_ref()], ingl_vote_data_account_info);
+
+    let (_expected_proposal_id, _expected_proposal_bump) = assert_pda_input(&[PROPOSAL_KEY.as_ref(), &(global_gem_data.proposal_numeration-1).to_be_bytes()], proposal_account_info);
+
+    let proposal_data = ValidatorProposal::decode(proposal_account_info)?;
+
+    assert_pubkeys_exactitude(validator_info.key, &proposal_data.winner.unwrap()).expect("validator id, not that expected");
+
+    let (expected_vote_pubkey, expected_vote_pubkey_bump) = assert_pda_input(&[VOTE_ACCOUNT_KEY.as_ref(), &(global_gem_data.proposal_numeration-1).to_be_bytes()], vote_account_info);
+    let (authorized_withdrawer, _authorized_withdrawer_nonce) = Pubkey::find_program_address(&[AUTHORIZED_WITHDRAWER_KEY.as_ref()], program_id);
+
+    let (_expected_mint_key, _expected_mint_bump) = assert_pda_input(&[COUNCIL_MINT_KEY.as_ref()], council_mint_account_info);
+
+    let (_expected_council_mint_authority_key, mint_authority_bump) = assert_pda_input(&[COUNCIL_MINT_AUTHORITY_KEY.as_ref()], council_mint_authority_info);
+    
+    let expected_assoc_key = get_associated_token_address(vote_account_info.key, council_mint_account_info.key);
+    assert_pubkeys_exactitude(&expected_assoc_key, mint_associated_token_account.key).expect("Council associated token is not that expected");
+    
+    let (_expected_stake_key, expected_stake_bump) = assert_pda_input(&[STAKE_ACCOUNT_KEY.as_ref(), expected_vote_pubkey.as_ref()], stake_account_info);
+
+    let space = 10240; //Change this size to 100_000+ through reallocs
+
+    let lamports = Rent::get()?.minimum_balance(space);
+
+    invoke_signed(
+        &system_instruction::create_account(validator_info.key, &expected_vote_data_pubkey, lamports, space as u64, program_id),
+        &[validator_info.clone(), ingl_vote_data_account_info.clone()],
+        &[&[VOTE_DATA_ACCOUNT_KEY.as_ref(), vote_account_info.key.as_ref(), &[expected_vote_data_bump]]]
+    )?;
+    let ingl_vote_data = InglVoteAccountData {
+        validation_phrase: INGL_VOTE_ACCOUNT_DATA_VAL_PHRASE,
+        total_delegated: 0,
+        last_withdraw_epoch: Clock::get()?.epoch,
+        dealloced: 0,
+        pending_validator_rewards: None,
+        validator_id: *validator_info.key,
+        pending_delegation_total: 0,
+        boosted_delegated: 0,
+        is_t_stake_initialized: false,
+        vote_rewards: Vec::new(),
+        last_rebalance_epoch: 0,
+        reserve_advances: 0,
+        unwinding_reserve_advances: 0,
+        last_total_staked: LAMPORTS_PER_SOL + Rent::get()?.minimum_balance(std::mem::size_of::<StakeState>() as usize),
+    };
+
+    ingl_vote_data.serialize(&mut &mut ingl_vote_data_account_info.data.borrow_mut()[..])?;
+
+    invoke(
+        &spl_associated_token_account::instruction::create_associated_token_account(
+            validator_info.key,
+            vote_account_info.key,
+            council_mint_account_info.key,
         ),
         &[
-            metadata_account_info.clone(),
-            mint_account_info.clone(),
-            mint_authority_account_info.clone(),
-            payer_account_info.clone(),
-            mint_authority_account_info.clone(),
+            validator_info.clone(),
+            mint_associated_token_account.clone(),
+            vote_account_info.clone(),
+            council_mint_account_info.clone(),
             system_program_account_info.clone(),
-            sysvar_rent_account_info.clone(),
+            spl_token_program_account_info.clone(),
         ],
-        &[&[INGL_MINT_AUTHORITY_KEY.as_ref(), &[mint_authority_bump]]],
     )?;
 
-    let (ingl_collection_edition_key, _collection_edition_bump) = Pubkey::find_program_address(
+    invoke_signed(
+        &spl_token::instruction::mint_to(&spl_token::id(), council_mint_account_info.key, mint_associated_token_account.key, council_mint_authority_info.key, &[], 1)?,
         &[
-            b"metadata",
-            mpl_token_metadata_id.as_ref(),
-            ingl_nft_collection_key.as_ref(),
-            b"edition",
+            council_mint_account_info.clone(),
+            mint_associated_token_account.clone(),
+            council_mint_authority_info.clone(),
         ],
-        &mpl_token_metadata_id,
-    );
-    assert_pubkeys_exactitude(&ingl_collection_edition_key, ingl_edition_account_info.key)
-        .expect("Error: @edition_account_info");
+        &[&[COUNCIL_MINT_AUTHORITY_KEY.as_ref(), &[mint_authority_bump]]]
+    )?;
+
+    let vote_init = VoteInit {
+        node_pubkey: *validator_info.key,
+        authorized_voter: *validator_info.key,
+        commission: 10,
+        authorized_withdrawer,
+    };
+    invoke_signed(
+        &vote_create_account(validator_info.key, vote_account_info.key),
+        &[validator_info.clone(), vote_account_info.clone()],
+        &[&[
+            VOTE_ACCOUNT_KEY.as_ref(),
+            &(global_gem_data.proposal_numeration-1).to_be_bytes(),
+            &[expected_vote_pubkey_bump],
+        ]],
+    )?;
+    invoke(
+        &vote_initialize_account(vote_account_info.key, &vote_init),
+        &[
+            vote_account_info.clone(),
+            sysvar_rent_info.clone(),
+            sysvar_clock_info.clone(),
+            validator_info.clone(),
+        ],
+    )?;
+
+    let authorized = &Authorized {
+        staker: *pd_pool_account_info.key,
+        withdrawer: *pd_pool_account_info.key,
+    };
+    let lockup = &Lockup {
+        unix_timestamp: 0,
+        epoch: 0,
+        custodian: *pd_pool_account_info.key,
+    };
+    
+    let lamports = LAMPORTS_PER_SOL + Rent::get()?.minimum_balance(std::mem::size_of::<StakeState>() as usize);
+    msg!("creating account");
+    invoke_signed(
+        &system_instruction::create_account(
+            validator_info.key,
+            stake_account_info.key,
+            lamports,
+            std::mem::size_of::<StakeState>() as u64,
+            &stake::program::id(),
+        ),
+        &[validator_info.clone(), stake_account_info.clone()],
+        &[&[
+            STAKE_ACCOUNT_KEY.as_ref(),
+            expected_vote_pubkey.as_ref(),
+            &[expected_stake_bump],
+        ]],
+    )?;
+    msg!("Initializing stake");
+    invoke(
+        &solana_program::stake::instruction::initialize(stake_account_info.key, authorized, lockup),
+        &[stake_account_info.clone(), sysvar_rent_info.clone()],
+    )?;
+
+
+    Ok(())
+}
+
+pub fn allocate_sol(_program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let payer_account_info = next_account_info(account_info_iter)?;
+    let mint_account_info = next_account_info(account_info_iter)?;
+    let gem_account_data_info = next_account_info(account_info_iter)?;
+    let associated_token_account_info = next_account_info(account_info_iter)?;
+    let global_gem_account_info = next_account_info(account_info_iter)?;
+    let pd_pool_account_info = next_account_info(account_info_iter)?;
+    let minting_pool_account_info = next_account_info(account_info_iter)?;
+
+    assert_program_owned(gem_account_data_info)?;
+    assert_program_owned(global_gem_account_info)?;
+    assert_owned_by(mint_account_info, &spl_program::id())?;
+    assert_owned_by(associated_token_account_info, &spl_program::id())?;
+
+    assert_is_signer(payer_account_info).unwrap();
+
+    let (_gem_account_pubkey, _gem_account_bump) = assert_pda_input(&[GEM_ACCOUNT_CONST.as_ref(), mint_account_info.key.as_ref()], gem_account_data_info);
+
+    assert_pubkeys_exactitude(
+        &get_associated_token_address(payer_account_info.key, mint_account_info.key),
+        associated_token_account_info.key,
+    )
+    .expect("Error: @associated_token_address");
+
+    let associated_token_address_data =
+        Account::unpack(&associated_token_account_info.data.borrow())?;
+    if associated_token_address_data.amount != 1 {
+        Err(ProgramError::InsufficientFunds)?
+    }
+
+    let (_global_gem_pubkey, _global_gem_bump) = assert_pda_input(&[GLOBAL_GEM_KEY.as_ref()], global_gem_account_info);
+
+    let (pd_pool_pubkey, _pd_pool_bump) = assert_pda_input(&[PD_POOL_KEY.as_ref()], pd_pool_account_info);
+
+    let mut gem_account_data: GemAccountV0_0_1 = GemAccountV0_0_1::validate(
+        GemAccountVersions::decode_unchecked(&gem_account_data_info.data.borrow())?,
+    )?;
+    let mut global_gem_account_data = GlobalGems::decode(global_gem_account_info)?;
+
+    let (minting_pool_id, minting_pool_bump) = assert_pda_input(&[INGL_MINTING_POOL_KEY.as_ref()], minting_pool_account_info);
+
+    match gem_account_data.funds_location {
+        FundsLocation::MintingPool => {
+            gem_account_data.funds_location = FundsLocation::PDPool;
+        }
+        _ => Err(InglError::InvalidFundsLocation.utilize(Some("gem's funds location.")))?,
+    }
+
+    let mint_cost = gem_account_data.class.clone().get_class_lamports();
+    //tranfer token from mint_pool, to pd_pool
+    invoke_signed(
+        &system_instruction::transfer(&minting_pool_id, &pd_pool_pubkey, mint_cost),
+        &[
+            minting_pool_account_info.clone(),
+            pd_pool_account_info.clone(),
+        ],
+        &[&[INGL_MINTING_POOL_KEY.as_ref(), &[minting_pool_bump]]],
+    )?;
+
+    let clock = Clock::get()?;
+
+    gem_account_data.date_allocated = Some(clock.unix_timestamp as u32);
+    gem_account_data.redeemable_date = clock.unix_timestamp as u32 + ALLOCATE_LOCK_TIME;
+
+    global_gem_account_data.pd_pool_total += mint_cost;
+
+    gem_account_data.serialize(&mut &mut gem_account_data_info.data.borrow_mut()[..])?;
+    global_gem_account_data.serialize(&mut &mut global_gem_account_info.data.borrow_mut()[..])?;
+
+    Ok(())
+}
+
+pub fn deallocate_sol(_program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let payer_account_info = next_account_info(account_info_iter)?;
+    let mint_account_info = next_account_info(account_info_iter)?;
+    let gem_account_data_info = next_account_info(account_info_iter)?;
+    let associated_token_account_info = next_account_info(account_info_iter)?;
+    let global_gem_account_info = next_account_info(account_info_iter)?;
+    let pd_pool_account_info = next_account_info(account_info_iter)?;
+    let minting_pool_account_info = next_account_info(account_info_iter)?;
+
+    assert_program_owned(gem_account_data_info)?;
+    assert_program_owned(global_gem_account_info)?;
+    assert_owned_by(mint_account_info, &spl_program::id())?;
+    assert_owned_by(associated_token_account_info, &spl_program::id())?;
+
+    assert_is_signer(payer_account_info).unwrap();
+
+    let (_gem_account_pubkey, _gem_account_bump) = assert_pda_input(&[GEM_ACCOUNT_CONST.as_ref(), mint_account_info.key.as_ref()], gem_account_data_info);
+
+    assert_pubkeys_exactitude(
+        &get_associated_token_address(payer_account_info.key, mint_account_info.key),
+        associated_token_account_info.key,
+    )
+    .expect("Error: @associated_token_address");
+
+    let associated_token_address_data =
+        Account::unpack(&associated_token_account_info.data.borrow())?;
+    if associated_token_address_data.amount != 1 {
+        Err(ProgramError::InsufficientFunds)?
+    }
+
+    let (_global_gem_pubkey, _global_gem_bump) = assert_pda_input(&[GLOBAL_GEM_KEY.as_ref()], global_gem_account_info);
+
+    let (pd_pool_pubkey, pd_pool_bump) = assert_pda_input(&[PD_POOL_KEY.as_ref()], pd_pool_account_info);
+
+    let mut gem_account_data: GemAccountV0_0_1 = GemAccountV0_0_1::validate(
+        GemAccountVersions::decode_unchecked(&gem_account_data_info.data.borrow())?,
+    )?;
+    let mut global_gem_account_data = GlobalGems::decode(global_gem_account_info)?;
+
+    let (minting_pool_id, _minting_pool_bump) = assert_pda_input(&[INGL_MINTING_POOL_KEY.as_ref()], minting_pool_account_info);
+
+    gem_account_data.assert_not_queued()?;
+    match gem_account_data.funds_location {
+        FundsLocation::PDPool => {
+            gem_account_data.funds_location = FundsLocation::MintingPool;
+        }
+        _ => Err(InglError::InvalidFundsLocation.utilize(Some("gem's funds location.")))?,
+    }
 
-    // msg!("verifying collection");
+    let mint_cost = gem_account_data.class.clone().get_class_lamports();
+    //tranfer token from mint_pool, to pd_pool
     invoke_signed(
-        &mpl_token_metadata::instruction::verify_collection(
-            mpl_token_metadata_id,
-            nft_metadata_key,
-            mint_authority_key,
-            *payer_account_info.key,
-            ingl_nft_collection_key,
-            collection_metadata_key,
-            ingl_collection_edition_key,
-            None,
-        ),
+        &system_instruction::transfer(&pd_pool_pubkey, &minting_pool_id, mint_cost),
         &[
-            metadata_account_info.clone(),
-            mint_authority_account_info.clone(),
-            payer_account_info.clone(),
-            ingl_collection_mint_info.clone(),
-            ingl_collection_account_info.clone(),
-            ingl_edition_account_info.clone(),
+            pd_pool_account_info.clone(),
+            minting_pool_account_info.clone(),
         ],
-        &[&[INGL_MINT_AUTHORITY_KEY.as_ref(), &[mint_authority_bump]]],
+        &[&[PD_POOL_KEY.as_ref(), &[pd_pool_bump]]],
     )?;
 
-    let (nft_edition_key, _edition_bump) = Pubkey::find_program_address(
-        &[
-            b"metadata",
-            mpl_token_metadata_id.as_ref(),
-            mint_account_info.key.as_ref(),
-            b"edition",
-        ],
-        &mpl_token_metadata_id,
-    );
-    assert_pubkeys_exactitude(&nft_edition_key, nft_edition_account_info.key)
-        .expect("Error: @edition_account_info");
+    let clock = Clock::get()?;
 
-    // msg!("Creating master Edition account...");
+    if (clock.unix_timestamp as u32) < gem_account_data.redeemable_date {
+        Err(InglError::TooEarly.utilize(Some("Deallocating Earlier than allowed")))?;
+    }
+    global_gem_account_data.pd_pool_total -= mint_cost;
+
+    gem_account_data.serialize(&mut &mut gem_account_data_info.data.borrow_mut()[..])?;
+    global_gem_account_data.serialize(&mut &mut global_gem_account_info.data.borrow_mut()[..])?;
+
+    Ok(())
+}
+
+pub fn mint_nft(program_id: &Pubkey, accounts: &[AccountInfo], class: Class, referrer: Option<Pubkey>) -> ProgramResult {
+    mint_scheduled_nft(program_id, accounts, class, referrer, None, false)
+}
+
+/// Mints paying in wrapped SOL, for treasuries and DAOs holding wSOL. Takes the `mint_nft` accounts followed by
+/// the payer's wrapped SOL token account, the payer's wrapped SOL escrow and the native mint.
+/// Other SPL tokens aren't accepted: a gem's backing has to be native SOL for it to be allocated and delegated.
+pub fn mint_nft_with_wrapped_sol(program_id: &Pubkey, accounts: &[AccountInfo], class: Class, referrer: Option<Pubkey>) -> ProgramResult {
+    mint_scheduled_nft(program_id, accounts, class, referrer, None, true)
+}
+
+/// Mints during the allowlist phase, proving the payer is allowlisted for up to `max_count` gems of `class`.
+/// Takes the same accounts as `mint_nft`.
+pub fn allowlist_mint_nft(program_id: &Pubkey, accounts: &[AccountInfo], class: Class, max_count: u32, proof: Vec<[u8; 32]>, referrer: Option<Pubkey>) -> ProgramResult {
+    mint_scheduled_nft(program_id, accounts, class, referrer, Some((max_count, &proof)), false)
+}
+
+fn mint_scheduled_nft(program_id: &Pubkey, accounts: &[AccountInfo], class: Class, referrer: Option<Pubkey>, allowlist_claim: Option<(u32, &[[u8; 32]])>, wrapped_sol: bool) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let payer_account_info = next_account_info(account_info_iter)?;
+    let mint_account_info = next_account_info(account_info_iter)?;
+    let mint_authority_account_info = next_account_info(account_info_iter)?;
+    let associated_token_account_info = next_account_info(account_info_iter)?;
+    let spl_token_program_account_info = next_account_info(account_info_iter)?;
+    let sysvar_rent_account_info = next_account_info(account_info_iter)?;
+    let system_program_account_info = next_account_info(account_info_iter)?;
+    let metadata_account_info = next_account_info(account_info_iter)?;
+    let minting_pool_account_info = next_account_info(account_info_iter)?;
+    let global_gem_account_info = next_account_info(account_info_iter)?;
+    let gem_account_info = next_account_info(account_info_iter)?;
+    let ingl_edition_account_info = next_account_info(account_info_iter)?;
+    let nft_edition_account_info = next_account_info(account_info_iter)?;
+    let ingl_collection_mint_info = next_account_info(account_info_iter)?;
+    let ingl_collection_account_info = next_account_info(account_info_iter)?;
+    let config_account_info = next_account_info(account_info_iter)?;
+    let uri_registry_account_info = next_account_info(account_info_iter)?;
+    let mint_schedule_account_info = next_account_info(account_info_iter)?;
+    let mint_wallet_record_account_info = next_account_info(account_info_iter)?;
+    let referrer_account_info = next_account_info(account_info_iter)?;
+
+    let (_config_pubkey, _config_bump) = assert_pda_input(&[INGL_CONFIG_KEY.as_ref()], config_account_info);
+    let config_data = InglConfig::decode(config_account_info)?;
+    config_data.assert_not_paused()?;
+    config_data.assert_class_listed(class)?;
+    let uri_registry_data = load_uri_registry(uri_registry_account_info)?;
+
+    let gem_mint_accounts = GemMintAccounts {
+        payer_account_info,
+        mint_account_info,
+        mint_authority_account_info,
+        associated_token_account_info,
+        spl_token_program_account_info,
+        sysvar_rent_account_info,
+        system_program_account_info,
+        metadata_account_info,
+        nft_edition_account_info,
+        ingl_edition_account_info,
+        ingl_collection_mint_info,
+        ingl_collection_account_info,
+    };
+    gem_mint_accounts.validate()?;
+
+    assert_program_owned(global_gem_account_info)?;
+    let (_global_gem_pubkey, _global_gem_bump) = assert_pda_input(&[GLOBAL_GEM_KEY.as_ref()], global_gem_account_info);
+    let (minting_pool_id, _minting_pool_bump) = assert_pda_input(&[INGL_MINTING_POOL_KEY.as_ref()], minting_pool_account_info);
+
+    let clock = Clock::get()?;
+    // Getting timestamp
+    let current_timestamp = clock.unix_timestamp as u32;
+
+    record_scheduled_mint(program_id, payer_account_info, mint_schedule_account_info, mint_wallet_record_account_info, class, allowlist_claim)?;
+    let referrer = record_referral(payer_account_info, referrer_account_info, class, referrer)?;
+    create_gem_account(program_id, payer_account_info, mint_account_info, gem_account_info)?;
+
+    let mut global_gem_data = GlobalGems::decode(global_gem_account_info)?;
+
+    let mint_cost = class.clone().get_class_lamports();
+    global_gem_data.counter += 1;
+    global_gem_data.total_raised += mint_cost;
+
+    global_gem_data.serialize(&mut &mut global_gem_account_info.data.borrow_mut()[..])?;
+    if wrapped_sol {
+        let payer_wrapped_sol_account_info = next_account_info(account_info_iter)?;
+        let wrapped_sol_escrow_account_info = next_account_info(account_info_iter)?;
+        let native_mint_account_info = next_account_info(account_info_iter)?;
+        pay_with_wrapped_sol(
+            payer_account_info,
+            payer_wrapped_sol_account_info,
+            wrapped_sol_escrow_account_info,
+            native_mint_account_info,
+            minting_pool_account_info,
+            spl_token_program_account_info,
+            sysvar_rent_account_info,
+            mint_cost,
+        )?;
+    } else {
+        //tranfer token from one account to an other
+        invoke(
+            &system_instruction::transfer(payer_account_info.key, &minting_pool_id, mint_cost),
+            &[
+                payer_account_info.clone(),
+                minting_pool_account_info.clone(),
+            ],
+        )?;
+    }
+
+    gem_mint_accounts.mint_gem(config_data.get_gem_name(class), global_gem_data.counter, uri_registry_data.get_uri(class, None))?;
+
+    let gem_account_data = GemAccountV0_0_1 {
+        struct_id: GemAccountVersions::GemAccountV0_0_1,
+        validation_phrase: GEM_ACCOUNT_VAL_PHRASE,
+        date_created: current_timestamp,
+        redeemable_date: current_timestamp,
+        numeration: global_gem_data.counter,
+        rarity: None,
+        funds_location: FundsLocation::MintingPool,
+        rarity_seed_time: None,
+        date_allocated: None,
+        class: class,
+        last_voted_proposal: None,
+        last_withdrawal_epoch: None,
+        last_delegation_epoch: None,
+        all_withdraws: Vec::new(),
+        all_votes: Vec::new(),
+        voting_delegate: None,
+        rarity_reveal_slot: None,
+        reward_boost: None,
+        rarity_rerolls: None,
+        referrer,
+        queued_redemption: None,
+        ballot_voter: None,
+        governance_vote_end: None,
+    };
+    gem_account_data.serialize(&mut &mut gem_account_info.data.borrow_mut()[..])?;
+    Ok(())
+}
+
+/// Pays a mint in wrapped SOL: the payer's wrapped SOL moves into an escrow token account, which is then closed into the
+/// minting pool so that the pool holds native SOL like for any other gem. The escrow's rent is handed back to the payer.
+#[allow(clippy::too_many_arguments)]
+fn pay_with_wrapped_sol<'a>(
+    payer_account_info: &AccountInfo<'a>,
+    payer_wrapped_sol_account_info: &AccountInfo<'a>,
+    wrapped_sol_escrow_account_info: &AccountInfo<'a>,
+    native_mint_account_info: &AccountInfo<'a>,
+    minting_pool_account_info: &AccountInfo<'a>,
+    spl_token_program_account_info: &AccountInfo<'a>,
+    sysvar_rent_account_info: &AccountInfo<'a>,
+    lamports: u64,
+) -> ProgramResult {
+    assert_pubkeys_exactitude(&spl_token::native_mint::id(), native_mint_account_info.key).expect("Error: @native_mint_account_info");
+    assert_owned_by(payer_wrapped_sol_account_info, &spl_program::id())?;
+    let payer_wrapped_sol_data = Account::unpack(&payer_wrapped_sol_account_info.data.borrow())?;
+    if payer_wrapped_sol_data.mint != spl_token::native_mint::id() {
+        Err(InglError::AddressMismatch.utilize(Some("wrapped sol account mint")))?
+    }
+    if payer_wrapped_sol_data.amount < lamports {
+        Err(ProgramError::InsufficientFunds)?
+    }
+
+    let (minting_pool_id, minting_pool_bump) = assert_pda_input(&[INGL_MINTING_POOL_KEY.as_ref()], minting_pool_account_info);
+    let (escrow_pubkey, escrow_bump) = assert_pda_input(&[WRAPPED_SOL_ESCROW_KEY.as_ref(), payer_account_info.key.as_ref()], wrapped_sol_escrow_account_info);
+    let escrow_seeds: &[&[u8]] = &[WRAPPED_SOL_ESCROW_KEY.as_ref(), payer_account_info.key.as_ref(), &[escrow_bump]];
+
+    let space = Account::LEN;
+    let rent_lamports = Rent::get()?.minimum_balance(space);
     invoke_signed(
-        &mpl_token_metadata::instruction::create_master_edition_v3(
-            mpl_token_metadata_id,
-            nft_edition_key,
-            *mint_account_info.key,
-            mint_authority_key,
-            mint_authority_key,
-            nft_metadata_key,
-            *payer_account_info.key,
-            None,
-        ),
+        &system_instruction::create_account(payer_account_info.key, &escrow_pubkey, rent_lamports, space as u64, &spl_token::id()),
+        &[payer_account_info.clone(), wrapped_sol_escrow_account_info.clone()],
+        &[escrow_seeds],
+    )?;
+    invoke(
+        &spl_token::instruction::initialize_account(&spl_token::id(), &escrow_pubkey, native_mint_account_info.key, &escrow_pubkey)?,
         &[
-            nft_edition_account_info.clone(),
-            mint_account_info.clone(),
-            mint_authority_account_info.clone(),
-            mint_authority_account_info.clone(),
-            payer_account_info.clone(),
-            metadata_account_info.clone(),
-            spl_token_program_account_info.clone(),
-            system_program_account_info.clone(),
+            wrapped_sol_escrow_account_info.clone(),
+            native_mint_account_info.clone(),
+            wrapped_sol_escrow_account_info.clone(),
             sysvar_rent_account_info.clone(),
         ],
-        &[&[INGL_MINT_AUTHORITY_KEY.as_ref(), &[mint_authority_bump]]],
     )?;
-
     invoke(
-        &spl_token::instruction::approve(
-            &spl_token::id(),
-            associated_token_account_info.key,
-            &mint_authority_key,
-            payer_account_info.key,
-            &[],
-            1,
-        )?,
+        &spl_token::instruction::transfer(&spl_token::id(), payer_wrapped_sol_account_info.key, &escrow_pubkey, payer_account_info.key, &[], lamports)?,
         &[
-            associated_token_account_info.clone(),
-            mint_authority_account_info.clone(),
+            payer_wrapped_sol_account_info.clone(),
+            wrapped_sol_escrow_account_info.clone(),
             payer_account_info.clone(),
+            spl_token_program_account_info.clone(),
         ],
     )?;
-
-    let (edition_key, _edition_bump) = Pubkey::find_program_address(
+    invoke_signed(
+        &spl_token::instruction::close_account(&spl_token::id(), &escrow_pubkey, &minting_pool_id, &escrow_pubkey, &[])?,
         &[
-            b"metadata",
+            wrapped_sol_escrow_account_info.clone(),
+            minting_pool_account_info.clone(),
+            wrapped_sol_escrow_account_info.clone(),
+        ],
+        &[escrow_seeds],
+    )?;
+    invoke_signed(
+        &system_instruction::transfer(&minting_pool_id, payer_account_info.key, rent_lamports),
+        &[minting_pool_account_info.clone(), payer_account_info.clone()],
+        &[&[INGL_MINTING_POOL_KEY.as_ref(), &[minting_pool_bump]]],
+    )?;
+    Ok(())
+}
+
+/// Creates the program owned account holding a gem's data, at its PDA derived from the gem's mint.
+fn create_gem_account<'a>(
+    program_id: &Pubkey,
+    payer_account_info: &AccountInfo<'a>,
+    mint_account_info: &AccountInfo<'a>,
+    gem_account_info: &AccountInfo<'a>,
+) -> ProgramResult {
+    let (gem_account_pubkey, gem_account_bump) = assert_pda_input(&[GEM_ACCOUNT_CONST.as_ref(), mint_account_info.key.as_ref()], gem_account_info);
+    let space = GemAccountV0_0_1::space();
+    let rent_lamports = Rent::get()?.minimum_balance(space);
+
+    invoke_signed(
+        &system_instruction::create_account(
+            payer_account_info.key,
+            &gem_account_pubkey,
+            rent_lamports,
+            space as u64,
+            program_id,
+        ),
+        &[payer_account_info.clone(), gem_account_info.clone()],
+        &[&[
+            GEM_ACCOUNT_CONST.as_ref(),
+            mint_account_info.key.as_ref(),
+            &[gem_account_bump],
+        ]],
+    )?;
+    Ok(())
+}
+
+/// Accounts needed to mint a gem's token, metadata and master edition into the payer's wallet.
+struct GemMintAccounts<'a, 'b> {
+    payer_account_info: &'a AccountInfo<'b>,
+    mint_account_info: &'a AccountInfo<'b>,
+    mint_authority_account_info: &'a AccountInfo<'b>,
+    associated_token_account_info: &'a AccountInfo<'b>,
+    spl_token_program_account_info: &'a AccountInfo<'b>,
+    sysvar_rent_account_info: &'a AccountInfo<'b>,
+    system_program_account_info: &'a AccountInfo<'b>,
+    metadata_account_info: &'a AccountInfo<'b>,
+    nft_edition_account_info: &'a AccountInfo<'b>,
+    ingl_edition_account_info: &'a AccountInfo<'b>,
+    ingl_collection_mint_info: &'a AccountInfo<'b>,
+    ingl_collection_account_info: &'a AccountInfo<'b>,
+}
+impl GemMintAccounts<'_, '_> {
+    fn validate(&self) -> ProgramResult {
+        assert_owned_by(self.ingl_edition_account_info, &metaplex::id())?;
+        assert_owned_by(self.ingl_collection_account_info, &metaplex::id())?;
+        assert_owned_by(self.ingl_collection_mint_info, &spl_program::id())?;
+        assert_pubkeys_exactitude(&system_program::id(), self.system_program_account_info.key).expect("Error: @system_program_account_info");
+        assert_pubkeys_exactitude(&spl_token::id(), self.spl_token_program_account_info.key).expect("Error: @spl_token_program_account_info");
+        assert_pubkeys_exactitude(self.sysvar_rent_account_info.key, &sysvar::rent::id())?;
+        let (_mint_authority_key, _mint_authority_bump) = assert_pda_input(&[INGL_MINT_AUTHORITY_KEY.as_ref()], self.mint_authority_account_info);
+        let (_ingl_nft_collection_key, _ingl_nft_bump) = assert_pda_input(&[INGL_NFT_COLLECTION_KEY.as_ref()], self.ingl_collection_mint_info);
+        Ok(())
+    }
+
+    /// Mints the gem's token to the payer, creates its metadata in the ingl collection and its master edition,
+    /// and approves the mint authority as delegate so that the gem can later be frozen for rarity imprints.
+    fn mint_gem(&self, name: String, numeration: u32, uri: String) -> ProgramResult {
+        let payer_account_info = self.payer_account_info;
+        let mint_account_info = self.mint_account_info;
+        let mint_authority_account_info = self.mint_authority_account_info;
+        let associated_token_account_info = self.associated_token_account_info;
+        let spl_token_program_account_info = self.spl_token_program_account_info;
+        let sysvar_rent_account_info = self.sysvar_rent_account_info;
+        let system_program_account_info = self.system_program_account_info;
+        let metadata_account_info = self.metadata_account_info;
+        let nft_edition_account_info = self.nft_edition_account_info;
+        let ingl_edition_account_info = self.ingl_edition_account_info;
+        let ingl_collection_mint_info = self.ingl_collection_mint_info;
+        let ingl_collection_account_info = self.ingl_collection_account_info;
+
+        let (mint_authority_key, mint_authority_bump) = assert_pda_input(&[INGL_MINT_AUTHORITY_KEY.as_ref()], mint_authority_account_info);
+        let (ingl_nft_collection_key, _ingl_nft_bump) = assert_pda_input(&[INGL_NFT_COLLECTION_KEY.as_ref()], ingl_collection_mint_info);
+
+        let space = 82;
+        let rent_lamports = Rent::get()?.minimum_balance(space);
+
+        assert_pubkeys_exactitude(
+            &get_associated_token_address(payer_account_info.key, mint_account_info.key),
+            associated_token_account_info.key,
+        )
+        .expect("Error: @associated_token_account_info");
+
+        let mpl_token_metadata_id = mpl_token_metadata::id();
+        let metadata_seeds = &[
+            PREFIX.as_bytes(),
+            mpl_token_metadata_id.as_ref(),
+            mint_account_info.key.as_ref(),
+        ];
+
+        let (nft_metadata_key, _nft_metadata_bump) =
+            Pubkey::find_program_address(metadata_seeds, &mpl_token_metadata::id());
+
+        assert_pubkeys_exactitude(&nft_metadata_key, metadata_account_info.key)
+            .expect("Error: @meta_data_account_info");
+
+        //create the mint account
+        invoke(
+            &system_instruction::create_account(
+                payer_account_info.key,
+                mint_account_info.key,
+                rent_lamports,
+                space as u64,
+                spl_token_program_account_info.key,
+            ),
+            &[payer_account_info.clone(), mint_account_info.clone()],
+        )?;
+
+        invoke(
+            &spl_token::instruction::initialize_mint(
+                &spl_token::id(),
+                &mint_account_info.key,
+                &mint_authority_key,
+                Some(&mint_authority_key),
+                0,
+            )?,
+            &[mint_account_info.clone(), sysvar_rent_account_info.clone()],
+        )?;
+
+        invoke(
+            &spl_associated_token_account::instruction::create_associated_token_account(
+                payer_account_info.key,
+                payer_account_info.key,
+                mint_account_info.key,
+            ),
+            &[
+                payer_account_info.clone(),
+                associated_token_account_info.clone(),
+                payer_account_info.clone(),
+                mint_account_info.clone(),
+                system_program_account_info.clone(),
+                spl_token_program_account_info.clone(),
+            ],
+        )?;
+
+        // msg!("Mint new collection token");
+        invoke_signed(
+            &spl_token::instruction::mint_to(
+                spl_token_program_account_info.key,
+                mint_account_info.key,
+                associated_token_account_info.key,
+                &mint_authority_key,
+                &[],
+                1,
+            )?,
+            &[
+                mint_account_info.clone(),
+                associated_token_account_info.clone(),
+                mint_authority_account_info.clone(),
+            ],
+            &[&[INGL_MINT_AUTHORITY_KEY.as_ref(), &[mint_authority_bump]]],
+        )?;
+
+        let mut creators = Vec::new();
+        creators.push(Creator {
+            address: mint_authority_key,
+            verified: true,
+            share: 100,
+        });
+
+
+        let metadata_seeds = &[
+            PREFIX.as_ref(),
             mpl_token_metadata_id.as_ref(),
             ingl_nft_collection_key.as_ref(),
-            b"edition",
-        ],
-        &mpl_token_metadata_id,
-    );
-    assert_pubkeys_exactitude(&edition_key, ingl_edition_account_info.key)
-        .expect("Error: @edition_account_info");
+        ];
+
+        let (collection_metadata_key, _collection_metadata_bump) =
+            Pubkey::find_program_address(metadata_seeds, &mpl_token_metadata_id);
+
+        assert_pubkeys_exactitude(&collection_metadata_key, ingl_collection_account_info.key)
+            .expect("Error: @collection_metadata_info");
+
+        // msg!("starting metadata creation");
+        invoke_signed(
+            &mpl_token_metadata::instruction::create_metadata_accounts_v3(
+                mpl_token_metadata_id,
+                nft_metadata_key,
+                *mint_account_info.key,
+                *mint_authority_account_info.key,
+                *payer_account_info.key,
+                *mint_authority_account_info.key,
+                name + " #" + &numeration.to_string(),
+                String::from("I-Gem#") + &numeration.to_string(),
+                uri,
+                Some(creators),
+                300,
+                true,
+                true,
+                Some(Collection {
+                    verified: false,
+                    key: ingl_nft_collection_key,
+                }),
+                None,
+                None,
+            ),
+            &[
+                metadata_account_info.clone(),
+                mint_account_info.clone(),
+                mint_authority_account_info.clone(),
+                payer_account_info.clone(),
+                mint_authority_account_info.clone(),
+                system_program_account_info.clone(),
+                sysvar_rent_account_info.clone(),
+            ],
+            &[&[INGL_MINT_AUTHORITY_KEY.as_ref(), &[mint_authority_bump]]],
+        )?;
+
+        let (ingl_collection_edition_key, _collection_edition_bump) = Pubkey::find_program_address(
+            &[
+                b"metadata",
+                mpl_token_metadata_id.as_ref(),
+                ingl_nft_collection_key.as_ref(),
+                b"edition",
+            ],
+            &mpl_token_metadata_id,
+        );
+        assert_pubkeys_exactitude(&ingl_collection_edition_key, ingl_edition_account_info.key)
+            .expect("Error: @edition_account_info");
+
+        // msg!("verifying collection");
+        invoke_signed(
+            &mpl_token_metadata::instruction::verify_collection(
+                mpl_token_metadata_id,
+                nft_metadata_key,
+                mint_authority_key,
+                *payer_account_info.key,
+                ingl_nft_collection_key,
+                collection_metadata_key,
+                ingl_collection_edition_key,
+                None,
+            ),
+            &[
+                metadata_account_info.clone(),
+                mint_authority_account_info.clone(),
+                payer_account_info.clone(),
+                ingl_collection_mint_info.clone(),
+                ingl_collection_account_info.clone(),
+                ingl_edition_account_info.clone(),
+            ],
+            &[&[INGL_MINT_AUTHORITY_KEY.as_ref(), &[mint_authority_bump]]],
+        )?;
+
+        let (nft_edition_key, _edition_bump) = Pubkey::find_program_address(
+            &[
+                b"metadata",
+                mpl_token_metadata_id.as_ref(),
+                mint_account_info.key.as_ref(),
+                b"edition",
+            ],
+            &mpl_token_metadata_id,
+        );
+        assert_pubkeys_exactitude(&nft_edition_key, nft_edition_account_info.key)
+            .expect("Error: @edition_account_info");
 
-    // msg!("updating update_primary_sale_happened_via_token");
-    invoke(
-        &mpl_token_metadata::instruction::update_primary_sale_happened_via_token(
-            mpl_token_metadata::id(),
-            nft_metadata_key,
-            *payer_account_info.key,
-            *associated_token_account_info.key,
-        ),
-        &[
-            metadata_account_info.clone(),
-            payer_account_info.clone(),
-            associated_token_account_info.clone(),
-        ],
-    )?;
+        // msg!("Creating master Edition account...");
+        invoke_signed(
+            &mpl_token_metadata::instruction::create_master_edition_v3(
+                mpl_token_metadata_id,
+                nft_edition_key,
+                *mint_account_info.key,
+                mint_authority_key,
+                mint_authority_key,
+                nft_metadata_key,
+                *payer_account_info.key,
+                None,
+            ),
+            &[
+                nft_edition_account_info.clone(),
+                mint_account_info.clone(),
+                mint_authority_account_info.clone(),
+                mint_authority_account_info.clone(),
+                payer_account_info.clone(),
+                metadata_account_info.clone(),
+                spl_token_program_account_info.clone(),
+                system_program_account_info.clone(),
+                sysvar_rent_account_info.clone(),
+            ],
+            &[&[INGL_MINT_AUTHORITY_KEY.as_ref(), &[mint_authority_bump]]],
+        )?;
 
-    let gem_account_data = GemAccountV0_0_1 {
-        struct_id: GemAccountVersions::GemAccountV0_0_1,
-        validation_phrase: GEM_ACCOUNT_VAL_PHRASE,
-        date_created: current_timestamp,
-        redeemable_date: current_timestamp,
-        numeration: global_gem_data.counter,
-        rarity: None,
-        funds_location: FundsLocation::MintingPool,
-        rarity_seed_time: None,
-        date_allocated: None,
-        class: class,
-        last_voted_proposal: None,
-        last_withdrawal_epoch: None,
-        last_delegation_epoch: None,
-        all_withdraws: Vec::new(),
-        all_votes: Vec::new(),
-    };
-    gem_account_data.serialize(&mut &mut gem_account_info.data.borrow_mut()[..])?;
-    Ok(())
+        invoke(
+            &spl_token::instruction::approve(
+                &spl_token::id(),
+                associated_token_account_info.key,
+                &mint_authority_key,
+                payer_account_info.key,
+                &[],
+                1,
+            )?,
+            &[
+                associated_token_account_info.clone(),
+                mint_authority_account_info.clone(),
+                payer_account_info.clone(),
+            ],
+        )?;
+
+        // msg!("updating update_primary_sale_happened_via_token");
+        invoke(
+            &mpl_token_metadata::instruction::update_primary_sale_happened_via_token(
+                mpl_token_metadata::id(),
+                nft_metadata_key,
+                *payer_account_info.key,
+                *associated_token_account_info.key,
+            ),
+            &[
+                metadata_account_info.clone(),
+                payer_account_info.clone(),
+                associated_token_account_info.clone(),
+            ],
+        )?;
+        Ok(())
+    }
 }
 
 pub fn mint_collection(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
@@ -995,8 +2392,12 @@ pub fn mint_collection(program_id: &Pubkey, accounts: &[AccountInfo]) -> Program
         is_proposal_ongoing: false,
         proposal_numeration: 0,
         pending_delegation_total: 0,
-        validator_list: Vec::new(),
+        candidate_count: 0,
         dealloced_total: 0,
+        redemption_queue_length: 0,
+        queued_redemptions: 0,
+        queued_redemption_lamports: 0,
+        reserve_advanced_total: 0,
     };
     global_gem_data.serialize(&mut &mut global_gem_account_info.data.borrow_mut()[..])?;
 
@@ -1178,6 +2579,7 @@ pub fn init_rarity_imprint(_program_id: &Pubkey, accounts: &[AccountInfo]) -> Pr
     let associated_token_account_info = next_account_info(account_info_iter)?;
     let freeze_authority_account_info = next_account_info(account_info_iter)?;
     let nft_edition_account_info = next_account_info(account_info_iter)?;
+    let config_account_info = next_account_info(account_info_iter)?;
 
     assert_is_signer(payer_account_info).unwrap();
     assert_program_owned(gem_account_info)?;
@@ -1185,7 +2587,9 @@ pub fn init_rarity_imprint(_program_id: &Pubkey, accounts: &[AccountInfo]) -> Pr
     assert_owned_by(associated_token_account_info, &spl_program::id())?;
 
     let (_gem_account_pubkey, _gem_account_bump) = assert_pda_input(&[GEM_ACCOUNT_CONST.as_ref(), mint_account_info.key.as_ref()], gem_account_info);
-    let (mint_authority_key, mint_authority_bump) = assert_pda_input(&[INGL_MINT_AUTHORITY_KEY.as_ref()], freeze_authority_account_info);
+    let (_config_pubkey, _config_bump) = assert_pda_input(&[INGL_CONFIG_KEY.as_ref()], config_account_info);
+    let config_data = InglConfig::decode(config_account_info)?;
+    config_data.assert_not_paused()?;
 
     assert_pubkeys_exactitude(
         &get_associated_token_address(payer_account_info.key, mint_account_info.key),
@@ -1209,11 +2613,30 @@ pub fn init_rarity_imprint(_program_id: &Pubkey, accounts: &[AccountInfo]) -> Pr
         Err(TokenError::AccountFrozen)?
     }
 
-    gem_data.rarity_seed_time =
-        Some(Clock::get()?.unix_timestamp as u32 + PRICE_TIME_INTERVAL as u32);
+    // Cancelled imprints are paid for and bounded as re-rolls when cancelling, so restarting after one needs no further check.
+    start_rarity_draw(&mut gem_data)?;
     gem_data.serialize(&mut &mut gem_account_info.data.borrow_mut()[..])?;
+    set_gem_frozen(true, mint_account_info, associated_token_account_info, freeze_authority_account_info, nft_edition_account_info)
+}
 
+/// Commits the gem to the price feed time and slot its rarity will be drawn from.
+fn start_rarity_draw(gem_data: &mut GemAccountV0_0_1) -> ProgramResult {
+    let clock = Clock::get()?;
+    gem_data.rarity_seed_time =
+        Some(clock.unix_timestamp as u32 + PRICE_TIME_INTERVAL as u32);
+    gem_data.rarity_reveal_slot = Some(clock.slot + RARITY_REVEAL_SLOT_DELAY);
+    Ok(())
+}
 
+/// Freezes or thaws the gem's token account. The mint authority PDA acts as the account's delegate through the gem's edition.
+fn set_gem_frozen<'a>(
+    freeze: bool,
+    mint_account_info: &AccountInfo<'a>,
+    associated_token_account_info: &AccountInfo<'a>,
+    freeze_authority_account_info: &AccountInfo<'a>,
+    nft_edition_account_info: &AccountInfo<'a>,
+) -> ProgramResult {
+    let (mint_authority_key, mint_authority_bump) = assert_pda_input(&[INGL_MINT_AUTHORITY_KEY.as_ref()], freeze_authority_account_info);
     let mpl_token_metadata_id = mpl_token_metadata::id();
     let (nft_edition_key, _nft_edition_bump) = Pubkey::find_program_address(
         &[
@@ -1227,14 +2650,25 @@ pub fn init_rarity_imprint(_program_id: &Pubkey, accounts: &[AccountInfo]) -> Pr
     assert_pubkeys_exactitude(&nft_edition_key, nft_edition_account_info.key)
         .expect("Error: @edition_account_info");
 
-    invoke_signed(
-        &mpl_token_metadata::instruction::freeze_delegated_account(
+    let instruction = if freeze {
+        mpl_token_metadata::instruction::freeze_delegated_account(
             mpl_token_metadata_id,
             mint_authority_key,
             *associated_token_account_info.key,
             nft_edition_key,
             *mint_account_info.key,
-        ),
+        )
+    } else {
+        mpl_token_metadata::instruction::thaw_delegated_account(
+            mpl_token_metadata_id,
+            mint_authority_key,
+            *associated_token_account_info.key,
+            nft_edition_key,
+            *mint_account_info.key,
+        )
+    };
+    invoke_signed(
+        &instruction,
         &[
             freeze_authority_account_info.clone(),
             associated_token_account_info.clone(),
@@ -1243,254 +2677,786 @@ pub fn init_rarity_imprint(_program_id: &Pubkey, accounts: &[AccountInfo]) -> Pr
         ],
         &[&[INGL_MINT_AUTHORITY_KEY.as_ref(), &[mint_authority_bump]]],
     )?;
+    Ok(())
+}
+
+/// Charges the re-roll fee to the treasury and counts the re-roll against the gem's `max_rarity_rerolls`.
+fn pay_rarity_reroll<'a>(
+    gem_data: &mut GemAccountV0_0_1,
+    config_data: &InglConfig,
+    payer_account_info: &AccountInfo<'a>,
+    treasury_account_info: &AccountInfo<'a>,
+    treasury_ledger_account_info: &AccountInfo<'a>,
+) -> ProgramResult {
+    let (treasury_pubkey, _treasury_bump) = assert_pda_input(&[INGL_TREASURY_ACCOUNT_KEY.as_ref()], treasury_account_info);
+    let rarity_rerolls = gem_data.rarity_rerolls.unwrap_or(0);
+    if rarity_rerolls >= config_data.max_rarity_rerolls {
+        Err(InglError::BeyondBounds.utilize(Some("rarity rerolls")))?
+    }
+
+    invoke(
+        &system_instruction::transfer(payer_account_info.key, &treasury_pubkey, config_data.reroll_fee),
+        &[payer_account_info.clone(), treasury_account_info.clone()],
+    )?;
+    update_treasury_ledger(treasury_ledger_account_info, |ledger| ledger.reroll_fees = ledger.reroll_fees.saturating_add(config_data.reroll_fee))?;
+    gem_data.rarity_rerolls = Some(rarity_rerolls + 1);
+    Ok(())
+}
+
+/// Thaws a gem whose rarity imprint could not be completed in time, e.g. because an oracle feed has no history for
+/// its seed time. The gem can then start a new imprint. By the timeout the draw's outcome could be computed offline,
+/// so cancelling is charged and bounded as a re-roll.
+pub fn cancel_rarity_imprint(_program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let payer_account_info = next_account_info(account_info_iter)?;
+    let gem_account_info = next_account_info(account_info_iter)?;
+    let mint_account_info = next_account_info(account_info_iter)?;
+    let associated_token_account_info = next_account_info(account_info_iter)?;
+    let freeze_authority_account_info = next_account_info(account_info_iter)?;
+    let nft_edition_account_info = next_account_info(account_info_iter)?;
+    let config_account_info = next_account_info(account_info_iter)?;
+    let treasury_account_info = next_account_info(account_info_iter)?;
+    let _system_program_account_info = next_account_info(account_info_iter)?;
+    let treasury_ledger_account_info = next_account_info(account_info_iter)?;
+
+    assert_is_signer(payer_account_info)?;
+    assert_program_owned(gem_account_info)?;
+    assert_owned_by(mint_account_info, &spl_program::id())?;
+    assert_owned_by(associated_token_account_info, &spl_program::id())?;
+    let (_gem_account_pubkey, _gem_account_bump) = assert_pda_input(&[GEM_ACCOUNT_CONST.as_ref(), mint_account_info.key.as_ref()], gem_account_info);
+    assert_pubkeys_exactitude(
+        &get_associated_token_address(payer_account_info.key, mint_account_info.key),
+        associated_token_account_info.key,
+    )?;
+
+    let mut gem_data = GemAccountV0_0_1::validate(GemAccountVersions::decode_unchecked(
+        &gem_account_info.data.borrow(),
+    )?)?;
+    let rarity_seed_time = match (gem_data.rarity_seed_time, &gem_data.rarity) {
+        (Some(rarity_seed_time), None) => rarity_seed_time,
+        _ => Err(ProgramError::InvalidAccountData)?,
+    };
+    let associated_token_account_data = Account::unpack(&associated_token_account_info.data.borrow())?;
+    if associated_token_account_data.amount != 1 {
+        Err(ProgramError::InsufficientFunds)?
+    }
+    if (Clock::get()?.unix_timestamp as u32) < rarity_seed_time.checked_add(RARITY_IMPRINT_TIMEOUT).unwrap() {
+        Err(InglError::TooEarly.utilize(Some("rarity imprint timeout")))?
+    }
+
+    let (_config_pubkey, _config_bump) = assert_pda_input(&[INGL_CONFIG_KEY.as_ref()], config_account_info);
+    let config_data = InglConfig::decode(config_account_info)?;
+    pay_rarity_reroll(&mut gem_data, &config_data, payer_account_info, treasury_account_info, treasury_ledger_account_info)?;
+
+    gem_data.rarity_seed_time = None;
+    gem_data.rarity_reveal_slot = None;
+    gem_data.serialize(&mut &mut gem_account_info.data.borrow_mut()[..])?;
+    set_gem_frozen(false, mint_account_info, associated_token_account_info, freeze_authority_account_info, nft_edition_account_info)
+}
+
+/// Clears an imprinted gem's rarity against a fee paid to the treasury and starts a new draw, as init_rarity_imprint does.
+pub fn reroll_rarity(_program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let payer_account_info = next_account_info(account_info_iter)?;
+    let gem_account_info = next_account_info(account_info_iter)?;
+    let mint_account_info = next_account_info(account_info_iter)?;
+    let associated_token_account_info = next_account_info(account_info_iter)?;
+    let freeze_authority_account_info = next_account_info(account_info_iter)?;
+    let nft_edition_account_info = next_account_info(account_info_iter)?;
+    let config_account_info = next_account_info(account_info_iter)?;
+    let treasury_account_info = next_account_info(account_info_iter)?;
+    let _system_program_account_info = next_account_info(account_info_iter)?;
+    let treasury_ledger_account_info = next_account_info(account_info_iter)?;
+
+    assert_is_signer(payer_account_info)?;
+    assert_program_owned(gem_account_info)?;
+    assert_owned_by(mint_account_info, &spl_program::id())?;
+    assert_owned_by(associated_token_account_info, &spl_program::id())?;
+    let (_gem_account_pubkey, _gem_account_bump) = assert_pda_input(&[GEM_ACCOUNT_CONST.as_ref(), mint_account_info.key.as_ref()], gem_account_info);
+    assert_pubkeys_exactitude(
+        &get_associated_token_address(payer_account_info.key, mint_account_info.key),
+        associated_token_account_info.key,
+    )?;
+
+    let (_config_pubkey, _config_bump) = assert_pda_input(&[INGL_CONFIG_KEY.as_ref()], config_account_info);
+    let config_data = InglConfig::decode(config_account_info)?;
+    config_data.assert_not_paused()?;
+
+    let mut gem_data = GemAccountV0_0_1::validate(GemAccountVersions::decode_unchecked(
+        &gem_account_info.data.borrow(),
+    )?)?;
+    if gem_data.rarity.is_none() {
+        Err(ProgramError::InvalidAccountData)?
+    }
+    let associated_token_account_data = Account::unpack(&associated_token_account_info.data.borrow())?;
+    if associated_token_account_data.amount != 1 {
+        Err(ProgramError::InsufficientFunds)?
+    }
+    if associated_token_account_data.is_frozen() {
+        Err(TokenError::AccountFrozen)?
+    }
+
+    pay_rarity_reroll(&mut gem_data, &config_data, payer_account_info, treasury_account_info, treasury_ledger_account_info)?;
+
+    gem_data.rarity = None;
+    start_rarity_draw(&mut gem_data)?;
+    gem_data.serialize(&mut &mut gem_account_info.data.borrow_mut()[..])?;
+    set_gem_frozen(true, mint_account_info, associated_token_account_info, freeze_authority_account_info, nft_edition_account_info)
+}
+
+pub fn imprint_rarity(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult { // here too, Marco. Do we need to assert ownership by metaplex for the NFT_edition_account. I know it will be redundant, but just for extra security.
+    let account_info_iter = &mut accounts.iter();
+    let payer_account_info = next_account_info(account_info_iter)?;
+    let gem_account_info = next_account_info(account_info_iter)?;
+    let mint_account_info = next_account_info(account_info_iter)?;
+    let associated_token_account_info = next_account_info(account_info_iter)?;
+    let freeze_authority_account_info = next_account_info(account_info_iter)?;
+    let metadata_account_info = next_account_info(account_info_iter)?;
+    let nft_edition_account_info = next_account_info(account_info_iter)?;
+    let config_account_info = next_account_info(account_info_iter)?;
+    let slot_hashes_account_info = next_account_info(account_info_iter)?;
+    let rarity_table_account_info = next_account_info(account_info_iter)?;
+    let uri_registry_account_info = next_account_info(account_info_iter)?;
+
+    let (_config_pubkey, _config_bump) = assert_pda_input(&[INGL_CONFIG_KEY.as_ref()], config_account_info);
+    let config_data = InglConfig::decode(config_account_info)?;
+    config_data.assert_not_paused()?;
+    let uri_registry_data = load_uri_registry(uri_registry_account_info)?;
+
+    // One account per feed set in the config, in the config's order.
+    let mut feed_account_infos = Vec::new();
+    for _ in 0..config_data.rarity_feeds.len() {
+        feed_account_infos.push(next_account_info(account_info_iter)?);
+    }
+
+    assert_program_owned(gem_account_info)?;
+    assert_owned_by(mint_account_info, &spl_program::id())?;
+    assert_owned_by(metadata_account_info, &mpl_token_metadata::id())?;
+    assert_owned_by(associated_token_account_info, &spl_program::id())?;
+
+    assert_pubkeys_exactitude(
+        &get_associated_token_address(payer_account_info.key, mint_account_info.key),
+        associated_token_account_info.key,
+    )
+    .expect("associated_token_account_info");
+    assert_is_signer(payer_account_info).unwrap();
+
+    let associated_token_account_data =
+        Account::unpack(&associated_token_account_info.data.borrow())?;
+    if associated_token_account_data.amount != 1 {
+        Err(ProgramError::InsufficientFunds)?
+    }
+    if !associated_token_account_data.is_frozen() {
+        Err(TokenError::AccountFrozen)?
+    }
+
+    let (_mint_authority_key, mint_authority_bump) =  assert_pda_input(&[INGL_MINT_AUTHORITY_KEY.as_ref()], freeze_authority_account_info);
+
+    let (_gem_pubkey, _gem_bump) = assert_pda_input(&[GEM_ACCOUNT_CONST.as_ref(), mint_account_info.key.as_ref()], gem_account_info);
+
+    set_gem_frozen(false, mint_account_info, associated_token_account_info, freeze_authority_account_info, nft_edition_account_info)?;
+
+    let mut gem_data = GemAccountV0_0_1::validate(GemAccountVersions::decode_unchecked(
+        &gem_account_info.data.borrow(),
+    )?)?;
+    let rarity_seed_time = match (gem_data.rarity_seed_time, &gem_data.rarity) {
+        (Some(rarity_seed_time), None) => rarity_seed_time,
+        (None, None) => Err(InglError::TooEarly.utilize(Some("rarity imprint not initialized")))?,
+        _ => Err(ProgramError::InvalidAccountData)?,
+    };
+    let now = Clock::get()?;
+    msg!(
+        "now: {}, seed_time: {}",
+        now.unix_timestamp,
+        rarity_seed_time
+    );
+    if (now.unix_timestamp as u32) < rarity_seed_time {
+        Err(InglError::TooEarly.utilize(Some("imprint_rarity")))?
+    }
+
+    let price_randomness = PriceFeedRandomness::new(
+        feed_account_infos,
+        &config_data.rarity_feeds,
+        &config_data.oracle_program_id,
+        rarity_seed_time,
+        config_data.max_feed_staleness,
+    )?;
+    // Gems committed before the slot hash reveal was introduced have no reveal slot and keep drawing from the price feeds only.
+    let rarity_hash_bytes = match gem_data.rarity_reveal_slot {
+        Some(reveal_slot) => {
+            let slot_randomness = SlotHashRandomness::new(slot_hashes_account_info, reveal_slot)?;
+            // Withholding the reveal until the slot hash is dropped from the sysvar must not improve the outcome.
+            if slot_randomness.is_expired()? {
+                None
+            } else {
+                Some(mix_randomness(&[&slot_randomness, &price_randomness], mint_account_info.key, program_id)?)
+            }
+        }
+        None => Some(mix_randomness(&[&price_randomness], mint_account_info.key, program_id)?),
+    };
+
+    gem_data.rarity = match rarity_hash_bytes {
+        Some(rarity_hash_bytes) => {
+            let random_value = uniform_random_value(&rarity_hash_bytes, RARITY_VALUE_RANGE);
+            msg!("Random value: {:?}", random_value);
+            let (_rarity_table_pubkey, _rarity_table_bump) = assert_pda_input(&[RARITY_TABLE_KEY.as_ref(), &gem_data.class.get_seed()], rarity_table_account_info);
+            if rarity_table_account_info.data_is_empty() {
+                gem_data.class.get_rarity(random_value)
+            } else {
+                Some(RarityTable::decode(rarity_table_account_info)?.get_rarity(random_value))
+            }
+        }
+        None => Some(Rarity::Common),
+    };
+
+    let mpl_token_metadata_id = mpl_token_metadata::id();
+    let metadata_seeds = &[
+        PREFIX.as_bytes(),
+        mpl_token_metadata_id.as_ref(),
+        mint_account_info.key.as_ref(),
+    ];
+
+    let (nft_metadata_key, _nft_metadata_bump) =
+        Pubkey::find_program_address(metadata_seeds, &mpl_token_metadata::id());
+
+    assert_pubkeys_exactitude(&nft_metadata_key, metadata_account_info.key)
+        .expect("Error: @meta_data_account_info");
+
+    let gem_metadata = Metadata::deserialize(&mut &metadata_account_info.data.borrow()[..])?;
+
+    invoke_signed(
+        &mpl_token_metadata::instruction::update_metadata_accounts_v2(
+            mpl_token_metadata_id,
+            *metadata_account_info.key,
+            *freeze_authority_account_info.key,
+            Some(*freeze_authority_account_info.key),
+            Some(DataV2 {
+                uri: uri_registry_data.get_uri(gem_data.class, gem_data.rarity.clone()),
+                uses: gem_metadata.uses,
+                name: gem_metadata.data.name,
+                symbol: gem_metadata.data.symbol,
+                collection: gem_metadata.collection,
+                creators: gem_metadata.data.creators,
+                seller_fee_basis_points: gem_metadata.data.seller_fee_basis_points,
+            }),
+            Some(gem_metadata.primary_sale_happened),
+            Some(gem_metadata.is_mutable),
+        ),
+        &[
+            metadata_account_info.clone(),
+            freeze_authority_account_info.clone(),
+        ],
+        &[&[INGL_MINT_AUTHORITY_KEY.as_ref(), &[mint_authority_bump]]],
+    )?;
+    gem_data.serialize(&mut &mut gem_account_info.data.borrow_mut()[..])?;
+    Ok(())
+}
+
+/// Re-points the metadata of already minted gems at the registry's current uris, e.g. after an artwork change.
+/// Gems whose uri is already up to date are left untouched.
+pub fn refresh_gem_metadata(_program_id: &Pubkey, accounts: &[AccountInfo], num_gems: u8) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let uri_registry_account_info = next_account_info(account_info_iter)?;
+    let mint_authority_account_info = next_account_info(account_info_iter)?;
+
+    let uri_registry_data = load_uri_registry(uri_registry_account_info)?;
+    let (_mint_authority_key, mint_authority_bump) = assert_pda_input(&[INGL_MINT_AUTHORITY_KEY.as_ref()], mint_authority_account_info);
+    let mpl_token_metadata_id = mpl_token_metadata::id();
+
+    for _ in 0..num_gems {
+        let mint_account_info = next_account_info(account_info_iter)?;
+        let gem_account_info = next_account_info(account_info_iter)?;
+        let metadata_account_info = next_account_info(account_info_iter)?;
+
+        assert_program_owned(gem_account_info)?;
+        assert_owned_by(metadata_account_info, &mpl_token_metadata_id)?;
+        let (_gem_pubkey, _gem_bump) = assert_pda_input(&[GEM_ACCOUNT_CONST.as_ref(), mint_account_info.key.as_ref()], gem_account_info);
+        let (nft_metadata_key, _nft_metadata_bump) = Pubkey::find_program_address(
+            &[PREFIX.as_bytes(), mpl_token_metadata_id.as_ref(), mint_account_info.key.as_ref()],
+            &mpl_token_metadata_id,
+        );
+        assert_pubkeys_exactitude(&nft_metadata_key, metadata_account_info.key)?;
+
+        let gem_data = GemAccountV0_0_1::validate(GemAccountVersions::decode_unchecked(
+            &gem_account_info.data.borrow(),
+        )?)?;
+        let gem_metadata = Metadata::deserialize(&mut &metadata_account_info.data.borrow()[..])?;
+        let uri = uri_registry_data.get_uri(gem_data.class, gem_data.rarity);
+        // Metaplex pads the stored uri with null bytes.
+        if gem_metadata.data.uri.trim_end_matches(char::from(0)) == uri {
+            continue;
+        }
 
+        invoke_signed(
+            &mpl_token_metadata::instruction::update_metadata_accounts_v2(
+                mpl_token_metadata_id,
+                *metadata_account_info.key,
+                *mint_authority_account_info.key,
+                Some(*mint_authority_account_info.key),
+                Some(DataV2 {
+                    uri,
+                    uses: gem_metadata.uses,
+                    name: gem_metadata.data.name,
+                    symbol: gem_metadata.data.symbol,
+                    collection: gem_metadata.collection,
+                    creators: gem_metadata.data.creators,
+                    seller_fee_basis_points: gem_metadata.data.seller_fee_basis_points,
+                }),
+                Some(gem_metadata.primary_sale_happened),
+                Some(gem_metadata.is_mutable),
+            ),
+            &[
+                metadata_account_info.clone(),
+                mint_authority_account_info.clone(),
+            ],
+            &[&[INGL_MINT_AUTHORITY_KEY.as_ref(), &[mint_authority_bump]]],
+        )?;
+    }
     Ok(())
 }
 
-pub fn imprint_rarity(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult { // here too, Marco. Do we need to assert ownership by metaplex for the NFT_edition_account. I know it will be redundant, but just for extra security.
+pub fn redeem_nft(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
     let account_info_iter = &mut accounts.iter();
     let payer_account_info = next_account_info(account_info_iter)?;
-    let gem_account_info = next_account_info(account_info_iter)?;
     let mint_account_info = next_account_info(account_info_iter)?;
+    let minting_pool_account_info = next_account_info(account_info_iter)?;
     let associated_token_account_info = next_account_info(account_info_iter)?;
-    let freeze_authority_account_info = next_account_info(account_info_iter)?;
+    let mint_authority_account_info = next_account_info(account_info_iter)?;
+    let gem_account_info = next_account_info(account_info_iter)?;
     let metadata_account_info = next_account_info(account_info_iter)?;
-    let nft_edition_account_info = next_account_info(account_info_iter)?;
+    let edition_account_info = next_account_info(account_info_iter)?;
+    let ingl_collection_account_info = next_account_info(account_info_iter)?;
+    let spl_token_program_account_info = next_account_info(account_info_iter)?;
+    let program_treasury_account_info = next_account_info(account_info_iter)?;
+    let config_account_info = next_account_info(account_info_iter)?;
+    let referrer_account_info = next_account_info(account_info_iter)?;
+    let liquidity_reserve_account_info = next_account_info(account_info_iter)?;
+    let treasury_ledger_account_info = next_account_info(account_info_iter)?;
 
-    let btc_feed_account_info = next_account_info(account_info_iter)?;
-    let sol_feed_account_info = next_account_info(account_info_iter)?;
-    let eth_feed_account_info = next_account_info(account_info_iter)?;
-    let bnb_feed_account_info = next_account_info(account_info_iter)?;
+    let (_config_pubkey, _config_bump) = assert_pda_input(&[INGL_CONFIG_KEY.as_ref()], config_account_info);
+    let config_data = InglConfig::decode(config_account_info)?;
+    let (liquidity_reserve_id, _liquidity_reserve_bump) = assert_pda_input(&[LIQUIDITY_RESERVE_KEY.as_ref()], liquidity_reserve_account_info);
 
+    assert_is_signer(payer_account_info).unwrap();
     assert_program_owned(gem_account_info)?;
     assert_owned_by(mint_account_info, &spl_program::id())?;
-    assert_owned_by(metadata_account_info, &mpl_token_metadata::id())?;
     assert_owned_by(associated_token_account_info, &spl_program::id())?;
-    assert_owned_by(btc_feed_account_info, &SWITCHBOARD_V2_DEVNET).or(assert_owned_by(
-        btc_feed_account_info,
-        &SWITCHBOARD_V2_MAINNET,
-    ))?;
-    assert_owned_by(sol_feed_account_info, &SWITCHBOARD_V2_DEVNET).or(assert_owned_by(
-        sol_feed_account_info,
-        &SWITCHBOARD_V2_MAINNET,
-    ))?;
-    assert_owned_by(eth_feed_account_info, &SWITCHBOARD_V2_DEVNET).or(assert_owned_by(
-        eth_feed_account_info,
-        &SWITCHBOARD_V2_MAINNET,
-    ))?;
-    assert_owned_by(bnb_feed_account_info, &SWITCHBOARD_V2_DEVNET).or(assert_owned_by(
-        bnb_feed_account_info,
-        &SWITCHBOARD_V2_MAINNET,
-    ))?;
-
     assert_pubkeys_exactitude(
         &get_associated_token_address(payer_account_info.key, mint_account_info.key),
         associated_token_account_info.key,
     )
     .expect("associated_token_account_info");
-    assert_is_signer(payer_account_info).unwrap();
+
+    let (mint_authority_key, _mint_authority_bump) = assert_pda_input(&[INGL_MINT_AUTHORITY_KEY.as_ref()], mint_authority_account_info);
+
+    let (minting_pool_id, minting_pool_bump) = assert_pda_input(&[INGL_MINTING_POOL_KEY.as_ref()], minting_pool_account_info);
+
+    let (_gem_pubkey, _gem_bump) = assert_pda_input(&[GEM_ACCOUNT_CONST.as_ref(), mint_account_info.key.as_ref()], gem_account_info);
 
     let associated_token_account_data =
         Account::unpack(&associated_token_account_info.data.borrow())?;
     if associated_token_account_data.amount != 1 {
         Err(ProgramError::InsufficientFunds)?
     }
-    if !associated_token_account_data.is_frozen() {
-        Err(TokenError::AccountFrozen)?
-    }
 
-    let (mint_authority_key, mint_authority_bump) =  assert_pda_input(&[INGL_MINT_AUTHORITY_KEY.as_ref()], freeze_authority_account_info);
+    let gem_data = GemAccountV0_0_1::validate(GemAccountVersions::decode_unchecked(
+        &gem_account_info.data.borrow(),
+    )?)?;
 
-    let (_gem_pubkey, _gem_bump) = assert_pda_input(&[GEM_ACCOUNT_CONST.as_ref(), mint_account_info.key.as_ref()], gem_account_info);
+    match gem_data.funds_location {
+        FundsLocation::MintingPool => {}
+        _ => Err(InglError::InvalidFundsLocation.utilize(Some("gem_account_redeem_nft")))?,
+    };
 
-    assert_pubkeys_exactitude(
-        &Pubkey::from_str(BTC_FEED_PUBLIC_KEY).unwrap(),
-        btc_feed_account_info.key,
-    )
-    .expect("btc_history_account_info_owner");
-    assert_pubkeys_exactitude(
-        &Pubkey::from_str(SOL_FEED_PUBLIC_KEY).unwrap(),
-        sol_feed_account_info.key,
-    )
-    .expect("sol_price_account_info_owner");
-    assert_pubkeys_exactitude(
-        &Pubkey::from_str(ETH_FEED_PUBLIC_KEY).unwrap(),
-        eth_feed_account_info.key,
-    )
-    .expect("eth_price_account_info_owner");
-    assert_pubkeys_exactitude(
-        &Pubkey::from_str(BNB_FEED_PUBLIC_KEY).unwrap(),
-        bnb_feed_account_info.key,
-    )
-    .expect("bnb_price_account_info_owner");
+    let now = Clock::get()?.unix_timestamp as u32;
+    if gem_data.redeemable_date > now {
+        Err(InglError::TooEarly.utilize(Some("redeem_nft")))?;
+    }
+    gem_data.assert_no_open_governance_vote(now)?;
 
-    let mpl_token_metadata_id = mpl_token_metadata::id();
-    let (nft_edition_key, _nft_edition_bump) = Pubkey::find_program_address(
-        &[
-            b"metadata",
-            mpl_token_metadata_id.as_ref(),
-            mint_account_info.key.as_ref(),
-            b"edition",
-        ],
-        &mpl_token_metadata_id,
-    );
+    let redeem_fees = config_data.get_redeem_fee(gem_data.class.get_class_lamports(), now.saturating_sub(gem_data.date_created));
+    if redeem_fees > 0 {
+        let (program_treasury_id, _treasury_bump) = assert_pda_input(&[INGL_TREASURY_ACCOUNT_KEY.as_ref()], program_treasury_account_info);
+
+        // The reserve's share is only set aside once it keeps the reserve rent exempt.
+        let mut reserve_funds = (redeem_fees as u128 * config_data.liquidity_reserve_share as u128 / 100) as u64;
+        if liquidity_reserve_account_info.lamports().checked_add(reserve_funds).ok_or(InglError::BeyondBounds)? < Rent::get()?.minimum_balance(0) {
+            reserve_funds = 0;
+        }
+        if reserve_funds > 0 {
+            invoke_signed(
+                &system_instruction::transfer(&minting_pool_id, &liquidity_reserve_id, reserve_funds),
+                &[
+                    minting_pool_account_info.clone(),
+                    liquidity_reserve_account_info.clone(),
+                ],
+                &[&[INGL_MINTING_POOL_KEY.as_ref(), &[minting_pool_bump]]],
+            )?;
+        }
+        let distributed_fees = redeem_fees.checked_sub(reserve_funds).ok_or(InglError::BeyondBounds)?;
+        update_treasury_ledger(treasury_ledger_account_info, |ledger| ledger.redeem_fees = ledger.redeem_fees.saturating_add(distributed_fees))?;
+
+        let treasury_funds = (distributed_fees as f64 * TREASURY_FEE_MULTIPLYER as f64 / 100.0) as u64;
+        let mint_authority_funds = distributed_fees.checked_sub(treasury_funds).ok_or(InglError::BeyondBounds).unwrap();
+
+        // The referrer's share stays in the treasury until claimed. Gems without a referrer ignore the referrer account.
+        if let Some(referrer) = gem_data.referrer {
+            let (_referrer_pubkey, _referrer_bump) = assert_pda_input(&[REFERRER_KEY.as_ref(), referrer.as_ref()], referrer_account_info);
+            let mut referrer_data = Referrer::decode(referrer_account_info)?;
+            referrer_data.accrue(&config_data, treasury_funds);
+            referrer_data.serialize(&mut &mut referrer_account_info.data.borrow_mut()[..])?;
+        }
 
-    assert_pubkeys_exactitude(&nft_edition_key, nft_edition_account_info.key).expect("Error: @edition_account_info");
+        invoke_signed(
+            &system_instruction::transfer(
+                &minting_pool_id,
+                &program_treasury_id,
+                treasury_funds,
+            ),
+            &[
+                minting_pool_account_info.clone(),
+                program_treasury_account_info.clone(),
+            ],
+            &[&[INGL_MINTING_POOL_KEY.as_ref(), &[minting_pool_bump]]],
+        )?;
+        invoke_signed(
+            &system_instruction::transfer(
+                &minting_pool_id,
+                &mint_authority_key,
+                mint_authority_funds,
+            ),
+            &[
+                minting_pool_account_info.clone(),
+                mint_authority_account_info.clone(),
+            ],
+            &[&[INGL_MINTING_POOL_KEY.as_ref(), &[minting_pool_bump]]],
+        )?;
+    }
 
+    msg!("Redeem_fees: {:?} lamports: {:?}",redeem_fees, gem_data.class.get_class_lamports());
     invoke_signed(
-        &mpl_token_metadata::instruction::thaw_delegated_account(
-            mpl_token_metadata_id,
-            mint_authority_key,
-            *associated_token_account_info.key,
-            nft_edition_key,
-            *mint_account_info.key,
+        &system_instruction::transfer(
+            &minting_pool_id,
+            payer_account_info.key,
+            gem_data.class.get_class_lamports().checked_sub(redeem_fees).ok_or(InglError::BeyondBounds).unwrap(),
         ),
         &[
-            freeze_authority_account_info.clone(),
-            associated_token_account_info.clone(),
-            nft_edition_account_info.clone(),
-            mint_account_info.clone(),
+            minting_pool_account_info.clone(),
+            payer_account_info.clone(),
         ],
-        &[&[INGL_MINT_AUTHORITY_KEY.as_ref(), &[mint_authority_bump]]],
+        &[&[INGL_MINTING_POOL_KEY.as_ref(), &[minting_pool_bump]]],
     )?;
 
-    let mut gem_data = GemAccountV0_0_1::validate(GemAccountVersions::decode_unchecked(
+    burn_gem(
+        program_id,
+        payer_account_info,
+        mint_account_info,
+        associated_token_account_info,
+        gem_account_info,
+        metadata_account_info,
+        edition_account_info,
+        ingl_collection_account_info,
+        spl_token_program_account_info,
+    )?;
+
+    Ok(())
+}
+
+/// Simulation only entrypoint quoting the fee `redeem_nft` would charge for the gem right now.
+/// The fee is set as the transaction's return data, in little endian lamports. Takes the gem account and the config.
+pub fn quote_redeem(_program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let gem_account_info = next_account_info(account_info_iter)?;
+    let config_account_info = next_account_info(account_info_iter)?;
+
+    let (_config_pubkey, _config_bump) = assert_pda_input(&[INGL_CONFIG_KEY.as_ref()], config_account_info);
+    let config_data = InglConfig::decode(config_account_info)?;
+
+    assert_program_owned(gem_account_info)?;
+    let gem_data = GemAccountV0_0_1::validate(GemAccountVersions::decode_unchecked(
         &gem_account_info.data.borrow(),
     )?)?;
-    let now = Clock::get()?;
-    msg!(
-        "now: {}, seed_time: {}",
-        now.unix_timestamp,
-        gem_data.rarity_seed_time.unwrap()
-    );
-    if (now.unix_timestamp as u32) < gem_data.rarity_seed_time.unwrap() {
-        Err(InglError::TooEarly.utilize(Some("imprint_rarity")))?
+
+    let now = Clock::get()?.unix_timestamp as u32;
+    let redeem_fees = config_data.get_redeem_fee(gem_data.class.get_class_lamports(), now.saturating_sub(gem_data.date_created));
+    msg!("Redeem_fees: {:?} lamports: {:?}", redeem_fees, gem_data.class.get_class_lamports());
+    set_return_data(&redeem_fees.to_le_bytes());
+    Ok(())
+}
+
+/// Queues a delegated or allocated gem for redemption, sparing its holder the undelegate, rebalance and deallocate round trip.
+/// A delegated gem is undelegated right away, and can be claimed once its vote account's stake has been rebalanced.
+/// Takes the `undelegate_nft` accounts, whose vote account ones are ignored for gems that aren't delegated.
+pub fn redeem_queued(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let payer_account_info = next_account_info(account_info_iter)?;
+    let _pd_pool_account_info = next_account_info(account_info_iter)?;
+    let _vote_account_info = next_account_info(account_info_iter)?;
+    let _ingl_vote_data_account_info = next_account_info(account_info_iter)?;
+    let mint_account_info = next_account_info(account_info_iter)?;
+    let gem_account_data_info = next_account_info(account_info_iter)?;
+    let associated_token_account_info = next_account_info(account_info_iter)?;
+    let global_gem_account_info = next_account_info(account_info_iter)?;
+
+    assert_is_signer(payer_account_info)?;
+    assert_program_owned(gem_account_data_info)?;
+    assert_owned_by(associated_token_account_info, &spl_program::id())?;
+    let (_gem_account_pubkey, _gem_account_bump) = assert_pda_input(&[GEM_ACCOUNT_CONST.as_ref(), mint_account_info.key.as_ref()], gem_account_data_info);
+    let (_global_gem_pubkey, _global_gem_bump) = assert_pda_input(&[GLOBAL_GEM_KEY.as_ref()], global_gem_account_info);
+    assert_pubkeys_exactitude(
+        &get_associated_token_address(payer_account_info.key, mint_account_info.key),
+        associated_token_account_info.key,
+    )
+    .expect("Error: @associated_token_address");
+    let associated_token_account_data = Account::unpack(&associated_token_account_info.data.borrow())?;
+    if associated_token_account_data.amount != 1 {
+        Err(ProgramError::InsufficientFunds)?
     }
-    if let Some(_) = gem_data.rarity {
-        Err(ProgramError::InvalidAccountData)?
+
+    let gem_account_data = GemAccountV0_0_1::validate(GemAccountVersions::decode_unchecked(
+        &gem_account_data_info.data.borrow(),
+    )?)?;
+    gem_account_data.assert_not_queued()?;
+    if gem_account_data.rarity_seed_time.is_some() && gem_account_data.rarity.is_none() {
+        Err(InglError::TooEarly.utilize(Some("rarity imprint pending")))?
     }
 
-    let btc_history = AggregatorHistoryBuffer::new(btc_feed_account_info)?;
-    let AggregatorHistoryRow {
-        value: btc_value,
-        timestamp: _,
-    } = btc_history
-        .lower_bound(gem_data.rarity_seed_time.unwrap() as i64)
-        .unwrap();
-    let btc_price = btc_value.mantissa * 10.pow(btc_value.scale) as i128;
-
-    let sol_history = AggregatorHistoryBuffer::new(sol_feed_account_info)?;
-    let AggregatorHistoryRow {
-        value: sol_value,
-        timestamp: _,
-    } = sol_history
-        .lower_bound(gem_data.rarity_seed_time.unwrap() as i64)
-        .unwrap();
-    let sol_price = sol_value.mantissa * 10.pow(sol_value.scale) as i128;
-
-    let eth_history = AggregatorHistoryBuffer::new(eth_feed_account_info)?;
-    let AggregatorHistoryRow {
-        value: eth_value,
-        timestamp: _,
-    } = eth_history
-        .lower_bound(gem_data.rarity_seed_time.unwrap() as i64)
-        .unwrap();
-    let eth_price = eth_value.mantissa * 10.pow(eth_value.scale) as i128;
-
-    let bnb_history = AggregatorHistoryBuffer::new(bnb_feed_account_info)?;
-    let AggregatorHistoryRow {
-        value: bnb_value,
-        timestamp: _,
-    } = bnb_history
-        .lower_bound(gem_data.rarity_seed_time.unwrap() as i64)
-        .unwrap();
-    let bnb_price = bnb_value.mantissa * 10.pow(bnb_value.scale) as i128;
+    let unwinding_vote_account = match gem_account_data.funds_location {
+        FundsLocation::MintingPool => Err(InglError::InvalidFundsLocation.utilize(Some("gems in the minting pool are redeemed directly")))?,
+        FundsLocation::PDPool => None,
+        FundsLocation::VoteAccount { vote_account_id } => {
+            undelegate_nft(program_id, accounts)?;
+            Some(vote_account_id)
+        }
+    };
 
-    let rarity_hash_string = hashv(&[&btc_price.to_be_bytes(), &sol_price.to_be_bytes(), &eth_price.to_be_bytes(), &bnb_price.to_be_bytes(), &mint_account_info.key.to_bytes(), &program_id.to_bytes()]);
-    let rarity_hash_bytes = rarity_hash_string.to_bytes();
+    // Reloaded, as undelegating rewrites both accounts.
+    let mut gem_account_data = GemAccountV0_0_1::validate(GemAccountVersions::decode_unchecked(
+        &gem_account_data_info.data.borrow(),
+    )?)?;
+    let mut global_gem_data = GlobalGems::decode(global_gem_account_info)?;
 
-    let mut byte_sum: u64 = 0;
-    for byte in rarity_hash_bytes {
-        byte_sum = byte_sum + (byte as u64).pow(2);
-    }
-    
-    let random_value = byte_sum*10%10000;
-    msg!("Bytes product: {:?}", random_value);
-    gem_data.rarity = gem_data.class.get_rarity(random_value);
+    let position = global_gem_data.redemption_queue_length;
+    gem_account_data.queued_redemption = Some(QueuedRedemption {
+        position,
+        requested_epoch: Clock::get()?.epoch,
+        unwinding_vote_account,
+    });
+    global_gem_data.redemption_queue_length = position.checked_add(1).ok_or(InglError::BeyondBounds)?;
+    global_gem_data.queued_redemptions = global_gem_data.queued_redemptions.checked_add(1).ok_or(InglError::BeyondBounds)?;
+    global_gem_data.queued_redemption_lamports = global_gem_data
+        .queued_redemption_lamports
+        .checked_add(gem_account_data.class.get_class_lamports())
+        .ok_or(InglError::BeyondBounds)?;
+    msg!("Redemption queued at position {:?}", position);
 
-    let mpl_token_metadata_id = mpl_token_metadata::id();
-    let metadata_seeds = &[
-        PREFIX.as_bytes(),
-        mpl_token_metadata_id.as_ref(),
-        mint_account_info.key.as_ref(),
-    ];
+    gem_account_data.serialize(&mut &mut gem_account_data_info.data.borrow_mut()[..])?;
+    global_gem_data.serialize(&mut &mut global_gem_account_info.data.borrow_mut()[..])?;
+    Ok(())
+}
 
-    let (nft_metadata_key, _nft_metadata_bump) =
-        Pubkey::find_program_address(metadata_seeds, &mpl_token_metadata::id());
+/// Pays out a queued redemption and burns the gem, charging the same fee as `redeem_nft`.
+/// Takes the global gem account, the pd pool and the ingl vote data account of the vote account the gem was undelegated from,
+/// which is ignored for gems that weren't delegated, followed by the `redeem_nft` accounts.
+pub fn claim_redemption(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let global_gem_account_info = next_account_info(account_info_iter)?;
+    let pd_pool_account_info = next_account_info(account_info_iter)?;
+    let ingl_vote_data_account_info = next_account_info(account_info_iter)?;
+    let redeem_accounts = account_info_iter.as_slice();
+    let payer_account_info = next_account_info(account_info_iter)?;
+    let mint_account_info = next_account_info(account_info_iter)?;
+    let minting_pool_account_info = next_account_info(account_info_iter)?;
+    let _associated_token_account_info = next_account_info(account_info_iter)?;
+    let _mint_authority_account_info = next_account_info(account_info_iter)?;
+    let gem_account_data_info = next_account_info(account_info_iter)?;
 
-    assert_pubkeys_exactitude(&nft_metadata_key, metadata_account_info.key)
-        .expect("Error: @meta_data_account_info");
+    assert_is_signer(payer_account_info)?;
+    assert_program_owned(gem_account_data_info)?;
+    let (_gem_account_pubkey, _gem_account_bump) = assert_pda_input(&[GEM_ACCOUNT_CONST.as_ref(), mint_account_info.key.as_ref()], gem_account_data_info);
+    let (_global_gem_pubkey, _global_gem_bump) = assert_pda_input(&[GLOBAL_GEM_KEY.as_ref()], global_gem_account_info);
+    let (pd_pool_pubkey, pd_pool_bump) = assert_pda_input(&[PD_POOL_KEY.as_ref()], pd_pool_account_info);
+    let (minting_pool_id, _minting_pool_bump) = assert_pda_input(&[INGL_MINTING_POOL_KEY.as_ref()], minting_pool_account_info);
 
-    let gem_metadata = Metadata::deserialize(&mut &metadata_account_info.data.borrow()[..])?;
+    let mut gem_account_data = GemAccountV0_0_1::validate(GemAccountVersions::decode_unchecked(
+        &gem_account_data_info.data.borrow(),
+    )?)?;
+    let mut global_gem_data = GlobalGems::decode(global_gem_account_info)?;
+    gem_account_data.assert_no_open_governance_vote(Clock::get()?.unix_timestamp as u32)?;
+
+    let queued_redemption = gem_account_data
+        .queued_redemption
+        .take()
+        .ok_or_else(|| InglError::InvalidFundsLocation.utilize(Some("gem isn't queued for redemption")))?;
+    if let Some(vote_account_id) = queued_redemption.unwinding_vote_account {
+        let (_expected_vote_data_pubkey, _expected_vote_data_bump) = assert_pda_input(&[VOTE_DATA_ACCOUNT_KEY.as_ref(), vote_account_id.as_ref()], ingl_vote_data_account_info);
+        let ingl_vote_account_data = InglVoteAccountData::decode(ingl_vote_data_account_info)?;
+        if ingl_vote_account_data.pending_validator_rewards.is_some() || ingl_vote_account_data.last_rebalance_epoch <= queued_redemption.requested_epoch {
+            Err(InglError::TooEarly.utilize(Some("the gem's stake is still unwinding")))?
+        }
+    }
 
+    match gem_account_data.funds_location {
+        FundsLocation::PDPool => gem_account_data.funds_location = FundsLocation::MintingPool,
+        _ => Err(InglError::InvalidFundsLocation.utilize(Some("gem's funds location.")))?,
+    }
+    let lamports = gem_account_data.class.get_class_lamports();
+    // Every position before the oldest unclaimed one has been claimed, so that one is never beyond the claim count.
+    // Later positions may only jump ahead while the pd pool can still pay every queued redemption.
+    let claimed = global_gem_data.redemption_queue_length.checked_sub(global_gem_data.queued_redemptions).ok_or(InglError::BeyondBounds)?;
+    if queued_redemption.position > claimed && global_gem_data.pd_pool_total < global_gem_data.queued_redemption_lamports {
+        Err(InglError::TooEarly.utilize(Some("earlier queued redemptions are paid first")))?
+    }
     invoke_signed(
-        &mpl_token_metadata::instruction::update_metadata_accounts_v2(
-            mpl_token_metadata_id,
-            *metadata_account_info.key,
-            *freeze_authority_account_info.key,
-            Some(*freeze_authority_account_info.key),
-            Some(DataV2 {
-                uri: String::from(nfts::get_uri(gem_data.class, gem_data.rarity.clone())),
-                uses: gem_metadata.uses,
-                name: gem_metadata.data.name,
-                symbol: gem_metadata.data.symbol,
-                collection: gem_metadata.collection,
-                creators: gem_metadata.data.creators,
-                seller_fee_basis_points: gem_metadata.data.seller_fee_basis_points,
-            }),
-            Some(gem_metadata.primary_sale_happened),
-            Some(gem_metadata.is_mutable),
-        ),
+        &system_instruction::transfer(&pd_pool_pubkey, &minting_pool_id, lamports),
+        &[
+            pd_pool_account_info.clone(),
+            minting_pool_account_info.clone(),
+        ],
+        &[&[PD_POOL_KEY.as_ref(), &[pd_pool_bump]]],
+    )?;
+    global_gem_data.pd_pool_total = global_gem_data.pd_pool_total.checked_sub(lamports).ok_or(InglError::BeyondBounds)?;
+    global_gem_data.queued_redemptions = global_gem_data.queued_redemptions.checked_sub(1).ok_or(InglError::BeyondBounds)?;
+    global_gem_data.queued_redemption_lamports = global_gem_data.queued_redemption_lamports.checked_sub(lamports).ok_or(InglError::BeyondBounds)?;
+
+    gem_account_data.serialize(&mut &mut gem_account_data_info.data.borrow_mut()[..])?;
+    global_gem_data.serialize(&mut &mut global_gem_account_info.data.borrow_mut()[..])?;
+
+    redeem_nft(program_id, redeem_accounts)
+}
+
+/// Redeems a delegated gem right away, the liquidity reserve advancing its lamports until the vote account's next rebalance
+/// unwinds its stake. The reserve keeps `instant_exit_discount` percent of the payout.
+/// Takes the `undelegate_nft` accounts followed by the minting pool, mint authority, metadata, edition, collection,
+/// spl token program, program treasury, config, referrer, liquidity reserve and treasury ledger accounts.
+pub fn instant_redeem(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let payer_account_info = next_account_info(account_info_iter)?;
+    let _pd_pool_account_info = next_account_info(account_info_iter)?;
+    let vote_account_info = next_account_info(account_info_iter)?;
+    let ingl_vote_data_account_info = next_account_info(account_info_iter)?;
+    let mint_account_info = next_account_info(account_info_iter)?;
+    let gem_account_data_info = next_account_info(account_info_iter)?;
+    let associated_token_account_info = next_account_info(account_info_iter)?;
+    let global_gem_account_info = next_account_info(account_info_iter)?;
+    let _validator_account_info = next_account_info(account_info_iter)?;
+    let _system_program_account_info = next_account_info(account_info_iter)?;
+    let _authorized_withdrawer_info = next_account_info(account_info_iter)?;
+    let minting_pool_account_info = next_account_info(account_info_iter)?;
+    let mint_authority_account_info = next_account_info(account_info_iter)?;
+    let metadata_account_info = next_account_info(account_info_iter)?;
+    let edition_account_info = next_account_info(account_info_iter)?;
+    let ingl_collection_account_info = next_account_info(account_info_iter)?;
+    let spl_token_program_account_info = next_account_info(account_info_iter)?;
+    let program_treasury_account_info = next_account_info(account_info_iter)?;
+    let config_account_info = next_account_info(account_info_iter)?;
+    let referrer_account_info = next_account_info(account_info_iter)?;
+    let liquidity_reserve_account_info = next_account_info(account_info_iter)?;
+    let treasury_ledger_account_info = next_account_info(account_info_iter)?;
+
+    assert_is_signer(payer_account_info)?;
+    assert_program_owned(gem_account_data_info)?;
+    let (_gem_account_pubkey, _gem_account_bump) = assert_pda_input(&[GEM_ACCOUNT_CONST.as_ref(), mint_account_info.key.as_ref()], gem_account_data_info);
+    let (_global_gem_pubkey, _global_gem_bump) = assert_pda_input(&[GLOBAL_GEM_KEY.as_ref()], global_gem_account_info);
+    let (_expected_vote_data_pubkey, _expected_vote_data_bump) = assert_pda_input(&[VOTE_DATA_ACCOUNT_KEY.as_ref(), vote_account_info.key.as_ref()], ingl_vote_data_account_info);
+    let (minting_pool_id, _minting_pool_bump) = assert_pda_input(&[INGL_MINTING_POOL_KEY.as_ref()], minting_pool_account_info);
+    let (liquidity_reserve_id, liquidity_reserve_bump) = assert_pda_input(&[LIQUIDITY_RESERVE_KEY.as_ref()], liquidity_reserve_account_info);
+    let (_config_pubkey, _config_bump) = assert_pda_input(&[INGL_CONFIG_KEY.as_ref()], config_account_info);
+    let config_data = InglConfig::decode(config_account_info)?;
+
+    let gem_account_data = GemAccountV0_0_1::validate(GemAccountVersions::decode_unchecked(
+        &gem_account_data_info.data.borrow(),
+    )?)?;
+    gem_account_data.assert_not_queued()?;
+    gem_account_data.assert_no_open_governance_vote(Clock::get()?.unix_timestamp as u32)?;
+    if gem_account_data.rarity_seed_time.is_some() && gem_account_data.rarity.is_none() {
+        Err(InglError::TooEarly.utilize(Some("rarity imprint pending")))?
+    }
+    match gem_account_data.funds_location {
+        FundsLocation::VoteAccount { .. } => {}
+        _ => Err(InglError::InvalidFundsLocation.utilize(Some("only delegated gems are redeemed instantly")))?,
+    }
+
+    let lamports = gem_account_data.class.get_class_lamports();
+    let available_liquidity = liquidity_reserve_account_info.lamports().saturating_sub(Rent::get()?.minimum_balance(0));
+    if available_liquidity < lamports {
+        msg!("The liquidity reserve only holds {:?} lamports", available_liquidity);
+        Err(ProgramError::InsufficientFunds)?
+    }
+
+    // undelegate_nft reads its accounts off the front and ignores the rest, as in redeem_queued.
+    undelegate_nft(program_id, accounts)?;
+
+    // Reloaded, as undelegating rewrites them. The gem's stake now goes to the reserve instead of the pd pool.
+    let mut gem_account_data = GemAccountV0_0_1::validate(GemAccountVersions::decode_unchecked(
+        &gem_account_data_info.data.borrow(),
+    )?)?;
+    let mut global_gem_data = GlobalGems::decode(global_gem_account_info)?;
+    let mut ingl_vote_account_data = InglVoteAccountData::decode(ingl_vote_data_account_info)?;
+
+    gem_account_data.funds_location = FundsLocation::MintingPool;
+    global_gem_data.pd_pool_total = global_gem_data.pd_pool_total.checked_sub(lamports).ok_or(InglError::BeyondBounds)?;
+    global_gem_data.reserve_advanced_total = global_gem_data.reserve_advanced_total.checked_add(lamports).ok_or(InglError::BeyondBounds)?;
+    ingl_vote_account_data.reserve_advances = ingl_vote_account_data.reserve_advances.checked_add(lamports).ok_or(InglError::BeyondBounds)?;
+    invoke_signed(
+        &system_instruction::transfer(&liquidity_reserve_id, &minting_pool_id, lamports),
         &[
-            metadata_account_info.clone(),
-            freeze_authority_account_info.clone(),
+            liquidity_reserve_account_info.clone(),
+            minting_pool_account_info.clone(),
         ],
-        &[&[INGL_MINT_AUTHORITY_KEY.as_ref(), &[mint_authority_bump]]],
+        &[&[LIQUIDITY_RESERVE_KEY.as_ref(), &[liquidity_reserve_bump]]],
     )?;
-    gem_data.serialize(&mut &mut gem_account_info.data.borrow_mut()[..])?;
-    Ok(())
-}
-
-pub fn redeem_nft(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
-    let account_info_iter = &mut accounts.iter();
-    let payer_account_info = next_account_info(account_info_iter)?;
-    let mint_account_info = next_account_info(account_info_iter)?;
-    let minting_pool_account_info = next_account_info(account_info_iter)?;
-    let associated_token_account_info = next_account_info(account_info_iter)?;
-    let mint_authority_account_info = next_account_info(account_info_iter)?;
-    let gem_account_info = next_account_info(account_info_iter)?;
-    let metadata_account_info = next_account_info(account_info_iter)?;
-    let edition_account_info = next_account_info(account_info_iter)?;
-    let ingl_collection_account_info = next_account_info(account_info_iter)?;
-    let spl_token_program_account_info = next_account_info(account_info_iter)?;
-    let program_treasury_account_info = next_account_info(account_info_iter)?;
 
-    assert_is_signer(payer_account_info).unwrap();
-    assert_program_owned(gem_account_info)?;
-    assert_owned_by(mint_account_info, &spl_program::id())?;
-    assert_owned_by(associated_token_account_info, &spl_program::id())?;
-    assert_pubkeys_exactitude(
-        &get_associated_token_address(payer_account_info.key, mint_account_info.key),
-        associated_token_account_info.key,
-    )
-    .expect("associated_token_account_info");
+    let payout = lamports.checked_sub(config_data.get_redeem_fee(lamports, (Clock::get()?.unix_timestamp as u32).saturating_sub(gem_account_data.date_created))).ok_or(InglError::BeyondBounds)?;
+    let discount = (payout as u128 * config_data.instant_exit_discount as u128 / 100) as u64;
 
-    let (mint_authority_key, _mint_authority_bump) = assert_pda_input(&[INGL_MINT_AUTHORITY_KEY.as_ref()], mint_authority_account_info);
+    gem_account_data.serialize(&mut &mut gem_account_data_info.data.borrow_mut()[..])?;
+    global_gem_data.serialize(&mut &mut global_gem_account_info.data.borrow_mut()[..])?;
+    ingl_vote_account_data.serialize(&mut &mut ingl_vote_data_account_info.data.borrow_mut()[..])?;
 
-    let (minting_pool_id, minting_pool_bump) = assert_pda_input(&[INGL_MINTING_POOL_KEY.as_ref()], minting_pool_account_info);
+    let redeem_accounts = &[
+        payer_account_info.clone(),
+        mint_account_info.clone(),
+        minting_pool_account_info.clone(),
+        associated_token_account_info.clone(),
+        mint_authority_account_info.clone(),
+        gem_account_data_info.clone(),
+        metadata_account_info.clone(),
+        edition_account_info.clone(),
+        ingl_collection_account_info.clone(),
+        spl_token_program_account_info.clone(),
+        program_treasury_account_info.clone(),
+        config_account_info.clone(),
+        referrer_account_info.clone(),
+        liquidity_reserve_account_info.clone(),
+        treasury_ledger_account_info.clone(),
+    ];
+    redeem_nft(program_id, redeem_accounts)?;
 
-    let (_gem_pubkey, _gem_bump) = assert_pda_input(&[GEM_ACCOUNT_CONST.as_ref(), mint_account_info.key.as_ref()], gem_account_info);
+    msg!("Instant exit discount: {:?}", discount);
+    invoke(
+        &system_instruction::transfer(payer_account_info.key, &liquidity_reserve_id, discount),
+        &[
+            payer_account_info.clone(),
+            liquidity_reserve_account_info.clone(),
+        ],
+    )?;
+    Ok(())
+}
 
+/// Burns a gem's token, metadata and master edition, then closes its gem account, returning the rent to the payer.
+#[allow(clippy::too_many_arguments)]
+fn burn_gem<'a>(
+    program_id: &Pubkey,
+    payer_account_info: &AccountInfo<'a>,
+    mint_account_info: &AccountInfo<'a>,
+    associated_token_account_info: &AccountInfo<'a>,
+    gem_account_info: &AccountInfo<'a>,
+    metadata_account_info: &AccountInfo<'a>,
+    edition_account_info: &AccountInfo<'a>,
+    ingl_collection_account_info: &AccountInfo<'a>,
+    spl_token_program_account_info: &AccountInfo<'a>,
+) -> ProgramResult {
     let mpl_token_metadata_id = mpl_token_metadata::id();
 
     let (edition_key, _edition_bump) = Pubkey::find_program_address(
@@ -1528,81 +3494,6 @@ pub fn redeem_nft(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResul
     assert_pubkeys_exactitude(&collection_metadata_key, ingl_collection_account_info.key)
         .expect("Error: @collection_metadata_info");
 
-    let associated_token_account_data =
-        Account::unpack(&associated_token_account_info.data.borrow())?;
-    if associated_token_account_data.amount != 1 {
-        Err(ProgramError::InsufficientFunds)?
-    }
-
-    let gem_data = GemAccountV0_0_1::validate(GemAccountVersions::decode_unchecked(
-        &gem_account_info.data.borrow(),
-    )?)?;
-
-    match gem_data.funds_location {
-        FundsLocation::MintingPool => {}
-        _ => Err(InglError::InvalidFundsLocation.utilize(Some("gem_account_redeem_nft")))?,
-    };
-
-    let now = Clock::get()?.unix_timestamp as u32;
-    if gem_data.redeemable_date > now {
-        Err(InglError::TooEarly.utilize(Some("redeem_nft")))?;
-    }
-
-    let mut redeem_fees: u64 = 0;
-    if let Some(val) = gem_data.rarity_seed_time{
-        let spent_time =
-            ((now.checked_sub(val)).unwrap() as f64) / ((60 * 60 * 24 * 365) as f64);
-        msg!("Now: {}, rarity_seed_time: {}, spent_time: {}",now, val, spent_time);
-
-        if spent_time < 1.0 {
-            redeem_fees = redeem_fees.checked_add((((1.0 - spent_time.pow(2) as f64).sqrt() * FEE_MULTIPLYER as f64 / 100.0) * (gem_data.class.get_class_lamports() as f64)) as u64).ok_or(InglError::BeyondBounds).unwrap();
-            
-            let (program_treasury_id, _treasury_bump) = assert_pda_input(&[INGL_TREASURY_ACCOUNT_KEY.as_ref()], program_treasury_account_info);
-
-            let treasury_funds = (redeem_fees as f64 * TREASURY_FEE_MULTIPLYER as f64 / 100.0) as u64;
-            let mint_authority_funds = redeem_fees.checked_sub(treasury_funds).ok_or(InglError::BeyondBounds).unwrap();
-
-                invoke_signed(
-                    &system_instruction::transfer(
-                        &minting_pool_id,
-                        &program_treasury_id,
-                        treasury_funds,
-                    ),
-                    &[
-                        minting_pool_account_info.clone(),
-                        program_treasury_account_info.clone(),
-                    ],
-                    &[&[INGL_MINTING_POOL_KEY.as_ref(), &[minting_pool_bump]]],
-                )?;
-                invoke_signed(
-                    &system_instruction::transfer(
-                        &minting_pool_id,
-                        &mint_authority_key,
-                        mint_authority_funds,
-                    ),
-                    &[
-                        minting_pool_account_info.clone(),
-                        mint_authority_account_info.clone(),
-                    ],
-                    &[&[INGL_MINTING_POOL_KEY.as_ref(), &[minting_pool_bump]]],
-                )?;
-            }
-    }
-
-    msg!("Redeem_fees: {:?} lamports: {:?}",redeem_fees, gem_data.class.get_class_lamports());
-    invoke_signed(
-        &system_instruction::transfer(
-            &minting_pool_id,
-            payer_account_info.key,
-            gem_data.class.get_class_lamports().checked_sub(redeem_fees).ok_or(InglError::BeyondBounds).unwrap(),
-        ),
-        &[
-            minting_pool_account_info.clone(),
-            payer_account_info.clone(),
-        ],
-        &[&[INGL_MINTING_POOL_KEY.as_ref(), &[minting_pool_bump]]],
-    )?;
-
     invoke(
         &mpl_token_metadata::instruction::burn_nft(
             mpl_token_metadata_id,
@@ -1637,6 +3528,405 @@ pub fn redeem_nft(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResul
     Ok(())
 }
 
+/// Burns `num_gems` gems whose classes add up to `class` and mints a single gem of that class in their place.
+/// Delegated gems must all sit on the same vote account with no unwithdrawn rewards, and any gems still in the minting pool
+/// are allocated along with pd pool gems, so that the backing lamports of the new gem are all in one place.
+pub fn fuse_gems(program_id: &Pubkey, accounts: &[AccountInfo], class: Class, num_gems: u8) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let payer_account_info = next_account_info(account_info_iter)?;
+    let mint_account_info = next_account_info(account_info_iter)?;
+    let mint_authority_account_info = next_account_info(account_info_iter)?;
+    let associated_token_account_info = next_account_info(account_info_iter)?;
+    let spl_token_program_account_info = next_account_info(account_info_iter)?;
+    let sysvar_rent_account_info = next_account_info(account_info_iter)?;
+    let system_program_account_info = next_account_info(account_info_iter)?;
+    let metadata_account_info = next_account_info(account_info_iter)?;
+    let minting_pool_account_info = next_account_info(account_info_iter)?;
+    let pd_pool_account_info = next_account_info(account_info_iter)?;
+    let global_gem_account_info = next_account_info(account_info_iter)?;
+    let gem_account_info = next_account_info(account_info_iter)?;
+    let ingl_edition_account_info = next_account_info(account_info_iter)?;
+    let nft_edition_account_info = next_account_info(account_info_iter)?;
+    let ingl_collection_mint_info = next_account_info(account_info_iter)?;
+    let ingl_collection_account_info = next_account_info(account_info_iter)?;
+    let config_account_info = next_account_info(account_info_iter)?;
+    let uri_registry_account_info = next_account_info(account_info_iter)?;
+
+    assert_is_signer(payer_account_info)?;
+    let (_config_pubkey, _config_bump) = assert_pda_input(&[INGL_CONFIG_KEY.as_ref()], config_account_info);
+    let config_data = InglConfig::decode(config_account_info)?;
+    config_data.assert_not_paused()?;
+    config_data.assert_class_listed(class)?;
+    let uri_registry_data = load_uri_registry(uri_registry_account_info)?;
+
+    let gem_mint_accounts = GemMintAccounts {
+        payer_account_info,
+        mint_account_info,
+        mint_authority_account_info,
+        associated_token_account_info,
+        spl_token_program_account_info,
+        sysvar_rent_account_info,
+        system_program_account_info,
+        metadata_account_info,
+        nft_edition_account_info,
+        ingl_edition_account_info,
+        ingl_collection_mint_info,
+        ingl_collection_account_info,
+    };
+    gem_mint_accounts.validate()?;
+
+    assert_program_owned(global_gem_account_info)?;
+    let (_global_gem_pubkey, _global_gem_bump) = assert_pda_input(&[GLOBAL_GEM_KEY.as_ref()], global_gem_account_info);
+    let (minting_pool_id, minting_pool_bump) = assert_pda_input(&[INGL_MINTING_POOL_KEY.as_ref()], minting_pool_account_info);
+    let (pd_pool_pubkey, _pd_pool_bump) = assert_pda_input(&[PD_POOL_KEY.as_ref()], pd_pool_account_info);
+
+    let mut global_gem_data = GlobalGems::decode(global_gem_account_info)?;
+    // Validator selection votes live on the burnt gems, so fusing mid proposal would let their weight vote twice.
+    if global_gem_data.is_proposal_ongoing {
+        Err(InglError::TooEarly.utilize(Some("A Proposal Is Currently Ongoing")))?
+    }
+    if num_gems < 2 {
+        Err(InglError::BeyondBounds.utilize(Some("number of gems to fuse")))?
+    }
+
+    let now = Clock::get()?.unix_timestamp as u32;
+
+    let mut fused_lamports: u64 = 0;
+    let mut minting_pool_lamports: u64 = 0;
+    let mut has_pd_pool_gem = false;
+    let mut vote_account_id: Option<Pubkey> = None;
+    let mut reward_checkpoint: Option<u64> = None;
+    let mut last_withdrawal_epoch: Option<u64> = None;
+    let mut last_delegation_epoch: Option<u64> = None;
+    let mut reward_boost: u64 = 0;
+    let mut redeemable_date = u32::MAX;
+    let mut minting_pool_redeemable_date = u32::MAX;
+    let mut date_allocated: Option<u32> = None;
+    let mut date_created = u32::MAX;
+    let mut referrer: Option<Option<Pubkey>> = None;
+    let mut rarity: Option<Rarity> = None;
+    let mut rarity_rerolls: Option<u8> = None;
+
+    for i in 0..num_gems {
+        let burnt_mint_account_info = next_account_info(account_info_iter)?;
+        let burnt_associated_token_account_info = next_account_info(account_info_iter)?;
+        let burnt_gem_account_info = next_account_info(account_info_iter)?;
+        let burnt_metadata_account_info = next_account_info(account_info_iter)?;
+        let burnt_edition_account_info = next_account_info(account_info_iter)?;
+
+        assert_program_owned(burnt_gem_account_info)?;
+        assert_owned_by(burnt_mint_account_info, &spl_program::id())?;
+        assert_owned_by(burnt_associated_token_account_info, &spl_program::id())?;
+        let (_gem_pubkey, _gem_bump) = assert_pda_input(&[GEM_ACCOUNT_CONST.as_ref(), burnt_mint_account_info.key.as_ref()], burnt_gem_account_info);
+        assert_pubkeys_exactitude(
+            &get_associated_token_address(payer_account_info.key, burnt_mint_account_info.key),
+            burnt_associated_token_account_info.key,
+        )
+        .expect("Error: @associated_token_address");
+
+        let associated_token_account_data = Account::unpack(&burnt_associated_token_account_info.data.borrow())?;
+        if associated_token_account_data.amount != 1 {
+            Err(ProgramError::InsufficientFunds)?
+        }
+        if associated_token_account_data.is_frozen() {
+            Err(TokenError::AccountFrozen)?
+        }
+
+        let gem_data = GemAccountV0_0_1::validate(GemAccountVersions::decode_unchecked(
+            &burnt_gem_account_info.data.borrow(),
+        )?)?;
+        if gem_data.rarity_seed_time.is_some() && gem_data.rarity.is_none() {
+            Err(InglError::TooEarly.utilize(Some("rarity imprint pending")))?
+        }
+        gem_data.assert_not_queued()?;
+        gem_data.assert_no_open_governance_vote(now)?;
+
+        // The fused gem keeps the lowest imprinted rarity and the most re-rolls, so fusing can't stand in for a re-roll.
+        // Imprinted and unrevealed gems don't mix, else the unrevealed ones would carry the others' rarity over.
+        if i > 0 && rarity.is_some() != gem_data.rarity.is_some() {
+            Err(InglError::TooEarly.utilize(Some("imprinted and unrevealed gems")))?
+        }
+        if let Some(gem_rarity) = gem_data.rarity.clone() {
+            rarity = Some(rarity.map_or(gem_rarity.clone(), |rarity| rarity.min(gem_rarity)));
+        }
+        rarity_rerolls = rarity_rerolls.max(gem_data.rarity_rerolls);
+
+        let gem_lamports = gem_data.class.get_class_lamports();
+        fused_lamports = fused_lamports.checked_add(gem_lamports).ok_or(InglError::BeyondBounds)?;
+
+        // A delegated gem can only be fused with gems delegated to the same vote account, all of them withdrawn up to the same epoch.
+        if i > 0 && vote_account_id.is_some() != matches!(gem_data.funds_location, FundsLocation::VoteAccount { .. }) {
+            Err(InglError::InvalidFundsLocation.utilize(Some("delegated and undelegated gems")))?
+        }
+        match gem_data.funds_location {
+            FundsLocation::MintingPool => {
+                minting_pool_lamports = minting_pool_lamports.checked_add(gem_lamports).ok_or(InglError::BeyondBounds)?;
+                minting_pool_redeemable_date = minting_pool_redeemable_date.min(gem_data.redeemable_date);
+            }
+            FundsLocation::PDPool => {
+                has_pd_pool_gem = true;
+                redeemable_date = redeemable_date.min(gem_data.redeemable_date);
+            }
+            FundsLocation::VoteAccount { vote_account_id: gem_vote_account_id } => {
+                let gem_reward_checkpoint = gem_data.last_withdrawal_epoch.max(gem_data.last_delegation_epoch);
+                if i > 0 && (vote_account_id != Some(gem_vote_account_id) || reward_checkpoint != gem_reward_checkpoint) {
+                    Err(InglError::InvalidFundsLocation.utilize(Some("gems delegated to different vote accounts or with unwithdrawn rewards")))?
+                }
+                vote_account_id = Some(gem_vote_account_id);
+                reward_checkpoint = gem_reward_checkpoint;
+                last_withdrawal_epoch = last_withdrawal_epoch.max(gem_data.last_withdrawal_epoch);
+                last_delegation_epoch = last_delegation_epoch.max(gem_data.last_delegation_epoch);
+                reward_boost = reward_boost.checked_add(gem_data.reward_boost.unwrap_or(0)).ok_or(InglError::BeyondBounds)?;
+                redeemable_date = redeemable_date.min(gem_data.redeemable_date);
+            }
+        }
+        // The fused gem keeps the referrer only if all the burnt gems were referred by it.
+        referrer = match referrer {
+            Some(referrer) if referrer != gem_data.referrer => Some(None),
+            Some(referrer) => Some(referrer),
+            None => Some(gem_data.referrer),
+        };
+        if let Some(gem_date_allocated) = gem_data.date_allocated {
+            date_allocated = Some(date_allocated.map_or(gem_date_allocated, |date| date.min(gem_date_allocated)));
+        }
+        date_created = date_created.min(gem_data.date_created);
+
+        burn_gem(
+            program_id,
+            payer_account_info,
+            burnt_mint_account_info,
+            burnt_associated_token_account_info,
+            burnt_gem_account_info,
+            burnt_metadata_account_info,
+            burnt_edition_account_info,
+            ingl_collection_account_info,
+            spl_token_program_account_info,
+        )?;
+    }
+
+    if fused_lamports != class.get_class_lamports() {
+        Err(InglError::BeyondBounds.utilize(Some("fused gems don't add up to the class")))?
+    }
+
+    let funds_location = if let Some(vote_account_id) = vote_account_id {
+        FundsLocation::VoteAccount { vote_account_id }
+    } else if has_pd_pool_gem {
+        // Gems still in the minting pool are allocated alongside the pd pool gems, as allocate_sol would.
+        if minting_pool_lamports > 0 {
+            invoke_signed(
+                &system_instruction::transfer(&minting_pool_id, &pd_pool_pubkey, minting_pool_lamports),
+                &[
+                    minting_pool_account_info.clone(),
+                    pd_pool_account_info.clone(),
+                ],
+                &[&[INGL_MINTING_POOL_KEY.as_ref(), &[minting_pool_bump]]],
+            )?;
+            global_gem_data.pd_pool_total = global_gem_data.pd_pool_total.checked_add(minting_pool_lamports).unwrap();
+            redeemable_date = redeemable_date.min(now + ALLOCATE_LOCK_TIME);
+            date_allocated = Some(date_allocated.map_or(now, |date| date.min(now)));
+        }
+        FundsLocation::PDPool
+    } else {
+        redeemable_date = minting_pool_redeemable_date;
+        FundsLocation::MintingPool
+    };
+
+    create_gem_account(program_id, payer_account_info, mint_account_info, gem_account_info)?;
+
+    global_gem_data.counter += 1;
+    global_gem_data.serialize(&mut &mut global_gem_account_info.data.borrow_mut()[..])?;
+
+    gem_mint_accounts.mint_gem(config_data.get_gem_name(class), global_gem_data.counter, uri_registry_data.get_uri(class, rarity.clone()))?;
+
+    let gem_account_data = GemAccountV0_0_1 {
+        struct_id: GemAccountVersions::GemAccountV0_0_1,
+        validation_phrase: GEM_ACCOUNT_VAL_PHRASE,
+        date_created,
+        redeemable_date,
+        numeration: global_gem_data.counter,
+        rarity,
+        funds_location,
+        rarity_seed_time: None,
+        date_allocated,
+        class,
+        last_voted_proposal: None,
+        last_withdrawal_epoch,
+        last_delegation_epoch,
+        all_withdraws: Vec::new(),
+        all_votes: Vec::new(),
+        voting_delegate: None,
+        rarity_reveal_slot: None,
+        reward_boost: vote_account_id.map(|_| reward_boost),
+        rarity_rerolls,
+        referrer: referrer.flatten(),
+        queued_redemption: None,
+        ballot_voter: None,
+        governance_vote_end: None,
+    };
+    gem_account_data.serialize(&mut &mut gem_account_info.data.borrow_mut()[..])?;
+    Ok(())
+}
+
+/// Burns a gem and mints one gem of each of the lower `classes` in its place, their class lamports adding up to the burnt gem's.
+/// The children keep the burnt gem's funds location, reward checkpoint and lock dates, so no lamports move between pools.
+pub fn split_gem(program_id: &Pubkey, accounts: &[AccountInfo], classes: Vec<Class>) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let payer_account_info = next_account_info(account_info_iter)?;
+    let mint_authority_account_info = next_account_info(account_info_iter)?;
+    let spl_token_program_account_info = next_account_info(account_info_iter)?;
+    let sysvar_rent_account_info = next_account_info(account_info_iter)?;
+    let system_program_account_info = next_account_info(account_info_iter)?;
+    let global_gem_account_info = next_account_info(account_info_iter)?;
+    let ingl_edition_account_info = next_account_info(account_info_iter)?;
+    let ingl_collection_mint_info = next_account_info(account_info_iter)?;
+    let ingl_collection_account_info = next_account_info(account_info_iter)?;
+    let config_account_info = next_account_info(account_info_iter)?;
+    let uri_registry_account_info = next_account_info(account_info_iter)?;
+    let burnt_mint_account_info = next_account_info(account_info_iter)?;
+    let burnt_associated_token_account_info = next_account_info(account_info_iter)?;
+    let burnt_gem_account_info = next_account_info(account_info_iter)?;
+    let burnt_metadata_account_info = next_account_info(account_info_iter)?;
+    let burnt_edition_account_info = next_account_info(account_info_iter)?;
+
+    assert_is_signer(payer_account_info)?;
+    let (_config_pubkey, _config_bump) = assert_pda_input(&[INGL_CONFIG_KEY.as_ref()], config_account_info);
+    let config_data = InglConfig::decode(config_account_info)?;
+    config_data.assert_not_paused()?;
+    let uri_registry_data = load_uri_registry(uri_registry_account_info)?;
+
+    assert_program_owned(global_gem_account_info)?;
+    let (_global_gem_pubkey, _global_gem_bump) = assert_pda_input(&[GLOBAL_GEM_KEY.as_ref()], global_gem_account_info);
+    let mut global_gem_data = GlobalGems::decode(global_gem_account_info)?;
+    // As with fusing, the children would otherwise be free to vote again on the ongoing proposal.
+    if global_gem_data.is_proposal_ongoing {
+        Err(InglError::TooEarly.utilize(Some("A Proposal Is Currently Ongoing")))?
+    }
+
+    assert_program_owned(burnt_gem_account_info)?;
+    assert_owned_by(burnt_mint_account_info, &spl_program::id())?;
+    assert_owned_by(burnt_associated_token_account_info, &spl_program::id())?;
+    let (_gem_pubkey, _gem_bump) = assert_pda_input(&[GEM_ACCOUNT_CONST.as_ref(), burnt_mint_account_info.key.as_ref()], burnt_gem_account_info);
+    assert_pubkeys_exactitude(
+        &get_associated_token_address(payer_account_info.key, burnt_mint_account_info.key),
+        burnt_associated_token_account_info.key,
+    )
+    .expect("Error: @associated_token_address");
+
+    let associated_token_account_data = Account::unpack(&burnt_associated_token_account_info.data.borrow())?;
+    if associated_token_account_data.amount != 1 {
+        Err(ProgramError::InsufficientFunds)?
+    }
+    if associated_token_account_data.is_frozen() {
+        Err(TokenError::AccountFrozen)?
+    }
+
+    let burnt_gem_data = GemAccountV0_0_1::validate(GemAccountVersions::decode_unchecked(
+        &burnt_gem_account_info.data.borrow(),
+    )?)?;
+    if burnt_gem_data.rarity_seed_time.is_some() && burnt_gem_data.rarity.is_none() {
+        Err(InglError::TooEarly.utilize(Some("rarity imprint pending")))?
+    }
+    burnt_gem_data.assert_not_queued()?;
+    let now = Clock::get()?.unix_timestamp as u32;
+    burnt_gem_data.assert_no_open_governance_vote(now)?;
+
+    let burnt_lamports = burnt_gem_data.class.get_class_lamports();
+    if classes.len() < 2 {
+        Err(InglError::BeyondBounds.utilize(Some("number of gems to split into")))?
+    }
+    let mut split_lamports: u64 = 0;
+    for class in classes.iter() {
+        config_data.assert_class_listed(*class)?;
+        split_lamports = split_lamports.checked_add(class.get_class_lamports()).ok_or(InglError::BeyondBounds)?;
+    }
+    if split_lamports != burnt_lamports {
+        Err(InglError::BeyondBounds.utilize(Some("split gems don't add up to the class")))?
+    }
+
+    burn_gem(
+        program_id,
+        payer_account_info,
+        burnt_mint_account_info,
+        burnt_associated_token_account_info,
+        burnt_gem_account_info,
+        burnt_metadata_account_info,
+        burnt_edition_account_info,
+        ingl_collection_account_info,
+        spl_token_program_account_info,
+    )?;
+
+    // The burnt gem's reward boost is shared out by class lamports, the last child taking the rounding,
+    // so that the vote account's boosted_delegated still matches the sum over its gems.
+    let mut remaining_boost = burnt_gem_data.reward_boost;
+    for (i, class) in classes.iter().enumerate() {
+        let mint_account_info = next_account_info(account_info_iter)?;
+        let associated_token_account_info = next_account_info(account_info_iter)?;
+        let gem_account_info = next_account_info(account_info_iter)?;
+        let metadata_account_info = next_account_info(account_info_iter)?;
+        let nft_edition_account_info = next_account_info(account_info_iter)?;
+
+        let gem_mint_accounts = GemMintAccounts {
+            payer_account_info,
+            mint_account_info,
+            mint_authority_account_info,
+            associated_token_account_info,
+            spl_token_program_account_info,
+            sysvar_rent_account_info,
+            system_program_account_info,
+            metadata_account_info,
+            nft_edition_account_info,
+            ingl_edition_account_info,
+            ingl_collection_mint_info,
+            ingl_collection_account_info,
+        };
+        gem_mint_accounts.validate()?;
+
+        let reward_boost = match (remaining_boost, burnt_gem_data.reward_boost) {
+            (Some(remaining), Some(total_boost)) if i + 1 < classes.len() => {
+                let boost = (total_boost as u128 * class.get_class_lamports() as u128 / burnt_lamports as u128) as u64;
+                remaining_boost = Some(remaining.checked_sub(boost).ok_or(InglError::BeyondBounds)?);
+                Some(boost)
+            }
+            _ => remaining_boost,
+        };
+
+        create_gem_account(program_id, payer_account_info, mint_account_info, gem_account_info)?;
+        global_gem_data.counter += 1;
+        // The children keep the burnt gem's rarity and re-rolls, so splitting can't stand in for a re-roll.
+        gem_mint_accounts.mint_gem(config_data.get_gem_name(*class), global_gem_data.counter, uri_registry_data.get_uri(*class, burnt_gem_data.rarity.clone()))?;
+
+        let gem_account_data = GemAccountV0_0_1 {
+            struct_id: GemAccountVersions::GemAccountV0_0_1,
+            validation_phrase: GEM_ACCOUNT_VAL_PHRASE,
+            date_created: burnt_gem_data.date_created,
+            redeemable_date: burnt_gem_data.redeemable_date,
+            numeration: global_gem_data.counter,
+            rarity: burnt_gem_data.rarity.clone(),
+            funds_location: burnt_gem_data.funds_location.clone(),
+            rarity_seed_time: None,
+            date_allocated: burnt_gem_data.date_allocated,
+            class: *class,
+            last_voted_proposal: None,
+            last_withdrawal_epoch: burnt_gem_data.last_withdrawal_epoch,
+            last_delegation_epoch: burnt_gem_data.last_delegation_epoch,
+            all_withdraws: Vec::new(),
+            all_votes: Vec::new(),
+            voting_delegate: None,
+            rarity_reveal_slot: None,
+            reward_boost,
+            rarity_rerolls: burnt_gem_data.rarity_rerolls,
+            referrer: burnt_gem_data.referrer,
+            queued_redemption: None,
+            ballot_voter: None,
+            governance_vote_end: None,
+        };
+        gem_account_data.serialize(&mut &mut gem_account_info.data.borrow_mut()[..])?;
+    }
+
+    global_gem_data.serialize(&mut &mut global_gem_account_info.data.borrow_mut()[..])?;
+    Ok(())
+}
+
 pub fn delegate_nft(_program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
     let account_info_iter = &mut accounts.iter();
     let payer_account_info = next_account_info(account_info_iter)?;
@@ -1648,6 +3938,10 @@ pub fn delegate_nft(_program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramRe
     let global_gem_account_info = next_account_info(account_info_iter)?;
     let sysvar_clock_info = next_account_info(account_info_iter)?;
     let stake_config_program_info = next_account_info(account_info_iter)?;
+    let config_account_info = next_account_info(account_info_iter)?;
+
+    let (_config_pubkey, _config_bump) = assert_pda_input(&[INGL_CONFIG_KEY.as_ref()], config_account_info);
+    let config_data = InglConfig::decode(config_account_info)?;
 
     assert_pubkeys_exactitude(sysvar_clock_info.key, &sysvar::clock::id())
         .expect("sysvar clock info");
@@ -1682,6 +3976,7 @@ pub fn delegate_nft(_program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramRe
         GemAccountVersions::decode_unchecked(&gem_account_data_info.data.borrow())?,
     )?;
     let mut global_gem_account_data = GlobalGems::decode(global_gem_account_info)?;
+    gem_account_data.assert_not_queued()?;
 
     global_gem_account_data.pd_pool_total = global_gem_account_data
         .pd_pool_total
@@ -1735,6 +4030,14 @@ pub fn delegate_nft(_program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramRe
         .checked_add(gem_account_data.class.get_class_lamports())
         .unwrap();
 
+    // The boost is recorded on the gem so that undelegating removes exactly the weight added here, whatever the config says by then.
+    let reward_boost = config_data.get_reward_boost(gem_account_data.class, gem_account_data.rarity.clone());
+    gem_account_data.reward_boost = Some(reward_boost);
+    ingl_vote_account_data.boosted_delegated = ingl_vote_account_data
+        .boosted_delegated
+        .checked_add(reward_boost)
+        .unwrap();
+
     global_gem_account_data.serialize(&mut &mut global_gem_account_info.data.borrow_mut()[..])?;
     gem_account_data.serialize(&mut &mut gem_account_data_info.data.borrow_mut()[..])?;
     ingl_vote_account_data
@@ -1798,6 +4101,11 @@ pub fn undelegate_nft(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramR
         .total_delegated
         .checked_sub(gem_account_data.class.get_class_lamports())
         .unwrap();
+    ingl_vote_account_data.boosted_delegated = ingl_vote_account_data
+        .boosted_delegated
+        .checked_sub(gem_account_data.reward_boost.unwrap_or(0))
+        .unwrap();
+    gem_account_data.reward_boost = None;
 
     if global_gem_account_data.pending_delegation_total
         > gem_account_data.class.get_class_lamports()
@@ -1867,6 +4175,7 @@ pub fn process_rewards(_program_id: &Pubkey, accounts: &[AccountInfo]) -> Progra
     let authorized_withdrawer_info = next_account_info(account_info_iter)?;
     let mint_authority_account_info = next_account_info(account_info_iter)?;
     let treasury_account_info = next_account_info(account_info_iter)?;
+    let treasury_ledger_account_info = next_account_info(account_info_iter)?;
 
     let (_mint_authority_key, _mint_authority_bump) = assert_pda_input(&[INGL_MINT_AUTHORITY_KEY.as_ref()], mint_authority_account_info);
 
@@ -1937,11 +4246,19 @@ pub fn process_rewards(_program_id: &Pubkey, accounts: &[AccountInfo]) -> Progra
         ]],
     )?;
 
+    // Rarity boosts enlarge the NFT share beyond NFTS_SHARE and are paid out of the treasury share, never beyond it.
+    let nft_reward = one_percent.checked_mul(NFTS_SHARE).unwrap();
+    let treasury_reward = one_percent.checked_mul(TREASURY_SHARE).unwrap();
+    let boost_reward = (nft_reward as u128 * ingl_vote_account_data.boosted_delegated as u128)
+        .checked_div(ingl_vote_account_data.total_delegated as u128)
+        .unwrap_or(0)
+        .min(treasury_reward as u128) as u64;
+
     invoke_signed(
         &system_instruction::transfer(
             authorized_withdrawer_info.key,
             treasury_account_info.key,
-            one_percent.checked_mul(TREASURY_SHARE).unwrap(),
+            treasury_reward.checked_sub(boost_reward).unwrap(),
         ),
         &[
             authorized_withdrawer_info.clone(),
@@ -1953,11 +4270,17 @@ pub fn process_rewards(_program_id: &Pubkey, accounts: &[AccountInfo]) -> Progra
         ]],
     )?;
 
+    update_treasury_ledger(treasury_ledger_account_info, |ledger| {
+        ledger.team_share = ledger.team_share.saturating_add(one_percent * TEAM_SHARE);
+        ledger.reward_share = ledger.reward_share.saturating_add(treasury_reward - boost_reward);
+    })?;
+
     ingl_vote_account_data.vote_rewards.push(VoteRewards {
         validation_phrase: VOTE_REWARDS_VAL_PHRASE,
         epoch_number: Clock::get()?.epoch,
-        total_stake: ingl_vote_account_data.total_delegated,
+        total_stake: ingl_vote_account_data.total_delegated.checked_add(ingl_vote_account_data.boosted_delegated).unwrap(),
         total_reward: lamports,
+        nft_reward: nft_reward.checked_add(boost_reward).unwrap(),
     });
     ingl_vote_account_data.last_withdraw_epoch = Clock::get()?.epoch;
 
@@ -2027,13 +4350,18 @@ pub fn nft_withdraw(_program_id: &Pubkey, accounts: &[AccountInfo], cnt: usize)
         };
         let interested_index =1 + ingl_vote_account_data.vote_rewards.iter().position(|x| x.epoch_number == interested_epoch).expect("couldn't find the last withdrawal epoch. One Can only undelegate the epoch after that which they delegated.");
         let mut total_reward: u64 = 0;
+        let reward_weight = gem_account_data.class.get_class_lamports().checked_add(gem_account_data.reward_boost.unwrap_or(0)).unwrap();
         for i in interested_index..ingl_vote_account_data.vote_rewards.len() {
             let epoch_reward = ingl_vote_account_data.vote_rewards[i];
             // msg!("epoch_reward: {:?}", epoch_reward);
-            total_reward = total_reward.checked_add((gem_account_data.class.get_class_lamports() as f64 * NFTS_SHARE as f64 * epoch_reward.total_reward as f64 / (100.0 * epoch_reward.total_stake as f64))as u64).unwrap(); //unsafe Get back to this Cyrial
+            // Rounding down keeps the sum of the gems' rewards within the epoch's nft_reward.
+            let gem_reward = (reward_weight as u128 * epoch_reward.nft_reward as u128)
+                .checked_div(epoch_reward.total_stake as u128)
+                .unwrap_or(0) as u64;
+            total_reward = total_reward.checked_add(gem_reward).unwrap();
         }
         gem_account_data.last_withdrawal_epoch = Some(Clock::get()?.epoch);
-        gem_account_data.all_withdraws.push(total_reward);
+        gem_account_data.record_withdraw(total_reward);
         general_rewards = general_rewards.checked_add(total_reward).unwrap();
         gem_account_data.serialize(&mut &mut gem_account_data_info.data.borrow_mut()[..])?;
     }
@@ -2186,7 +4514,7 @@ pub fn init_rebalance(_program_id: &Pubkey, accounts: &[AccountInfo]) -> Program
             .checked_add(
                 ingl_vote_account_data
                     .dealloced
-                    .checked_sub(ingl_vote_account_data.total_delegated)
+                    .checked_sub(ingl_vote_account_data.pending_delegation_total)
                     .unwrap(),
             )
             .unwrap();
@@ -2250,6 +4578,9 @@ pub fn init_rebalance(_program_id: &Pubkey, accounts: &[AccountInfo]) -> Program
         .unwrap();
     ingl_vote_account_data.dealloced = 0;
     ingl_vote_account_data.pending_validator_rewards = val_owners_lamports;
+    ingl_vote_account_data.last_rebalance_epoch = Clock::get()?.epoch;
+    ingl_vote_account_data.unwinding_reserve_advances = ingl_vote_account_data.reserve_advances;
+    ingl_vote_account_data.reserve_advances = 0;
 
     ingl_vote_account_data
         .serialize(&mut &mut ingl_vote_data_account_info.data.borrow_mut()[..])?;
@@ -2270,8 +4601,13 @@ pub fn finalize_rebalance(_program_id: &Pubkey, accounts: &[AccountInfo]) -> Pro
     let stake_account_info = next_account_info(account_info_iter)?;
     let t_withdraw_info = next_account_info(account_info_iter)?;
     let sysvar_stake_history_info = next_account_info(account_info_iter)?;
+    let global_gem_account_info = next_account_info(account_info_iter)?;
+    let liquidity_reserve_account_info = next_account_info(account_info_iter)?;
 
-    let (_pd_pool_pubkey, pd_pool_bump) = assert_pda_input(&[PD_POOL_KEY.as_ref()], pd_pool_account_info);
+    let (pd_pool_pubkey, pd_pool_bump) = assert_pda_input(&[PD_POOL_KEY.as_ref()], pd_pool_account_info);
+    let (_global_gem_pubkey, _global_gem_bump) = assert_pda_input(&[GLOBAL_GEM_KEY.as_ref()], global_gem_account_info);
+    let (liquidity_reserve_id, _liquidity_reserve_bump) = assert_pda_input(&[LIQUIDITY_RESERVE_KEY.as_ref()], liquidity_reserve_account_info);
+    let mut global_gem_data = GlobalGems::decode(global_gem_account_info)?;
 
     let (expected_t_stake_key, _expected_t_stake_bump) = assert_pda_input(&[T_STAKE_ACCOUNT_KEY.as_ref(), vote_account_info.key.as_ref()], t_stake_account_info);
     assert_pubkeys_exactitude(&expected_t_stake_key, t_stake_account_info.key)?;
@@ -2330,17 +4666,18 @@ pub fn finalize_rebalance(_program_id: &Pubkey, accounts: &[AccountInfo]) -> Pro
         &[&[PD_POOL_KEY.as_ref(), &[pd_pool_bump]]],
     )?;
 
+    // What is left is the deallocated stake, which goes back to the pd pool for its gems to be deallocated or redeemed.
     invoke_signed(
         &solana_program::stake::instruction::withdraw(
             t_withdraw_info.key,
             pd_pool_account_info.key,
-            validator_account_info.key,
+            pd_pool_account_info.key,
             t_withdraw_info.lamports(),
             None,
         ),
         &[
             t_withdraw_info.clone(),
-            validator_account_info.clone(),
+            pd_pool_account_info.clone(),
             sysvar_clock_info.clone(),
             sysvar_stake_history_info.clone(),
             pd_pool_account_info.clone(),
@@ -2348,11 +4685,27 @@ pub fn finalize_rebalance(_program_id: &Pubkey, accounts: &[AccountInfo]) -> Pro
         &[&[PD_POOL_KEY.as_ref(), &[pd_pool_bump]]],
     )?;
 
+    // The stake of instantly redeemed gems is now in the pd pool, and repays the liquidity reserve's advances on them.
+    let refill = ingl_vote_account_data.unwinding_reserve_advances;
+    if refill > 0 {
+        invoke_signed(
+            &system_instruction::transfer(&pd_pool_pubkey, &liquidity_reserve_id, refill),
+            &[
+                pd_pool_account_info.clone(),
+                liquidity_reserve_account_info.clone(),
+            ],
+            &[&[PD_POOL_KEY.as_ref(), &[pd_pool_bump]]],
+        )?;
+        global_gem_data.reserve_advanced_total = global_gem_data.reserve_advanced_total.checked_sub(refill).ok_or(InglError::BeyondBounds)?;
+        ingl_vote_account_data.unwinding_reserve_advances = 0;
+    }
+
     ingl_vote_account_data.pending_validator_rewards = None;
     ingl_vote_account_data.last_total_staked = stake_account_info.lamports();
 
     ingl_vote_account_data
         .serialize(&mut &mut ingl_vote_data_account_info.data.borrow_mut()[..])?;
+    global_gem_data.serialize(&mut &mut global_gem_account_info.data.borrow_mut()[..])?;
     Ok(())
 }
 
@@ -2389,8 +4742,9 @@ pub fn inject_testing_data(_program_id: &Pubkey, accounts: &[AccountInfo], num_m
         &[payer_account_info.clone(), authorized_withdrawer_info.clone()]
     )?;
     // ingl_vote_account_data.vote_rewards = Vec::new();
-    ingl_vote_account_data.vote_rewards.push(VoteRewards{validation_phrase: VOTE_REWARDS_VAL_PHRASE, epoch_number: chosen_epoch-1, total_stake: ingl_vote_account_data.total_delegated, total_reward: 1 * LAMPORTS_PER_SOL });
-    ingl_vote_account_data.vote_rewards.push(VoteRewards{validation_phrase: VOTE_REWARDS_VAL_PHRASE, epoch_number: chosen_epoch, total_stake: ingl_vote_account_data.total_delegated, total_reward: 2*LAMPORTS_PER_SOL });
+    let total_stake = ingl_vote_account_data.total_delegated + ingl_vote_account_data.boosted_delegated;
+    ingl_vote_account_data.vote_rewards.push(VoteRewards{validation_phrase: VOTE_REWARDS_VAL_PHRASE, epoch_number: chosen_epoch-1, total_stake, total_reward: 1 * LAMPORTS_PER_SOL, nft_reward: NFTS_SHARE * LAMPORTS_PER_SOL / 100 });
+    ingl_vote_account_data.vote_rewards.push(VoteRewards{validation_phrase: VOTE_REWARDS_VAL_PHRASE, epoch_number: chosen_epoch, total_stake, total_reward: 2*LAMPORTS_PER_SOL, nft_reward: NFTS_SHARE * 2 * LAMPORTS_PER_SOL / 100 });
     ingl_vote_account_data.last_withdraw_epoch = chosen_epoch-1;
 
 
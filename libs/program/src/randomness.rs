@@ -0,0 +1,265 @@
+use crate::{
+    error::InglError,
+    utils::{assert_owned_by, assert_pubkeys_exactitude},
+};
+use solana_program::{
+    account_info::AccountInfo, hash::hashv, program_error::ProgramError, pubkey::Pubkey, sysvar,
+};
+use switchboard_v2::{AggregatorHistoryBuffer, AggregatorHistoryRow};
+
+/// Provides the bytes a gem's rarity is drawn from. Kept behind a trait so that the source can be swapped
+/// (e.g. for a VRF account) or mocked in tests.
+pub trait RandomnessSource {
+    fn random_bytes(&self) -> Result<[u8; 32], ProgramError>;
+}
+
+/// Commit-reveal over the SlotHashes sysvar. The reveal slot is committed to in `init_rarity_imprint`,
+/// before its hash exists, so the outcome can't be known when committing.
+pub struct SlotHashRandomness<'a, 'b> {
+    slot_hashes_account_info: &'a AccountInfo<'b>,
+    reveal_slot: u64,
+}
+impl<'a, 'b> SlotHashRandomness<'a, 'b> {
+    pub fn new(slot_hashes_account_info: &'a AccountInfo<'b>, reveal_slot: u64) -> Result<Self, ProgramError> {
+        assert_pubkeys_exactitude(&sysvar::slot_hashes::id(), slot_hashes_account_info.key)?;
+        Ok(Self {
+            slot_hashes_account_info,
+            reveal_slot,
+        })
+    }
+
+    /// Hash of the first slot at or after the reveal slot (the reveal slot itself may have been skipped),
+    /// or None once that slot has left the sysvar's window.
+    fn reveal_hash(&self) -> Result<Option<[u8; 32]>, ProgramError> {
+        // The sysvar is read raw as it is too large to be deserialized on chain.
+        // Layout: u64 entry count, then (u64 slot, [u8; 32] hash) entries from the most recent slot.
+        let data = self.slot_hashes_account_info.data.borrow();
+        let entry_count = u64::from_le_bytes(data[0..8].try_into().unwrap()) as usize;
+        let entry_slot = |index: usize| u64::from_le_bytes(data[8 + index * 40..16 + index * 40].try_into().unwrap());
+
+        if entry_count == 0 || entry_slot(0) < self.reveal_slot {
+            Err(InglError::TooEarly.utilize(Some("rarity reveal slot")))?
+        }
+        if entry_slot(entry_count - 1) > self.reveal_slot {
+            return Ok(None);
+        }
+        let index = (0..entry_count).rev().find(|&index| entry_slot(index) >= self.reveal_slot).unwrap();
+        Ok(Some(data[16 + index * 40..48 + index * 40].try_into().unwrap()))
+    }
+
+    pub fn is_expired(&self) -> Result<bool, ProgramError> {
+        Ok(self.reveal_hash()?.is_none())
+    }
+}
+impl RandomnessSource for SlotHashRandomness<'_, '_> {
+    fn random_bytes(&self) -> Result<[u8; 32], ProgramError> {
+        match self.reveal_hash()? {
+            Some(hash) => Ok(hash),
+            None => Err(InglError::TooLate.utilize(Some("rarity reveal slot")))?,
+        }
+    }
+}
+
+const HISTORY_BUFFER_DISCRIMINATOR: &[u8; 8] = b"BUFFERxx";
+const HISTORY_BUFFER_HEADER_LEN: usize = 8 + 4;
+const HISTORY_ROW_LEN: usize = 8 + 16 + 4;
+
+/// Hashes the Switchboard price history of the configured feeds at `seed_time`. Predictable once the prices are known,
+/// so only used as additional entropy.
+pub struct PriceFeedRandomness<'a, 'b> {
+    feed_account_infos: Vec<&'a AccountInfo<'b>>,
+    seed_time: u32,
+    max_staleness: u32,
+}
+impl<'a, 'b> PriceFeedRandomness<'a, 'b> {
+    pub fn new(
+        feed_account_infos: Vec<&'a AccountInfo<'b>>,
+        feed_keys: &[Pubkey],
+        oracle_program_id: &Pubkey,
+        seed_time: u32,
+        max_staleness: u32,
+    ) -> Result<Self, ProgramError> {
+        if feed_account_infos.len() != feed_keys.len() {
+            Err(InglError::BeyondBounds.utilize(Some("rarity feed count")))?
+        }
+        for (feed_account_info, feed_key) in feed_account_infos.iter().zip(feed_keys.iter()) {
+            assert_pubkeys_exactitude(feed_key, feed_account_info.key)?;
+            assert_owned_by(feed_account_info, oracle_program_id)?;
+        }
+        Ok(Self {
+            feed_account_infos,
+            seed_time,
+            max_staleness,
+        })
+    }
+
+    /// Latest row of the feed's history at or before `timestamp`. The buffer's shape is checked beforehand,
+    /// as the Switchboard parser panics on malformed or empty buffers.
+    fn history_row_at(feed_account_info: &AccountInfo, timestamp: i64) -> Result<AggregatorHistoryRow, ProgramError> {
+        let feed_unavailable = || InglError::OracleFeedUnavailable.utilize(Some(&feed_account_info.key.to_string()));
+        {
+            let data = feed_account_info.data.borrow();
+            if data.len() < HISTORY_BUFFER_HEADER_LEN + HISTORY_ROW_LEN || &data[..8] != HISTORY_BUFFER_DISCRIMINATOR {
+                return Err(feed_unavailable());
+            }
+            let row_count = (data.len() - HISTORY_BUFFER_HEADER_LEN) / HISTORY_ROW_LEN;
+            let insertion_index = u32::from_le_bytes(data[8..12].try_into().unwrap()) as usize;
+            if data.len() != HISTORY_BUFFER_HEADER_LEN + row_count * HISTORY_ROW_LEN || insertion_index >= row_count {
+                return Err(feed_unavailable());
+            }
+        }
+        let history = AggregatorHistoryBuffer::new(feed_account_info).map_err(|_| feed_unavailable())?;
+        history.lower_bound(timestamp).ok_or_else(feed_unavailable)
+    }
+}
+impl RandomnessSource for PriceFeedRandomness<'_, '_> {
+    fn random_bytes(&self) -> Result<[u8; 32], ProgramError> {
+        let mut prices: Vec<[u8; 20]> = Vec::new();
+        for feed_account_info in self.feed_account_infos.iter() {
+            let AggregatorHistoryRow { value, timestamp } = Self::history_row_at(feed_account_info, self.seed_time as i64)?;
+            if self.seed_time as i64 - timestamp > self.max_staleness as i64 {
+                Err(InglError::StaleOracleFeed.utilize(Some(&feed_account_info.key.to_string())))?
+            }
+            let mut price = [0; 20];
+            price[..16].copy_from_slice(&value.mantissa.to_le_bytes());
+            price[16..].copy_from_slice(&value.scale.to_le_bytes());
+            prices.push(price);
+        }
+        let price_bytes: Vec<&[u8]> = prices.iter().map(|price| price.as_ref()).collect();
+        Ok(hashv(&price_bytes).to_bytes())
+    }
+}
+
+/// Account data of a Switchboard history buffer holding `rows`, oldest first, so that imprinting can be exercised
+/// locally (e.g. in solana-program-test) against feeds owned by the configured oracle program.
+#[cfg(not(target_arch = "bpf"))]
+pub fn mock_history_buffer_data(rows: &[AggregatorHistoryRow]) -> Vec<u8> {
+    let mut data = Vec::with_capacity(HISTORY_BUFFER_HEADER_LEN + rows.len().max(1) * HISTORY_ROW_LEN);
+    data.extend_from_slice(HISTORY_BUFFER_DISCRIMINATOR);
+    data.extend_from_slice(&(rows.len().saturating_sub(1) as u32).to_le_bytes());
+    for row in rows {
+        let AggregatorHistoryRow { timestamp, value } = *row;
+        data.extend_from_slice(&timestamp.to_le_bytes());
+        data.extend_from_slice(&value.mantissa.to_le_bytes());
+        data.extend_from_slice(&value.scale.to_le_bytes());
+    }
+    if rows.is_empty() {
+        data.resize(HISTORY_BUFFER_HEADER_LEN + HISTORY_ROW_LEN, 0);
+    }
+    data
+}
+
+/// Maps random bytes uniformly onto `0..range`. Each 8 byte chunk is read as a u64 and rejected when it falls in the
+/// incomplete last multiple of `range`, which would otherwise favour low values; the bytes are rehashed if all chunks are rejected.
+pub fn uniform_random_value(random_bytes: &[u8; 32], range: u64) -> u64 {
+    let limit = u64::MAX - u64::MAX % range;
+    let mut bytes = *random_bytes;
+    loop {
+        for chunk in bytes.chunks_exact(8) {
+            let value = u64::from_le_bytes(chunk.try_into().unwrap());
+            if value < limit {
+                return value % range;
+            }
+        }
+        bytes = hashv(&[&bytes]).to_bytes();
+    }
+}
+
+/// Combines the sources with the gem's mint, so that gems revealed from the same sources still get distinct values.
+pub fn mix_randomness(sources: &[&dyn RandomnessSource], mint: &Pubkey, program_id: &Pubkey) -> Result<[u8; 32], ProgramError> {
+    let mut source_bytes: Vec<[u8; 32]> = Vec::new();
+    for source in sources {
+        source_bytes.push(source.random_bytes()?);
+    }
+    let mut seeds: Vec<&[u8]> = source_bytes.iter().map(|bytes| bytes.as_ref()).collect();
+    seeds.push(mint.as_ref());
+    seeds.push(program_id.as_ref());
+    Ok(hashv(&seeds).to_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        error::InglError,
+        state::{
+            constants::{self, RARITY_VALUE_RANGE},
+            Class,
+        },
+    };
+    use switchboard_v2::{SwitchboardDecimal, SWITCHBOARD_V2_DEVNET};
+
+    struct MockRandomness(u64);
+    impl RandomnessSource for MockRandomness {
+        fn random_bytes(&self) -> Result<[u8; 32], ProgramError> {
+            let mut bytes = [0; 32];
+            bytes[..8].copy_from_slice(&self.0.to_le_bytes());
+            Ok(bytes)
+        }
+    }
+
+    #[test]
+    fn rarity_distribution_matches_class_tables() {
+        const IMPRINTS: u64 = 1_000_000;
+        // Chi-square critical value for 4 degrees of freedom at the 0.1% level.
+        const CHI_SQUARE_LIMIT: f64 = 18.47;
+        let mint = Pubkey::new_unique();
+
+        for class in [Class::Ruby, Class::Diamond, Class::Sapphire, Class::Emerald, Class::Serendibite, Class::Benitoite] {
+            let mut expected = [0.0; 5];
+            for value in 0..RARITY_VALUE_RANGE {
+                expected[class.get_rarity(value).unwrap() as usize] += IMPRINTS as f64 / RARITY_VALUE_RANGE as f64;
+            }
+
+            let mut realized = [0u64; 5];
+            for nonce in 0..IMPRINTS {
+                let bytes = mix_randomness(&[&MockRandomness(nonce)], &mint, &constants::id()).unwrap();
+                realized[class.get_rarity(uniform_random_value(&bytes, RARITY_VALUE_RANGE)).unwrap() as usize] += 1;
+            }
+
+            let chi_square: f64 = expected
+                .iter()
+                .zip(realized.iter())
+                .filter(|(expected, _)| **expected > 0.0)
+                .map(|(expected, realized)| (*realized as f64 - expected).powi(2) / expected)
+                .sum();
+            assert!(chi_square < CHI_SQUARE_LIMIT, "{:?}: expected {:?}, realized {:?}", class, expected, realized);
+        }
+    }
+
+    fn history_row(timestamp: i64, mantissa: i128) -> AggregatorHistoryRow {
+        AggregatorHistoryRow {
+            timestamp,
+            value: SwitchboardDecimal { mantissa, scale: 2 },
+        }
+    }
+
+    fn feed_random_bytes(data: &mut [u8], seed_time: u32) -> Result<[u8; 32], ProgramError> {
+        let feed_key = Pubkey::new_unique();
+        let mut lamports = 0;
+        let feed_account_info = AccountInfo::new(&feed_key, false, false, &mut lamports, data, &SWITCHBOARD_V2_DEVNET, false, 0);
+        PriceFeedRandomness::new(vec![&feed_account_info], &[feed_key], &SWITCHBOARD_V2_DEVNET, seed_time, 600)?.random_bytes()
+    }
+
+    #[test]
+    fn price_feed_randomness_uses_row_at_seed_time() {
+        let mut data = mock_history_buffer_data(&[history_row(1_000, 100), history_row(1_500, 200)]);
+        let mut same_price_data = mock_history_buffer_data(&[history_row(1_400, 100)]);
+        let mut other_price_data = mock_history_buffer_data(&[history_row(1_400, 300)]);
+
+        let bytes = feed_random_bytes(&mut data, 1_450).unwrap();
+        assert_eq!(bytes, feed_random_bytes(&mut same_price_data, 1_450).unwrap());
+        assert_ne!(bytes, feed_random_bytes(&mut other_price_data, 1_450).unwrap());
+    }
+
+    #[test]
+    fn price_feed_randomness_rejects_stale_and_missing_history() {
+        let stale = Err(ProgramError::from(InglError::StaleOracleFeed));
+        let unavailable = Err(ProgramError::from(InglError::OracleFeedUnavailable));
+
+        assert_eq!(feed_random_bytes(&mut mock_history_buffer_data(&[history_row(1_000, 100)]), 1_601), stale);
+        assert_eq!(feed_random_bytes(&mut mock_history_buffer_data(&[history_row(1_000, 100)]), 999), unavailable);
+        assert_eq!(feed_random_bytes(&mut mock_history_buffer_data(&[]), 1_000), unavailable);
+        assert_eq!(feed_random_bytes(&mut [0; 40], 1_000), unavailable);
+    }
+}
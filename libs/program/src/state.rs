@@ -1,9 +1,11 @@
 use crate::{error::InglError, utils::assert_program_owned};
 use borsh::{BorshDeserialize, BorshSerialize};
+use mpl_token_metadata::state::MAX_URI_LENGTH;
 use serde::{Deserialize, Serialize};
 use solana_program::{
     account_info::AccountInfo,
     borsh::try_from_slice_unchecked,
+    entrypoint::ProgramResult,
     native_token::LAMPORTS_PER_SOL,
     program_error::ProgramError,
     pubkey::Pubkey,
@@ -22,11 +24,24 @@ pub mod constants {
     pub const COLLECTION_HOLDER_KEY: &str = "collection_holder";
     pub const GLOBAL_GEM_KEY: &str = "global_gem_account";
     pub const GEM_ACCOUNT_CONST: &str = "gem_account";
-    pub const FEE_MULTIPLYER: u8 = 10;
+    pub const FEE_MULTIPLYER: u8 = 10; //Default percent of the class lamports charged when redeeming right after minting.
+    pub const DEFAULT_REDEEM_FEE_DURATION: u32 = 60 * 60 * 24 * 365; //Seconds after creation from which a gem redeems without fee.
     pub const PRICE_TIME_INTERVAL: u8 = 20;
+    pub const RARITY_REVEAL_SLOT_DELAY: u64 = 50; //Roughly PRICE_TIME_INTERVAL seconds worth of slots.
+    pub const RARITY_VALUE_RANGE: u64 = 10_000; //Rarity tables are expressed in basis points of this range.
+    pub const RARITY_IMPRINT_TIMEOUT: u32 = 86400; //Seconds after the rarity seed time from which a pending imprint can be cancelled.
+    pub const DEFAULT_REROLL_FEE: u64 = LAMPORTS_PER_SOL / 10;
+    pub const DEFAULT_MAX_RARITY_REROLLS: u8 = 3;
+    pub const DEFAULT_REFERRAL_SHARE: u8 = 10; //Percent of the treasury's part of a referred gem's redeem fee.
+    pub const MAX_REFERRAL_SHARE: u8 = 50;
+    pub const DEFAULT_MAX_REFERRAL_REWARD: u64 = 10 * LAMPORTS_PER_SOL; //Lifetime rewards of a single referrer.
     pub const TREASURY_FEE_MULTIPLYER: u8 = 50;
+    pub const DEFAULT_LIQUIDITY_RESERVE_SHARE: u8 = 20; //Percent of the redeem fees set aside in the liquidity reserve.
+    pub const DEFAULT_INSTANT_EXIT_DISCOUNT: u8 = 3; //Percent of an instant redemption's payout kept by the liquidity reserve.
+    pub const MAX_INSTANT_EXIT_DISCOUNT: u8 = 50;
     pub const MAXIMUM_DELEGATABLE_STAKE: u64 = 5 * LAMPORTS_PER_SOL; //10_000 * LAMPORTS_PER_SOL;
     pub const ALLOCATE_LOCK_TIME: u32 = /*86400**/1*365*2; //Needs to be changed back to 86400*... before deployment on mainnet. reduced for testing purposes during development
+    //Default rarity feeds, copied into the config on init.
     pub const BTC_FEED_PUBLIC_KEY: &str = "9ATrvi6epR5hVYtwNs7BB7VCiYnd4WM7e8MfafWpfiXC";
     pub const SOL_FEED_PUBLIC_KEY: &str = "7LLvRhMs73FqcLkA8jvEE1AM2mYZXTmqfUv8GAEurymx";
     pub const ETH_FEED_PUBLIC_KEY: &str = "6fhxFvPocWapZ5Wa2miDnrX2jYRFKvFqYnX11GGkBo2f";
@@ -43,11 +58,54 @@ pub mod constants {
     pub const T_STAKE_ACCOUNT_KEY: &str = "Temporary_stake_account_key";
     pub const T_WITHDRAW_KEY: &str = "Temporary_withdraw";
     pub const DUPKEYBYTES: &[u8] = b"dupkey";
+    pub const VOTE_BALLOT_KEY: &str = "vote_ballot";
+    pub const INGL_CONFIG_KEY: &str = "ingl_config";
+    pub const GOVERNANCE_PROPOSAL_KEY: &str = "governance_proposal";
+    pub const GOVERNANCE_VOTE_KEY: &str = "governance_vote";
+    pub const COUNCIL_VETO_KEY: &str = "council_veto";
+    pub const VALIDATOR_CANDIDATE_KEY: &str = "validator_candidate";
+    pub const VALIDATOR_BOND_KEY: &str = "validator_bond";
+    pub const CANDIDATE_INDEX_KEY: &str = "candidate_index";
+    pub const PROPOSAL_PAGE_KEY: &str = "proposal_page";
+    pub const RARITY_TABLE_KEY: &str = "rarity_table";
+    pub const METADATA_URI_REGISTRY_KEY: &str = "metadata_uri_registry";
+    pub const MINT_SCHEDULE_KEY: &str = "mint_schedule";
+    pub const MINT_WALLET_RECORD_KEY: &str = "mint_wallet_record";
+    pub const REFERRER_KEY: &str = "referrer";
+    pub const WRAPPED_SOL_ESCROW_KEY: &str = "wrapped_sol_escrow";
+    pub const LIQUIDITY_RESERVE_KEY: &str = "liquidity_reserve";
+    pub const TREASURY_LEDGER_KEY: &str = "treasury_ledger";
+
+    pub const DEFAULT_GOVERNANCE_VOTING_PERIOD: u32 = 86400 * 3;
+    pub const DEFAULT_GOVERNANCE_TIMELOCK: u32 = 86400 * 2;
+    pub const DEFAULT_GOVERNANCE_QUORUM: u32 = 10; //In SOL backing the voting gems. To be raised before deployment on mainnet.
+    pub const DEFAULT_COUNCIL_VETO_THRESHOLD: u32 = 1; //Number of council members. To be raised as more validators join.
+    pub const DEFAULT_COUNCIL_PAUSE_THRESHOLD: u32 = 0; //0 requires a majority of the council tokens in circulation.
+
+    pub const VALIDATOR_REGISTRATION_FEE: u64 = 2 * LAMPORTS_PER_SOL;
+    pub const DEFAULT_REGISTRATION_REFUND_PERCENTAGE: u8 = 50; //Portion of the registration fee refunded to withdrawing candidates.
+    pub const MIN_VALIDATOR_SELF_BOND: u64 = LAMPORTS_PER_SOL; //Escrowed by the validator on registration.
+    pub const MAX_VALIDATOR_COMMISSION: u8 = 100;
+    pub const MAX_CANDIDATE_NAME_LENGTH: usize = 32;
+    pub const MAX_CANDIDATE_WEBSITE_LENGTH: usize = 64;
+    pub const CANDIDATES_PER_PAGE: u32 = 20; //Bounded by the number of candidate accounts fitting in one transaction.
+    pub const MAX_URI_REGISTRY_ENTRIES: usize = 36; //One per (class, rarity) pair of the fixed classes, unrevealed included.
+    pub const MAX_RARITY_FEEDS: usize = 8;
+    pub const DEFAULT_MAX_FEED_STALENESS: u32 = 600; //Seconds between the rarity seed time and the feed price used.
+    pub const MAX_BASE_URI_LENGTH: usize = 160; //Leaves room for the "/<class>/<rarity>.json" suffix within metaplex's uri limit.
+    pub const MAX_GEM_WITHDRAW_HISTORY: usize = 11; //The history caps keep a full gem within the 500 bytes gem accounts were created with.
+    pub const MAX_GEM_VOTE_HISTORY: usize = 2;
 
     pub const VALIDATOR_ID_SHARE: u64 = 15;
     pub const TREASURY_SHARE: u64 = 13;
     pub const TEAM_SHARE: u64 = 12;
     pub const NFTS_SHARE: u64 = 60;
+    pub const MIN_CUSTOM_CLASS_SOL: u32 = 1;
+    pub const MAX_CUSTOM_CLASS_SOL: u32 = 10_000;
+    pub const MAX_CUSTOM_CLASSES: usize = 8;
+    pub const MAX_CLASS_NAME_LENGTH: usize = 20; //Leaves room for the " #<numeration>" suffix within metaplex's name limit.
+    pub const MAX_SCHEDULED_CLASSES: usize = 6 + MAX_CUSTOM_CLASSES;
+    pub const MAX_RARITY_BOOST: u8 = 20; //Percent of a gem's reward weight. At most NFTS_SHARE * 20% = 12, which the treasury share can fund.
 
     pub const VALIDATOR_PROPOSAL_VAL_PHRASE: u32 = 842_542_964;
     pub const GLOBAL_GEMS_VAL_PHRASE: u32 = 546_875_215;
@@ -55,6 +113,18 @@ pub mod constants {
     pub const INGL_VOTE_ACCOUNT_DATA_VAL_PHRASE: u32 = 842_154_348;
     pub const GEM_ACCOUNT_VAL_PHRASE: u32 = 516_248_961;
     pub const VOTE_REWARDS_VAL_PHRASE: u32 = 584_625_418;
+    pub const VOTE_BALLOT_VAL_PHRASE: u32 = 735_218_469;
+    pub const INGL_CONFIG_VAL_PHRASE: u32 = 397_564_182;
+    pub const GOVERNANCE_PROPOSAL_VAL_PHRASE: u32 = 628_417_953;
+    pub const VALIDATOR_CANDIDATE_VAL_PHRASE: u32 = 472_915_836;
+    pub const CANDIDATE_INDEX_VAL_PHRASE: u32 = 913_482_675;
+    pub const PROPOSAL_PAGE_VAL_PHRASE: u32 = 267_391_548;
+    pub const RARITY_TABLE_VAL_PHRASE: u32 = 581_736_294;
+    pub const METADATA_URI_REGISTRY_VAL_PHRASE: u32 = 359_184_726;
+    pub const MINT_SCHEDULE_VAL_PHRASE: u32 = 724_913_568;
+    pub const MINT_WALLET_RECORD_VAL_PHRASE: u32 = 186_452_397;
+    pub const REFERRER_VAL_PHRASE: u32 = 639_284_715;
+    pub const TREASURY_LEDGER_VAL_PHRASE: u32 = 472_815_936;
 
     pub mod spl_program {
         use solana_program::declare_id;
@@ -83,7 +153,7 @@ pub struct VoteInit {
     pub commission: u8,
 }
 
-#[derive(BorshSerialize, Copy, Debug,  Clone, BorshDeserialize)]
+#[derive(BorshSerialize, Copy, Debug,  Clone, BorshDeserialize, PartialEq)]
 pub enum Class {
     Ruby,
     Diamond,
@@ -91,6 +161,7 @@ pub enum Class {
     Emerald,
     Serendibite,
     Benitoite,
+    Custom { sol: u32 }, //A tier listed in the config's class catalogue by governance.
 }
 
 impl Class {
@@ -103,11 +174,22 @@ impl Class {
                 Self::Emerald => 10,
                 Self::Serendibite => 5,
                 Self::Benitoite => 1,
+                Self::Custom { sol } => sol as u64,
             }
     }
+
+    /// Seed of the class' PDAs. For the fixed classes this is the single discriminant byte.
+    pub fn get_seed(self) -> Vec<u8> {
+        self.try_to_vec().unwrap()
+    }
+
+    /// Weight of a gem of this class in proposal votes, i.e. its backing in whole SOL.
+    pub fn get_vote_weight(self) -> u32 {
+        (self.get_class_lamports() / LAMPORTS_PER_SOL) as u32
+    }
 }
 
-#[derive(BorshDeserialize, Debug, BorshSerialize, Clone)]
+#[derive(BorshDeserialize, Debug, BorshSerialize, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub enum Rarity {
     Common,
     Uncommon,
@@ -116,6 +198,49 @@ pub enum Rarity {
     Mythic,
 }
 
+/// Basis point weights of each rarity for a class, indexed from Common to Mythic. Set through governance ahead of a mint season;
+/// classes without a table fall back to `Class::get_rarity`.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct RarityTable {
+    pub validation_phrase: u32,
+    pub class: Class,
+    pub weights: [u16; 5],
+}
+impl RarityTable {
+    pub fn space() -> usize {
+        4 + (1 + 4) + 2 * 5
+    }
+    pub fn are_weights_valid(weights: &[u16; 5]) -> bool {
+        weights.iter().map(|weight| *weight as u64).sum::<u64>() == RARITY_VALUE_RANGE
+    }
+    pub fn validate(self) -> Result<Self, ProgramError> {
+        if self.validation_phrase != RARITY_TABLE_VAL_PHRASE {
+            Err(InglError::InvalidValPhrase.utilize(Some("Rarity Table")))?
+        }
+        if !Self::are_weights_valid(&self.weights) {
+            Err(InglError::BeyondBounds.utilize(Some("rarity weights")))?
+        }
+        Ok(self)
+    }
+    pub fn decode(account: &AccountInfo) -> Result<Self, ProgramError> {
+        assert_program_owned(account).unwrap();
+        let a: Self = try_from_slice_unchecked(&account.data.borrow()).unwrap();
+        a.validate()
+    }
+    /// Like the hard coded tables, the lowest values are assigned to the rarest gems.
+    pub fn get_rarity(&self, random_value: u64) -> Rarity {
+        let rarities = [Rarity::Common, Rarity::Uncommon, Rarity::Rare, Rarity::Exalted, Rarity::Mythic];
+        let mut threshold: u64 = 0;
+        for (rarity, weight) in rarities.into_iter().zip(self.weights.iter()).rev() {
+            threshold += *weight as u64;
+            if random_value < threshold {
+                return rarity;
+            }
+        }
+        Rarity::Common
+    }
+}
+
 #[derive(BorshDeserialize, Clone, BorshSerialize)]
 pub struct GlobalGems {
     pub validation_phrase: u32,
@@ -127,8 +252,12 @@ pub struct GlobalGems {
     pub is_proposal_ongoing: bool,
     pub proposal_numeration: u32,
     pub pending_delegation_total: u64,
-    pub validator_list: Vec<Pubkey>, //This is not the validator list to display for proposals check instead the ValidatorProposal Struct.
-                                     // pub winners_list: Vec<u32>, // To include next so as to
+    pub candidate_count: u32, //Number of unelected candidates, each reachable through its CandidateIndex account.
+    pub redemption_queue_length: u32, //Queue positions handed out so far.
+    pub queued_redemptions: u32, //Queued redemptions not yet claimed.
+    pub queued_redemption_lamports: u64, //Class lamports owed to the queued redemptions not yet claimed.
+    pub reserve_advanced_total: u64, //Lamports paid out by the liquidity reserve for instant redemptions, not yet refilled from the unwound stake.
+                              // pub winners_list: Vec<u32>, // To include next so as to
 }
 impl GlobalGems {
     pub fn validate(self) -> Result<Self, ProgramError> {
@@ -144,7 +273,7 @@ impl GlobalGems {
     }
 }
 
-#[derive(BorshDeserialize,  Debug, BorshSerialize)]
+#[derive(BorshDeserialize,  Debug, BorshSerialize, Clone)]
 pub enum FundsLocation {
     MintingPool,
     PDPool,
@@ -171,6 +300,14 @@ impl ValidatorVote {
     }
 }
 
+/// A key allowed to vote with a gem on behalf of its owner, e.g. a hot wallet for a gem kept in cold storage.
+/// Only honoured while `owner` still holds the gem.
+#[derive(BorshDeserialize, Debug, BorshSerialize)]
+pub struct VotingDelegate {
+    pub owner: Pubkey,
+    pub delegate: Pubkey,
+}
+
 #[derive(BorshDeserialize, Debug, BorshSerialize)]
 pub struct GemAccountV0_0_1 {
     pub struct_id: GemAccountVersions,
@@ -188,6 +325,14 @@ pub struct GemAccountV0_0_1 {
     pub last_delegation_epoch: Option<u64>,
     pub all_withdraws: Vec<u64>,
     pub all_votes: Vec<ValidatorVote>,
+    pub voting_delegate: Option<VotingDelegate>,
+    pub rarity_reveal_slot: Option<u64>,
+    pub reward_boost: Option<u64>, //Reward weight added on top of the class lamports, fixed while the gem is delegated.
+    pub rarity_rerolls: Option<u8>, //Paid re-rolls and cancelled imprints.
+    pub referrer: Option<Pubkey>, //Wallet of the referrer credited with a share of the gem's redeem fee.
+    pub queued_redemption: Option<QueuedRedemption>,
+    pub ballot_voter: Option<Pubkey>, //Voter whose ballot holds the gem's weight for `last_voted_proposal`.
+    pub governance_vote_end: Option<u32>, //End of the latest governance vote the gem took part in.
 }
 impl GemAccountV0_0_1 {
     pub fn validate(account_data: Self) -> Result<Self, ProgramError> {
@@ -196,6 +341,50 @@ impl GemAccountV0_0_1 {
         }
         Ok(account_data)
     }
+    pub fn space() -> usize {
+        1 + 4 + 4 + 5 + 4 + 4 + 2 + 33 + 5 + 5 + 33 + 9 + 9
+            + (4 + 8 * MAX_GEM_WITHDRAW_HISTORY)
+            + (4 + (4 + 32 + 4) * MAX_GEM_VOTE_HISTORY)
+            + 65 + 9 + 9 + 2 + 33 + 46 + 33 + 5
+    }
+    /// Records a reward withdrawal, dropping the oldest ones once the history is full.
+    pub fn record_withdraw(&mut self, lamports: u64) {
+        while self.all_withdraws.len() >= MAX_GEM_WITHDRAW_HISTORY {
+            self.all_withdraws.remove(0);
+        }
+        self.all_withdraws.push(lamports);
+    }
+    /// Records a validator vote, dropping the oldest ones once the history is full.
+    pub fn record_vote(&mut self, vote: ValidatorVote) {
+        while self.all_votes.len() >= MAX_GEM_VOTE_HISTORY {
+            self.all_votes.remove(0);
+        }
+        self.all_votes.push(vote);
+    }
+    /// Governance votes are recorded per mint, so a gem burnt during a vote, whether fused, split or redeemed and re-minted,
+    /// would let its weight vote again.
+    pub fn assert_no_open_governance_vote(&self, now: u32) -> ProgramResult {
+        if matches!(self.governance_vote_end, Some(vote_end) if now < vote_end) {
+            Err(InglError::TooEarly.utilize(Some("governance vote ongoing")))?
+        }
+        Ok(())
+    }
+    /// Queued gems are on their way out: their funds can't be moved other than by claiming the redemption.
+    pub fn assert_not_queued(&self) -> ProgramResult {
+        if self.queued_redemption.is_some() {
+            Err(InglError::RedemptionQueued.utilize(Some("gem")))?
+        }
+        Ok(())
+    }
+}
+
+/// A gem's place in the redemption queue, from `RedeemQueued` until `ClaimRedemption` pays it out.
+/// While the pd pool can't cover every queued redemption, claims are paid in queue order.
+#[derive(BorshDeserialize, Debug, BorshSerialize)]
+pub struct QueuedRedemption {
+    pub position: u32,
+    pub requested_epoch: u64,
+    pub unwinding_vote_account: Option<Pubkey>, //Vote account the gem was undelegated from, whose stake must be rebalanced before claiming.
 }
 
 #[derive(BorshDeserialize, Debug,  BorshSerialize)]
@@ -224,13 +413,20 @@ impl GemAccountVersions {
 #[derive(BorshDeserialize, BorshSerialize)]
 pub struct ValidatorProposal {
     pub validation_phrase: u32,
-    pub validator_ids: Vec<Pubkey>,
+    pub candidate_count: u32, //Candidates are listed over ProposalPage accounts of CANDIDATES_PER_PAGE each.
     pub date_created: u32,
     pub date_finalized: Option<u32>,
-    pub votes: Vec<u32>,
+    pub leader: Option<Pubkey>,
+    pub leader_votes: u32,
     pub winner: Option<Pubkey>,
 }
 impl ValidatorProposal {
+    pub fn space() -> usize {
+        4 + 4 + 4 + 5 + 33 + 4 + 33
+    }
+    pub fn page_count(&self) -> u32 {
+        self.candidate_count.div_ceil(CANDIDATES_PER_PAGE)
+    }
     pub fn validate(self) -> Result<Self, ProgramError> {
         if self.validation_phrase != VALIDATOR_PROPOSAL_VAL_PHRASE {
             Err(InglError::InvalidValPhrase.utilize(Some("Validator Proposal")))?
@@ -244,12 +440,124 @@ impl ValidatorProposal {
     }
 }
 
+/// Registration details of a validator, displayed to voters and checked for eligibility on proposal creation.
+/// The identity key co-signing the registration stands as the attestation that the registrant controls the node.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct ValidatorCandidate {
+    pub validation_phrase: u32,
+    pub validator_id: Pubkey,
+    pub name: String,
+    pub website: String,
+    pub commission: u8,
+    pub self_bond: u64,
+    pub date_registered: u32,
+    pub index: Option<u32>, //None once elected or withdrawn.
+}
+impl ValidatorCandidate {
+    pub fn space() -> usize {
+        4 + 32 + (4 + MAX_CANDIDATE_NAME_LENGTH) + (4 + MAX_CANDIDATE_WEBSITE_LENGTH) + 1 + 8 + 4 + 5
+    }
+    pub fn validate(self) -> Result<Self, ProgramError> {
+        if self.validation_phrase != VALIDATOR_CANDIDATE_VAL_PHRASE {
+            Err(InglError::InvalidValPhrase.utilize(Some("Validator Candidate")))?
+        }
+        Ok(self)
+    }
+    pub fn decode(account: &AccountInfo) -> Result<Self, ProgramError> {
+        assert_program_owned(account).unwrap();
+        let a: Self = try_from_slice_unchecked(&account.data.borrow()).unwrap();
+        a.validate()
+    }
+    pub fn is_eligible(&self) -> bool {
+        self.self_bond >= MIN_VALIDATOR_SELF_BOND && self.commission <= MAX_VALIDATOR_COMMISSION
+    }
+}
+
+/// Maps a position in the candidate set to the candidate's validator id, so that candidates can be enumerated.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct CandidateIndex {
+    pub validation_phrase: u32,
+    pub validator_id: Pubkey,
+}
+impl CandidateIndex {
+    pub fn space() -> usize {
+        4 + 32
+    }
+    pub fn validate(self) -> Result<Self, ProgramError> {
+        if self.validation_phrase != CANDIDATE_INDEX_VAL_PHRASE {
+            Err(InglError::InvalidValPhrase.utilize(Some("Candidate Index")))?
+        }
+        Ok(self)
+    }
+    pub fn decode(account: &AccountInfo) -> Result<Self, ProgramError> {
+        assert_program_owned(account).unwrap();
+        let a: Self = try_from_slice_unchecked(&account.data.borrow()).unwrap();
+        a.validate()
+    }
+}
+
+/// Holds the candidates of a `ValidatorProposal` from index `page_number * CANDIDATES_PER_PAGE`, along with their votes.
+/// Ineligible candidates are kept as the default pubkey so that indices stay aligned.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct ProposalPage {
+    pub validation_phrase: u32,
+    pub proposal_id: Pubkey,
+    pub page_number: u32,
+    pub validator_ids: Vec<Pubkey>,
+    pub votes: Vec<u32>,
+}
+impl ProposalPage {
+    pub fn space() -> usize {
+        4 + 32 + 4 + (4 + 32 * CANDIDATES_PER_PAGE as usize) + (4 + 4 * CANDIDATES_PER_PAGE as usize)
+    }
+    pub fn validate(self) -> Result<Self, ProgramError> {
+        if self.validation_phrase != PROPOSAL_PAGE_VAL_PHRASE {
+            Err(InglError::InvalidValPhrase.utilize(Some("Proposal Page")))?
+        }
+        Ok(self)
+    }
+    pub fn decode(account: &AccountInfo) -> Result<Self, ProgramError> {
+        assert_program_owned(account).unwrap();
+        let a: Self = try_from_slice_unchecked(&account.data.borrow()).unwrap();
+        a.validate()
+    }
+}
+
+/// Accumulates the weight of a voter's gems over several `AddGemsToBallot` transactions,
+/// so that it can be committed to a `ValidatorProposal` in one `CastBallot`.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct VoteBallot {
+    pub validation_phrase: u32,
+    pub proposal_id: Pubkey,
+    pub voter: Pubkey,
+    pub total_weight: u32,
+    pub gem_count: u32,
+    pub cast_validator_index: Option<u32>,
+}
+impl VoteBallot {
+    pub fn space() -> usize {
+        4 + 32 + 32 + 4 + 4 + 5
+    }
+    pub fn validate(self) -> Result<Self, ProgramError> {
+        if self.validation_phrase != VOTE_BALLOT_VAL_PHRASE {
+            Err(InglError::InvalidValPhrase.utilize(Some("Vote Ballot")))?
+        }
+        Ok(self)
+    }
+    pub fn decode(account: &AccountInfo) -> Result<Self, ProgramError> {
+        assert_program_owned(account).unwrap();
+        let a: Self = try_from_slice_unchecked(&account.data.borrow()).unwrap();
+        a.validate()
+    }
+}
+
 #[derive(BorshDeserialize, Copy, Clone, BorshSerialize)]
 pub struct VoteRewards {
     pub validation_phrase: u32,
     pub epoch_number: u64,
     pub total_reward: u64,
-    pub total_stake: u64,
+    pub total_stake: u64, //Reward weight of the delegated gems, rarity boosts included.
+    pub nft_reward: u64,
 }
 impl VoteRewards {
     pub fn validate(self) -> Result<Self, ProgramError> {
@@ -275,7 +583,11 @@ pub struct InglVoteAccountData {
     pub last_total_staked: u64,
     pub is_t_stake_initialized: bool,
     pub pending_delegation_total: u64,
+    pub boosted_delegated: u64, //Sum of the rarity boosts of the delegated gems.
     pub vote_rewards: Vec<VoteRewards>,
+    pub last_rebalance_epoch: u64, //Epoch of the last init_rebalance. Stake undelegated before it is back in the pd pool once it is finalized.
+    pub reserve_advances: u64, //Liquidity reserve advances on gems undelegated since the last init_rebalance.
+    pub unwinding_reserve_advances: u64, //Advances refilled from the stake deactivated by the ongoing rebalance.
 }
 impl InglVoteAccountData {
     pub fn validate(self) -> Result<Self, ProgramError> {
@@ -291,6 +603,528 @@ impl InglVoteAccountData {
     }
 }
 
+/// Protocol parameters that can be changed through governance proposals.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct InglConfig {
+    pub validation_phrase: u32,
+    pub authority: Pubkey,
+    pub governance_voting_period: u32,
+    pub governance_timelock: u32,
+    pub governance_quorum: u32,
+    pub governance_proposal_numeration: u32,
+    pub council_veto_threshold: u32,
+    pub council_pause_threshold: u32,
+    pub is_paused: bool,
+    pub registration_refund_percentage: u8,
+    pub oracle_program_id: Pubkey,
+    pub rarity_feeds: Vec<Pubkey>,
+    pub max_feed_staleness: u32,
+    pub rarity_boosts: [u8; 5], //Percent of reward weight added per rarity, indexed like the Rarity enum.
+    pub reroll_fee: u64,
+    pub max_rarity_rerolls: u8,
+    pub custom_classes: Vec<CustomClass>,
+    pub referral_share: u8,
+    pub max_referral_reward: u64,
+    pub redeem_fee_curve: RedeemFeeCurve,
+    pub redeem_fee_percent: u8,
+    pub redeem_fee_duration: u32,
+    pub liquidity_reserve_share: u8,
+    pub instant_exit_discount: u8,
+}
+impl InglConfig {
+    pub fn validate(self) -> Result<Self, ProgramError> {
+        if self.validation_phrase != INGL_CONFIG_VAL_PHRASE {
+            Err(InglError::InvalidValPhrase.utilize(Some("Ingl Config")))?
+        }
+        Ok(self)
+    }
+    pub fn decode(account: &AccountInfo) -> Result<Self, ProgramError> {
+        assert_program_owned(account).unwrap();
+        let a: Self = try_from_slice_unchecked(&account.data.borrow()).unwrap();
+        a.validate()
+    }
+    pub fn space() -> usize {
+        4 + 32 + 4 * 6 + 1 + 1 + 32
+            + (4 + 32 * MAX_RARITY_FEEDS)
+            + 4 + 5 + 8 + 1
+            + (4 + (4 + 4 + MAX_CLASS_NAME_LENGTH) * MAX_CUSTOM_CLASSES)
+            + 1 + 8 + 2 + 1 + 4 + 1 + 1
+    }
+    pub fn apply(&mut self, update: ConfigUpdate) -> Result<(), ProgramError> {
+        match update {
+            ConfigUpdate::GovernanceVotingPeriod(period) => self.governance_voting_period = period,
+            ConfigUpdate::GovernanceTimelock(timelock) => self.governance_timelock = timelock,
+            ConfigUpdate::GovernanceQuorum(quorum) => self.governance_quorum = quorum,
+            ConfigUpdate::Authority(authority) => self.authority = authority,
+            ConfigUpdate::CouncilVetoThreshold(threshold) => self.council_veto_threshold = threshold,
+            ConfigUpdate::CouncilPauseThreshold(threshold) => self.council_pause_threshold = threshold,
+            ConfigUpdate::RegistrationRefundPercentage(percentage) => self.registration_refund_percentage = percentage,
+            ConfigUpdate::OracleProgram(oracle_program_id) => self.oracle_program_id = oracle_program_id,
+            ConfigUpdate::RarityFeeds(rarity_feeds) => self.rarity_feeds = rarity_feeds,
+            ConfigUpdate::MaxFeedStaleness(staleness) => self.max_feed_staleness = staleness,
+            ConfigUpdate::RarityBoosts(boosts) => self.rarity_boosts = boosts,
+            ConfigUpdate::RerollFee(fee) => self.reroll_fee = fee,
+            ConfigUpdate::MaxRarityRerolls(rerolls) => self.max_rarity_rerolls = rerolls,
+            ConfigUpdate::ReferralShare(share) => self.referral_share = share,
+            ConfigUpdate::MaxReferralReward(lamports) => self.max_referral_reward = lamports,
+            ConfigUpdate::RedeemFeeCurve(curve) => self.redeem_fee_curve = curve,
+            ConfigUpdate::RedeemFeePercent(percent) => self.redeem_fee_percent = percent,
+            ConfigUpdate::RedeemFeeDuration(duration) => self.redeem_fee_duration = duration,
+            ConfigUpdate::LiquidityReserveShare(share) => self.liquidity_reserve_share = share,
+            ConfigUpdate::InstantExitDiscount(discount) => self.instant_exit_discount = discount,
+            ConfigUpdate::Paused(is_paused) => self.is_paused = is_paused,
+            ConfigUpdate::AddCustomClass(custom_class) => {
+                let class_count = self.custom_classes.len();
+                match self.custom_classes.iter_mut().find(|listed| listed.sol == custom_class.sol) {
+                    Some(listed) => listed.name = custom_class.name,
+                    None if class_count < MAX_CUSTOM_CLASSES => self.custom_classes.push(custom_class),
+                    None => Err(InglError::BeyondBounds.utilize(Some("custom class count")))?,
+                }
+            }
+            ConfigUpdate::RemoveCustomClass(sol) => self.custom_classes.retain(|listed| listed.sol != sol),
+        }
+        Ok(())
+    }
+    /// Fee charged for redeeming a gem of `class_lamports` `elapsed` seconds after its creation. It starts at
+    /// `redeem_fee_percent` of the class lamports and decreases along the curve to nothing after `redeem_fee_duration`.
+    pub fn get_redeem_fee(&self, class_lamports: u64, elapsed: u32) -> u64 {
+        if elapsed >= self.redeem_fee_duration {
+            return 0;
+        }
+        let max_fee = class_lamports as u128 * self.redeem_fee_percent as u128 / 100;
+        let (duration, elapsed) = (self.redeem_fee_duration as u128, elapsed as u128);
+        (match self.redeem_fee_curve {
+            RedeemFeeCurve::Linear => max_fee * (duration - elapsed) / duration,
+            RedeemFeeCurve::QuarterCircle => max_fee * integer_sqrt(duration * duration - elapsed * elapsed) / duration,
+            RedeemFeeCurve::Step { steps } => {
+                let steps = steps as u128;
+                max_fee * (steps - elapsed * steps / duration) / steps
+            }
+        }) as u64
+    }
+    /// Fixed classes can always be minted, custom ones only while listed in the class catalogue.
+    pub fn assert_class_listed(&self, class: Class) -> ProgramResult {
+        if let Class::Custom { sol } = class {
+            if !self.custom_classes.iter().any(|listed| listed.sol == sol) {
+                Err(InglError::UnlistedClass.utilize(Some(&sol.to_string())))?
+            }
+        }
+        Ok(())
+    }
+    /// Name the gems of a class are minted under, ahead of their numeration.
+    pub fn get_gem_name(&self, class: Class) -> String {
+        match class {
+            Class::Custom { sol } => match self.custom_classes.iter().find(|listed| listed.sol == sol) {
+                Some(listed) => listed.name.clone(),
+                None => String::from("Ingl Gem"),
+            },
+            _ => String::from("Ingl Gem"),
+        }
+    }
+    /// Reward weight a gem gets on top of its class lamports for its rarity. Unrevealed gems get no boost.
+    pub fn get_reward_boost(&self, class: Class, rarity: Option<Rarity>) -> u64 {
+        match rarity {
+            Some(rarity) => class.get_class_lamports() * self.rarity_boosts[rarity as usize] as u64 / 100,
+            None => 0,
+        }
+    }
+    pub fn assert_not_paused(&self) -> ProgramResult {
+        if self.is_paused {
+            Err(InglError::ProgramPaused.utilize(None))?
+        }
+        Ok(())
+    }
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Clone)]
+pub struct UriOverride {
+    pub class: Class,
+    pub rarity: Option<Rarity>,
+    pub uri: String,
+}
+
+/// Artwork uris of the gems, changeable without a program upgrade. Entries override the base uri for a single
+/// (class, rarity) pair, unrevealed gems being keyed with a None rarity.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct MetadataUriRegistry {
+    pub validation_phrase: u32,
+    pub base_uri: Option<String>,
+    pub entries: Vec<UriOverride>,
+}
+impl MetadataUriRegistry {
+    pub fn new() -> Self {
+        Self {
+            validation_phrase: METADATA_URI_REGISTRY_VAL_PHRASE,
+            base_uri: None,
+            entries: Vec::new(),
+        }
+    }
+    pub fn space() -> usize {
+        4 + (1 + 4 + MAX_BASE_URI_LENGTH) + 4 + MAX_URI_REGISTRY_ENTRIES * ((1 + 4) + 2 + 4 + MAX_URI_LENGTH)
+    }
+    pub fn validate(self) -> Result<Self, ProgramError> {
+        if self.validation_phrase != METADATA_URI_REGISTRY_VAL_PHRASE {
+            Err(InglError::InvalidValPhrase.utilize(Some("Metadata Uri Registry")))?
+        }
+        Ok(self)
+    }
+    pub fn decode(account: &AccountInfo) -> Result<Self, ProgramError> {
+        assert_program_owned(account).unwrap();
+        let a: Self = try_from_slice_unchecked(&account.data.borrow()).unwrap();
+        a.validate()
+    }
+    pub fn apply(&mut self, update: UriRegistryUpdate) -> Result<(), ProgramError> {
+        match update {
+            UriRegistryUpdate::BaseUri(base_uri) => self.base_uri = base_uri,
+            UriRegistryUpdate::Entry { class, rarity, uri: None } => self.entries.retain(|entry| entry.class != class || entry.rarity != rarity),
+            UriRegistryUpdate::Entry { class, rarity, uri: Some(uri) } => {
+                let entry_count = self.entries.len();
+                match self.entries.iter_mut().find(|entry| entry.class == class && entry.rarity == rarity) {
+                    Some(entry) => entry.uri = uri,
+                    None if entry_count >= MAX_URI_REGISTRY_ENTRIES => Err(InglError::BeyondBounds.utilize(Some("uri registry entries")))?,
+                    None => self.entries.push(UriOverride { class, rarity, uri }),
+                }
+            }
+        }
+        Ok(())
+    }
+}
+impl Default for MetadataUriRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Clone, Copy)]
+pub struct MintPhase {
+    pub start: u32,
+    pub end: u32,
+}
+impl MintPhase {
+    pub fn is_valid(&self) -> bool {
+        self.start < self.end
+    }
+    pub fn is_active(&self, now: u32) -> bool {
+        self.start <= now && now < self.end
+    }
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Clone, Copy)]
+pub struct ClassCap {
+    pub class: Class,
+    pub max_supply: u32,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Clone, Copy)]
+pub struct ClassSupply {
+    pub class: Class,
+    pub max_supply: u32,
+    pub minted: u32,
+}
+
+/// Launch controls of gem mints, set by the config authority. Classes without a supply entry are uncapped,
+/// and minting is unrestricted until the schedule is first set.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct MintSchedule {
+    pub validation_phrase: u32,
+    pub class_supplies: Vec<ClassSupply>,
+    pub allowlist_phase: Option<MintPhase>,
+    pub public_phase: Option<MintPhase>,
+    pub wallet_cap: Option<u32>, //Gems a single wallet can mint across all classes and phases.
+    pub allowlist_root: Option<[u8; 32]>, //Root of the (wallet, class, max_count) tree built by allowlist::build_allowlist_from_csv.
+}
+impl MintSchedule {
+    pub fn space() -> usize {
+        4 + 4 + MAX_SCHEDULED_CLASSES * ((1 + 4) + 4 + 4) + 2 * (1 + 4 + 4) + (1 + 4) + (1 + 32)
+    }
+    pub fn validate(self) -> Result<Self, ProgramError> {
+        if self.validation_phrase != MINT_SCHEDULE_VAL_PHRASE {
+            Err(InglError::InvalidValPhrase.utilize(Some("Mint Schedule")))?
+        }
+        Ok(self)
+    }
+    pub fn decode(account: &AccountInfo) -> Result<Self, ProgramError> {
+        assert_program_owned(account).unwrap();
+        let a: Self = try_from_slice_unchecked(&account.data.borrow()).unwrap();
+        a.validate()
+    }
+    /// Replaces the supply caps, carrying over the mint counters of classes that stay capped.
+    pub fn set_class_caps(&mut self, class_caps: Vec<ClassCap>) {
+        self.class_supplies = class_caps
+            .into_iter()
+            .map(|cap| ClassSupply {
+                class: cap.class,
+                max_supply: cap.max_supply,
+                minted: self
+                    .class_supplies
+                    .iter()
+                    .find(|supply| supply.class == cap.class)
+                    .map_or(0, |supply| supply.minted),
+            })
+            .collect();
+    }
+    pub fn record_mint(&mut self, class: Class) -> ProgramResult {
+        if let Some(supply) = self.class_supplies.iter_mut().find(|supply| supply.class == class) {
+            if supply.minted >= supply.max_supply {
+                Err(InglError::BeyondBounds.utilize(Some("class supply")))?
+            }
+            supply.minted += 1;
+        }
+        Ok(())
+    }
+}
+
+/// Gems minted by a wallet while the mint schedule is set, checked against the schedule's wallet cap
+/// and, for allowlist mints, against the wallet's allowlisted max_count.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct MintWalletRecord {
+    pub validation_phrase: u32,
+    pub minted: u32,
+    pub allowlist_minted: u32,
+}
+impl MintWalletRecord {
+    pub fn space() -> usize {
+        4 + 4 + 4
+    }
+    pub fn validate(self) -> Result<Self, ProgramError> {
+        if self.validation_phrase != MINT_WALLET_RECORD_VAL_PHRASE {
+            Err(InglError::InvalidValPhrase.utilize(Some("Mint Wallet Record")))?
+        }
+        Ok(self)
+    }
+    pub fn decode(account: &AccountInfo) -> Result<Self, ProgramError> {
+        assert_program_owned(account).unwrap();
+        let a: Self = try_from_slice_unchecked(&account.data.borrow()).unwrap();
+        a.validate()
+    }
+}
+
+/// Mint volume brought in by a referrer, and the share of the redeem fees of the referred gems owed to them.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct Referrer {
+    pub validation_phrase: u32,
+    pub wallet: Pubkey,
+    pub referred_gems: u32,
+    pub referred_volume: u64,
+    pub accrued_rewards: u64,
+    pub claimed_rewards: u64,
+}
+impl Referrer {
+    pub fn space() -> usize {
+        4 + 32 + 4 + 8 + 8 + 8
+    }
+    pub fn validate(self) -> Result<Self, ProgramError> {
+        if self.validation_phrase != REFERRER_VAL_PHRASE {
+            Err(InglError::InvalidValPhrase.utilize(Some("Referrer")))?
+        }
+        Ok(self)
+    }
+    pub fn decode(account: &AccountInfo) -> Result<Self, ProgramError> {
+        assert_program_owned(account).unwrap();
+        let a: Self = try_from_slice_unchecked(&account.data.borrow()).unwrap();
+        a.validate()
+    }
+    /// Credits the referrer's share of a treasury fee, up to the config's lifetime cap. Returns the credited lamports.
+    pub fn accrue(&mut self, config: &InglConfig, treasury_fee: u64) -> u64 {
+        let share = (treasury_fee as u128 * config.referral_share as u128 / 100) as u64;
+        let credited = share.min(config.max_referral_reward.saturating_sub(self.accrued_rewards));
+        self.accrued_rewards += credited;
+        credited
+    }
+}
+
+/// Running totals of the lamports paid into the program's treasuries by source, and of those spent from each treasury.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct TreasuryLedger {
+    pub validation_phrase: u32,
+    pub redeem_fees: u64, //Into the ingl treasury and the mint authority.
+    pub reward_share: u64, //Treasury share of the vote rewards, into the rewards treasury.
+    pub team_share: u64, //Team share of the vote rewards, into the mint authority.
+    pub registration_fees: u64, //Validator registration fees net of refunds, into the mint authority.
+    pub reroll_fees: u64, //Into the ingl treasury.
+    pub ingl_treasury_spent: u64,
+    pub rewards_treasury_spent: u64,
+    pub mint_authority_spent: u64,
+}
+impl TreasuryLedger {
+    pub fn space() -> usize {
+        4 + 8 * 8
+    }
+    pub fn validate(self) -> Result<Self, ProgramError> {
+        if self.validation_phrase != TREASURY_LEDGER_VAL_PHRASE {
+            Err(InglError::InvalidValPhrase.utilize(Some("TreasuryLedger")))?
+        }
+        Ok(self)
+    }
+    pub fn decode(account: &AccountInfo) -> Result<Self, ProgramError> {
+        assert_program_owned(account).unwrap();
+        let a: Self = try_from_slice_unchecked(&account.data.borrow()).unwrap();
+        a.validate()
+    }
+    pub fn record_spend(&mut self, treasury: TreasuryAccount, lamports: u64) {
+        let spent = match treasury {
+            TreasuryAccount::InglTreasury => &mut self.ingl_treasury_spent,
+            TreasuryAccount::RewardsTreasury => &mut self.rewards_treasury_spent,
+            TreasuryAccount::MintAuthority => &mut self.mint_authority_spent,
+        };
+        *spent = spent.saturating_add(lamports);
+    }
+}
+
+/// Change to the metadata uri registry. An entry set to None falls back to the base uri again.
+#[derive(BorshDeserialize, BorshSerialize, Clone)]
+pub enum UriRegistryUpdate {
+    BaseUri(Option<String>),
+    Entry {
+        class: Class,
+        rarity: Option<Rarity>,
+        uri: Option<String>,
+    },
+}
+impl UriRegistryUpdate {
+    pub fn is_valid(&self) -> bool {
+        match self {
+            Self::BaseUri(base_uri) => !matches!(base_uri, Some(base_uri) if base_uri.len() > MAX_BASE_URI_LENGTH),
+            Self::Entry { uri, .. } => !matches!(uri, Some(uri) if uri.len() > MAX_URI_LENGTH),
+        }
+    }
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Clone)]
+pub enum ConfigUpdate {
+    GovernanceVotingPeriod(u32),
+    GovernanceTimelock(u32),
+    GovernanceQuorum(u32),
+    Authority(Pubkey),
+    CouncilVetoThreshold(u32),
+    CouncilPauseThreshold(u32),
+    RegistrationRefundPercentage(u8),
+    OracleProgram(Pubkey),
+    RarityFeeds(Vec<Pubkey>),
+    MaxFeedStaleness(u32),
+    RarityBoosts([u8; 5]),
+    RerollFee(u64),
+    MaxRarityRerolls(u8),
+    AddCustomClass(CustomClass),
+    RemoveCustomClass(u32),
+    ReferralShare(u8),
+    MaxReferralReward(u64),
+    RedeemFeeCurve(RedeemFeeCurve),
+    RedeemFeePercent(u8),
+    RedeemFeeDuration(u32),
+    LiquidityReserveShare(u8),
+    InstantExitDiscount(u8),
+    Paused(bool),
+}
+
+/// Shape of the early redemption fee over the fee duration, as a fraction of the initial fee at elapsed time t of the duration.
+#[derive(BorshDeserialize, BorshSerialize, Clone, Copy)]
+pub enum RedeemFeeCurve {
+    Linear,        // 1 - t
+    QuarterCircle, // sqrt(1 - t^2)
+    Step { steps: u8 }, // Drops by 1/steps at each of the steps equal intervals.
+}
+impl RedeemFeeCurve {
+    pub fn is_valid(&self) -> bool {
+        !matches!(self, Self::Step { steps: 0 })
+    }
+}
+
+/// Floor of the square root, keeping fee quotes exact across platforms.
+fn integer_sqrt(value: u128) -> u128 {
+    if value < 2 {
+        return value;
+    }
+    let mut root = value;
+    let mut next = value / 2 + 1;
+    while next < root {
+        root = next;
+        next = (root + value / root) / 2;
+    }
+    root
+}
+
+/// A class catalogue entry, letting governance open a new tier of `sol` whole SOL without a program upgrade.
+/// Removing an entry only stops new mints of the tier; existing gems keep their class.
+#[derive(BorshDeserialize, BorshSerialize, Clone)]
+pub struct CustomClass {
+    pub sol: u32,
+    pub name: String,
+}
+impl CustomClass {
+    pub fn is_valid(&self) -> bool {
+        (MIN_CUSTOM_CLASS_SOL..=MAX_CUSTOM_CLASS_SOL).contains(&self.sol)
+            && !self.name.is_empty()
+            && self.name.len() <= MAX_CLASS_NAME_LENGTH
+    }
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Clone, Copy)]
+pub enum TreasuryAccount {
+    InglTreasury,
+    RewardsTreasury,
+    MintAuthority, //Holds the team share and the registration fees.
+}
+impl TreasuryAccount {
+    pub fn seed(self) -> &'static str {
+        match self {
+            Self::InglTreasury => INGL_TREASURY_ACCOUNT_KEY,
+            Self::RewardsTreasury => TREASURY_ACCOUNT_KEY,
+            Self::MintAuthority => INGL_MINT_AUTHORITY_KEY,
+        }
+    }
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Clone)]
+pub enum GovernanceAction {
+    UpdateConfig(ConfigUpdate),
+    TreasurySpend {
+        treasury: TreasuryAccount,
+        recipient: Pubkey,
+        lamports: u64,
+    },
+    UpdateUriRegistry(UriRegistryUpdate),
+    RotateValidator {
+        vote_account: Pubkey,
+        new_validator_id: Pubkey,
+    },
+    SetRarityTable {
+        class: Class,
+        weights: [u16; 5],
+    },
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct GovernanceProposal {
+    pub validation_phrase: u32,
+    pub numeration: u32,
+    pub proposer: Pubkey,
+    pub action: GovernanceAction,
+    pub date_created: u32,
+    pub voting_end: u32,
+    pub executable_date: u32,
+    pub votes_for: u32,
+    pub votes_against: u32,
+    pub council_vetoes: u32,
+    pub date_executed: Option<u32>,
+}
+impl GovernanceProposal {
+    pub fn validate(self) -> Result<Self, ProgramError> {
+        if self.validation_phrase != GOVERNANCE_PROPOSAL_VAL_PHRASE {
+            Err(InglError::InvalidValPhrase.utilize(Some("Governance Proposal")))?
+        }
+        Ok(self)
+    }
+    pub fn decode(account: &AccountInfo) -> Result<Self, ProgramError> {
+        assert_program_owned(account).unwrap();
+        let a: Self = try_from_slice_unchecked(&account.data.borrow()).unwrap();
+        a.validate()
+    }
+    pub fn is_passed(&self, quorum: u32) -> bool {
+        self.votes_for > self.votes_against && self.votes_for.saturating_add(self.votes_against) >= quorum
+    }
+}
+
 pub struct VoteState {}
 impl VoteState {
     pub fn space() -> usize {
@@ -299,4 +1133,77 @@ impl VoteState {
     pub fn min_lamports() -> u64 {
         Rent::get().unwrap().minimum_balance(3731)
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn full_gem_fits_its_space() {
+        let mut gem = GemAccountV0_0_1 {
+            struct_id: GemAccountVersions::GemAccountV0_0_1,
+            validation_phrase: GEM_ACCOUNT_VAL_PHRASE,
+            date_created: 0,
+            class: Class::Custom { sol: 0 },
+            redeemable_date: 0,
+            numeration: 0,
+            rarity: Some(Rarity::Mythic),
+            funds_location: FundsLocation::VoteAccount { vote_account_id: Pubkey::default() },
+            rarity_seed_time: Some(0),
+            date_allocated: Some(0),
+            last_voted_proposal: Some(Pubkey::default()),
+            last_withdrawal_epoch: Some(0),
+            last_delegation_epoch: Some(0),
+            all_withdraws: Vec::new(),
+            all_votes: Vec::new(),
+            voting_delegate: Some(VotingDelegate { owner: Pubkey::default(), delegate: Pubkey::default() }),
+            rarity_reveal_slot: Some(0),
+            reward_boost: Some(0),
+            rarity_rerolls: Some(0),
+            referrer: Some(Pubkey::default()),
+            queued_redemption: Some(QueuedRedemption { position: 0, requested_epoch: 0, unwinding_vote_account: Some(Pubkey::default()) }),
+            ballot_voter: Some(Pubkey::default()),
+            governance_vote_end: Some(0),
+        };
+        for _ in 0..MAX_GEM_WITHDRAW_HISTORY + 1 {
+            gem.record_withdraw(0);
+        }
+        for _ in 0..MAX_GEM_VOTE_HISTORY + 1 {
+            gem.record_vote(ValidatorVote { validation_phrase: VALIDATOR_VOTE_VAL_PHRASE, proposal_id: Pubkey::default(), validator_index: 0 });
+        }
+        assert_eq!(gem.try_to_vec().unwrap().len(), GemAccountV0_0_1::space());
+        assert!(GemAccountV0_0_1::space() <= 500);
+    }
+
+    #[test]
+    fn full_config_fits_its_space() {
+        let config = InglConfig {
+            validation_phrase: INGL_CONFIG_VAL_PHRASE,
+            authority: Pubkey::default(),
+            governance_voting_period: 0,
+            governance_timelock: 0,
+            governance_quorum: 0,
+            governance_proposal_numeration: 0,
+            council_veto_threshold: 0,
+            council_pause_threshold: 0,
+            is_paused: false,
+            registration_refund_percentage: 0,
+            oracle_program_id: Pubkey::default(),
+            rarity_feeds: vec![Pubkey::default(); MAX_RARITY_FEEDS],
+            max_feed_staleness: 0,
+            rarity_boosts: [0; 5],
+            reroll_fee: 0,
+            max_rarity_rerolls: 0,
+            custom_classes: (0..MAX_CUSTOM_CLASSES).map(|_| CustomClass { sol: 0, name: "x".repeat(MAX_CLASS_NAME_LENGTH) }).collect(),
+            referral_share: 0,
+            max_referral_reward: 0,
+            redeem_fee_curve: RedeemFeeCurve::Step { steps: 1 },
+            redeem_fee_percent: 0,
+            redeem_fee_duration: 0,
+            liquidity_reserve_share: 0,
+            instant_exit_discount: 0,
+        };
+        assert_eq!(config.try_to_vec().unwrap().len(), InglConfig::space());
+    }
+}
@@ -1,6 +1,7 @@
 use solana_program::{
     pubkey::Pubkey,
     program_error::ProgramError, account_info::AccountInfo, entrypoint::ProgramResult,
+    bpf_loader_upgradeable::{self, UpgradeableLoaderState}, program_utils::limited_deserialize,
 };
 use crate::{
     error::InglError,
@@ -38,4 +39,22 @@ pub fn assert_pda_input(seeds : &[&[u8]], account_info: &AccountInfo) -> (Pubkey
     assert_pubkeys_exactitude(&pda_key, account_info.key).unwrap();
     (pda_key, pda_bump)
 
-}
\ No newline at end of file
+}
+
+/// Checks that the signer is the program's upgrade authority, read from the program's ProgramData account.
+pub fn assert_upgrade_authority(program_data_account_info: &AccountInfo, authority_account_info: &AccountInfo) -> ProgramResult {
+    assert_is_signer(authority_account_info)?;
+    let (program_data_key, _program_data_bump) = Pubkey::find_program_address(&[constants::id().as_ref()], &bpf_loader_upgradeable::id());
+    assert_pubkeys_exactitude(&program_data_key, program_data_account_info.key)?;
+    assert_owned_by(program_data_account_info, &bpf_loader_upgradeable::id())?;
+
+    let metadata_len = UpgradeableLoaderState::programdata_data_offset().map_err(|_| ProgramError::InvalidAccountData)?;
+    let data = program_data_account_info.data.borrow();
+    let metadata = data.get(..metadata_len).ok_or(ProgramError::InvalidAccountData)?;
+    match limited_deserialize(metadata, metadata_len as u64) {
+        Ok(UpgradeableLoaderState::ProgramData { upgrade_authority_address: Some(upgrade_authority), .. }) => {
+            assert_pubkeys_exactitude(&upgrade_authority, authority_account_info.key)
+        }
+        _ => Err(InglError::Unauthorized.utilize(Some("program upgrade authority")))?,
+    }
+}